@@ -0,0 +1,59 @@
+//! Weighted voting: voters seal their ballots, a coordinator tallies them homomorphically with
+//! the voters' weights, and only the tally is opened once the timelock expires.
+//!
+//! Run with `cargo run --release --example aggregation`.
+use lhtlp::{LhtlpError, LhtlpIteratorExt, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// A voter with a public voting weight, choosing one of the candidates.
+pub struct Ballot {
+    pub weight: u64,
+    pub choice: usize,
+}
+
+/// Tally `ballots` over `candidates` options, returning the weighted number of votes of each.
+///
+/// Every voter seals one puzzle per candidate, embedding 1 for their choice and 0 elsewhere. The
+/// coordinator only sees puzzles: it scales each by the weight of its voter and sums them.
+pub fn weighted_tally(difficulty: u64, candidates: usize, ballots: &[Ballot]) -> Result<Vec<BigUint>, LhtlpError> {
+    let lhtlp = LHTLP::setup(64, difficulty);
+    let sealed: Vec<Vec<Puzzle>> = ballots
+        .iter()
+        .map(|ballot| (0..candidates).map(|c| lhtlp.generate(u64::from(c == ballot.choice))).collect())
+        .collect();
+
+    // The first candidate with scalar_mul and evaluate, the others with aggregate_weighted.
+    let first: Vec<Puzzle> = sealed
+        .iter()
+        .zip(ballots)
+        .map(|(puzzles, ballot)| lhtlp.scalar_mul(&puzzles[0], &BigUint::from(ballot.weight)))
+        .collect();
+    let mut tallies = vec![lhtlp.evaluate(first)];
+    let weights: Vec<BigUint> = ballots.iter().map(|ballot| BigUint::from(ballot.weight)).collect();
+    for c in 1..candidates {
+        let column = sealed.iter().map(|puzzles| &puzzles[c]);
+        // Without ballots there is nothing to weigh: the tally is a puzzle of zero.
+        let tally = match column.aggregate_weighted(&lhtlp, &weights) {
+            Err(LhtlpError::EmptyInput) => lhtlp.generate(0),
+            tally => tally?,
+        };
+        tallies.push(tally);
+    }
+
+    Ok(tallies.into_iter().map(|tally| lhtlp.solve(tally)).collect())
+}
+
+fn main() {
+    let ballots = [
+        Ballot { weight: 10, choice: 0 },
+        Ballot { weight: 3, choice: 1 },
+        Ballot { weight: 5, choice: 2 },
+        Ballot { weight: 4, choice: 1 },
+        Ballot { weight: 1, choice: 1 },
+    ];
+    let tally = weighted_tally(100_000, 3, &ballots).unwrap();
+    for (candidate, votes) in tally.iter().enumerate() {
+        println!("candidate {}: {} votes", candidate, votes);
+    }
+    assert!(tally == [10u32, 8, 5].map(BigUint::from));
+}
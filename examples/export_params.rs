@@ -0,0 +1,52 @@
+//! Two processes: one exports its public parameters and a puzzle as text, the other imports them,
+//! seals its own secret and adds it to the imported puzzle.
+//!
+//! Run with `cargo run --release --example export_params`.
+use lhtlp::{Puzzle, PublicParams, LHTLP};
+use num_bigint::BigUint;
+
+/// The message sent from the first process to the second, one hex line each for the
+/// difficulty, `n`, `g`, `h` and the puzzle bytes.
+pub fn export(lhtlp: &LHTLP, puzzle: &Puzzle) -> String {
+    let params = lhtlp.public_params();
+    let mut lines: Vec<String> = [lhtlp.difficulty(), &params.n, &params.g, &params.h]
+        .iter()
+        .map(|value| value.to_str_radix(16))
+        .collect();
+    lines.push(puzzle.to_bytes().iter().map(|byte| format!("{:02x}", byte)).collect());
+    lines.join("\n")
+}
+
+/// Parse a message produced by `export`.
+pub fn import(message: &str) -> (LHTLP, Puzzle) {
+    let lines: Vec<&str> = message.lines().collect();
+    let [difficulty, n, g, h, puzzle] = <[&str; 5]>::try_from(lines).expect("five lines");
+    let [difficulty, n, g, h] =
+        [difficulty, n, g, h].map(|line| BigUint::parse_bytes(line.as_bytes(), 16).expect("hex value"));
+    let lhtlp = LHTLP::from_public_params(PublicParams { n, g, h }, difficulty).expect("valid parameters");
+
+    let bytes: Vec<u8> = (0..puzzle.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&puzzle[i..i + 2], 16).expect("hex byte"))
+        .collect();
+    let puzzle = Puzzle::from_bytes(&bytes).expect("valid puzzle");
+    (lhtlp, puzzle)
+}
+
+/// Seal `first` in one process, add `second` in another from the exported message, and open the
+/// result back in the first process.
+pub fn export_and_import(difficulty: u64, first: u64, second: u64) -> BigUint {
+    let exporter = LHTLP::setup(64, difficulty);
+    let message = export(&exporter, &exporter.generate(first));
+
+    let (importer, puzzle) = import(&message);
+    let sum = importer.add(&puzzle, &importer.generate(second));
+
+    exporter.solve(Puzzle::from_bytes(&sum.to_bytes()).expect("valid puzzle"))
+}
+
+fn main() {
+    let opened = export_and_import(100_000, 40, 2);
+    println!("opened across processes: {}", opened);
+    assert!(opened == BigUint::from(42u32));
+}
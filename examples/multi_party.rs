@@ -0,0 +1,29 @@
+//! Several parties: each seals a secret, the puzzles are aggregated and only the sum is opened.
+//!
+//! Run with `cargo run --release --example multi_party`.
+use lhtlp::{Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// Seal each of `secrets`, small and wide alike, evaluate their sum and open it.
+pub fn aggregate_and_open(difficulty: u64, secrets: &[BigUint]) -> BigUint {
+    let lhtlp = LHTLP::setup(64, difficulty);
+    let puzzles: Vec<Puzzle> = secrets
+        .iter()
+        .map(|secret| lhtlp.generate_biguint(secret).expect("secret fits the plaintext space"))
+        .collect();
+    let sum = lhtlp.evaluate(puzzles);
+    lhtlp.solve(sum)
+}
+
+fn main() {
+    let secrets = [
+        BigUint::from(7u32),
+        BigUint::from(u64::MAX),
+        BigUint::from(1u32) << 100u32,
+        BigUint::from(0u32),
+    ];
+    let expected: BigUint = secrets.iter().sum();
+    let opened = aggregate_and_open(100_000, &secrets);
+    println!("sum of {} secrets: {}", secrets.len(), opened);
+    assert!(opened == expected);
+}
@@ -0,0 +1,19 @@
+//! Single party: seal a secret in a puzzle and force it open by sequential squaring.
+//!
+//! Run with `cargo run --release --example seal_and_open`.
+use lhtlp::LHTLP;
+use num_bigint::BigUint;
+
+/// Seal `secret` under an instance of the given difficulty and force the puzzle open.
+pub fn seal_and_open(difficulty: u64, secret: u64) -> BigUint {
+    let lhtlp = LHTLP::setup(64, difficulty);
+    let puzzle = lhtlp.generate(secret);
+    lhtlp.solve(puzzle)
+}
+
+fn main() {
+    let secret = 42;
+    let opened = seal_and_open(100_000, secret);
+    println!("sealed {}, forced open {}", secret, opened);
+    assert!(opened == BigUint::from(secret));
+}
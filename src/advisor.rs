@@ -0,0 +1,232 @@
+use crate::{LhtlpError, PartialSolution, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::time::{Duration, Instant};
+
+/// Squarings per second of the static benchmark: a difficulty of 100000000 takes roughly 5
+/// seconds with a 128-bit modulus.
+const STATIC_SQUARINGS_PER_SECOND: f64 = 20_000_000.0;
+const STATIC_MODULUS_BITS: u64 = 128;
+
+/// Number of squarings between two progress reports of `solve_with_progress`.
+const PROGRESS_INTERVAL: u64 = 1024;
+
+/// A completed solve: its difficulty, the time it took and the size of the modulus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveSample {
+    pub difficulty: u64,
+    pub wall_clock: Duration,
+    pub modulus_bits: u64,
+}
+
+impl SolveSample {
+    /// Squarings per second of this solve, scaled to a modulus of `modulus_bits` bits.
+    ///
+    /// Squaring cost is taken to grow with the square of the modulus size.
+    fn rate_at(&self, modulus_bits: u64) -> f64 {
+        let rate = self.difficulty as f64 / self.wall_clock.as_secs_f64();
+        let scale = self.modulus_bits as f64 / modulus_bits as f64;
+        rate * scale * scale
+    }
+}
+
+/// Measurements of one solve, returned by `LHTLP::solve_with_stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveStats {
+    pub squarings_performed: u64,
+    pub elapsed: Duration,
+    /// `squarings_performed / elapsed`, zero when no squaring was performed.
+    pub squarings_per_second: f64,
+}
+
+/// Recommends difficulties from the solve rates observed on real hardware.
+///
+/// Solve rates drift as hardware gets faster, so a difficulty picked once silently buys less
+/// delay over time. The advisor keeps a history of completed solves and fits the median rate,
+/// which a few outliers cannot skew. With fewer than two samples it falls back to the static
+/// benchmark of `setup`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DifficultyAdvisor {
+    samples: Vec<SolveSample>,
+}
+
+impl DifficultyAdvisor {
+    pub fn new() -> DifficultyAdvisor {
+        DifficultyAdvisor::default()
+    }
+
+    /// Add a completed solve to the history. Samples with a zero difficulty or duration are ignored.
+    pub fn record(&mut self, sample: SolveSample) {
+        if sample.difficulty > 0 && !sample.wall_clock.is_zero() && sample.modulus_bits > 0 {
+            self.samples.push(sample);
+        }
+    }
+
+    pub fn samples(&self) -> &[SolveSample] {
+        &self.samples
+    }
+
+    /// Median squarings per second for a modulus of `modulus_bits` bits.
+    pub fn squarings_per_second(&self, modulus_bits: u64) -> f64 {
+        if self.samples.len() < 2 {
+            let scale = STATIC_MODULUS_BITS as f64 / modulus_bits as f64;
+            return STATIC_SQUARINGS_PER_SECOND * scale * scale;
+        }
+        let mut rates: Vec<f64> = self.samples.iter().map(|sample| sample.rate_at(modulus_bits)).collect();
+        rates.sort_by(f64::total_cmp);
+        let mid = rates.len() / 2;
+        if rates.len().is_multiple_of(2) {
+            (rates[mid - 1] + rates[mid]) / 2.0
+        } else {
+            rates[mid]
+        }
+    }
+
+    /// The difficulty expected to take `target` to solve with a modulus of `modulus_bits` bits.
+    pub fn recommended_difficulty(&self, target: Duration, modulus_bits: u64) -> BigUint {
+        let difficulty = (self.squarings_per_second(modulus_bits) * target.as_secs_f64()).round();
+        BigUint::from(difficulty.clamp(1.0, u64::MAX as f64) as u64)
+    }
+}
+
+impl LHTLP {
+    /// Open a puzzle, reporting `(squarings done, total squarings)` to `progress` every 1024 squarings
+    /// and once the squarings are complete. Fails with `LhtlpError::DifficultyTooLarge` if the
+    /// difficulty does not fit in `u64`.
+    pub fn solve_with_progress(&self, puzzle: Puzzle, mut progress: impl FnMut(u64, u64)) -> Result<BigUint, LhtlpError> {
+        let total = self.try_squarings()?;
+        let mut w = puzzle.u.clone();
+        let mut done = 0;
+        while done < total {
+            let steps = PROGRESS_INTERVAL.min(total - done);
+            w = self.square(&w, &BigUint::from(steps));
+            done += steps;
+            progress(done, total);
+        }
+        self.try_decode(&puzzle.v, &w)
+    }
+
+    /// Open a puzzle like `solve_checked`, measuring the solve.
+    ///
+    /// The puzzle is validated and the policy of the instance enforced exactly as in
+    /// `solve_checked`, whose squaring loop is timed and counted. The clock is read once before
+    /// and once after the squarings, so the measurement adds no work to the loop.
+    pub fn solve_with_stats(&self, puzzle: Puzzle) -> Result<(BigUint, SolveStats), LhtlpError> {
+        self.verify_puzzle(&puzzle)?;
+        let mut squarings_performed = 0;
+        let start = Instant::now();
+        let solution = self.solve_with_policy_counted(PartialSolution::new(puzzle), self.policy(), &mut squarings_performed)?;
+        let elapsed = start.elapsed();
+        let squarings_per_second = match squarings_performed {
+            0 => 0.0,
+            squarings => squarings as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE),
+        };
+        Ok((solution, SolveStats { squarings_performed, elapsed, squarings_per_second }))
+    }
+
+    /// Like `solve_with_progress`, recording the time of the solve in `advisor`.
+    pub fn solve_with_progress_recorded(
+        &self,
+        puzzle: Puzzle,
+        advisor: &mut DifficultyAdvisor,
+        progress: impl FnMut(u64, u64),
+    ) -> Result<BigUint, LhtlpError> {
+        let start = Instant::now();
+        let solution = self.solve_with_progress(puzzle, progress)?;
+        advisor.record(SolveSample {
+            difficulty: self.try_squarings()?,
+            wall_clock: start.elapsed(),
+            modulus_bits: self.n.bits(),
+        });
+        Ok(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    fn sample(difficulty: u64, millis: u64, modulus_bits: u64) -> SolveSample {
+        SolveSample { difficulty, wall_clock: Duration::from_millis(millis), modulus_bits }
+    }
+
+    fn within(value: BigUint, expected: u64, tolerance: f64) -> bool {
+        let value: f64 = value.to_string().parse().unwrap();
+        (value - expected as f64).abs() <= expected as f64 * tolerance
+    }
+
+    #[test]
+    fn median_rate() {
+        let mut advisor = DifficultyAdvisor::new();
+        // One million squarings per second at 2048 bits, plus an outlier.
+        for millis in [990, 1000, 1010, 1000] {
+            advisor.record(sample(1_000_000, millis, 2048));
+        }
+        advisor.record(sample(1_000_000, 10, 2048));
+
+        assert!(within(advisor.recommended_difficulty(Duration::from_secs(60), 2048), 60_000_000, 0.01));
+        // Twice the modulus size costs four times as much per squaring.
+        assert!(within(advisor.recommended_difficulty(Duration::from_secs(60), 4096), 15_000_000, 0.01));
+    }
+
+    #[test]
+    fn static_fallback() {
+        let mut advisor = DifficultyAdvisor::new();
+        let fallback = advisor.recommended_difficulty(Duration::from_secs(5), 128);
+        assert!(fallback == BigUint::from(100_000_000u64));
+
+        advisor.record(sample(1_000, 1_000, 128));
+        assert!(advisor.recommended_difficulty(Duration::from_secs(5), 128) == fallback);
+        advisor.record(sample(1_000, 1_000, 128));
+        assert!(advisor.recommended_difficulty(Duration::from_secs(5), 128) == BigUint::from(5_000u32));
+    }
+
+    #[test]
+    fn progress_hook_records_samples() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut advisor = DifficultyAdvisor::new();
+        let mut reports = Vec::new();
+        let solution = lhtlp.solve_with_progress_recorded(lhtlp.generate(42), &mut advisor, |done, total| {
+            reports.push((done, total))
+        });
+
+        assert!(solution == Ok(BigUint::from(42u32)));
+        assert!(reports == vec![(DIFFICULTY, DIFFICULTY)]);
+        assert!(advisor.samples().len() == 1);
+        assert!(advisor.samples()[0].difficulty == DIFFICULTY);
+        assert!(advisor.samples()[0].modulus_bits == lhtlp.n.bits());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn advisor_serde_roundtrip() {
+        let mut advisor = DifficultyAdvisor::new();
+        advisor.record(sample(1_000, 1_000, 128));
+        let json = serde_json::to_string(&advisor).unwrap();
+        assert!(serde_json::from_str::<DifficultyAdvisor>(&json).unwrap() == advisor);
+    }
+
+    #[test]
+    fn solve_stats() {
+        let lhtlp = LHTLP::setup(256, 100_000u64);
+        let (solution, stats) = lhtlp.solve_with_stats(lhtlp.generate(42)).unwrap();
+        assert!(solution == BigUint::from(42u32));
+        assert!(stats.squarings_performed == 100_000);
+        assert!(stats.squarings_per_second.is_finite());
+        assert!(stats.squarings_per_second > 1e3);
+
+        let trivial = LHTLP::setup(LAMBDA, 0u64);
+        let (_, stats) = trivial.solve_with_stats(trivial.generate(42)).unwrap();
+        assert!(stats.squarings_performed == 0 && stats.squarings_per_second == 0.0);
+
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.solve_with_stats(malformed) == Err(LhtlpError::InvalidPuzzle));
+        let limited = lhtlp.clone().with_policy(crate::SolvePolicy::default().with_max_difficulty(1000u64));
+        assert!(matches!(limited.solve_with_stats(limited.generate(42)), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+
+/// A puzzle together with a public upper bound on the secret it embeds.
+///
+/// Since secrets live modulo `n`, adding puzzles whose secrets sum past `n` silently wraps around.
+/// Tracking the bound lets aggregation detect this and fail with `LhtlpError::Overflow` instead.
+#[derive(Debug, Clone)]
+pub struct BoundedPuzzle<'a> {
+    lhtlp: &'a LHTLP,
+    pub puzzle: Puzzle,
+    pub bound: BigUint,
+}
+
+impl<'a> BoundedPuzzle<'a> {
+    /// Attach to a `puzzle` of `lhtlp` the public `bound` (inclusive) on its secret.
+    pub fn new(lhtlp: &'a LHTLP, puzzle: Puzzle, bound: BigUint) -> Result<BoundedPuzzle<'a>, LhtlpError> {
+        if !lhtlp.plaintext_space().fits(&bound) {
+            return Err(LhtlpError::Overflow);
+        }
+        Ok(BoundedPuzzle { lhtlp, puzzle, bound })
+    }
+
+    /// Homomorphically add two bounded puzzles, failing if the summed bound could wrap modulo `n`.
+    pub fn try_add(&self, other: &BoundedPuzzle<'a>) -> Result<BoundedPuzzle<'a>, LhtlpError> {
+        BoundedPuzzle::new(self.lhtlp, self.lhtlp.add(&self.puzzle, &other.puzzle), &self.bound + &other.bound)
+    }
+
+    /// Sum an iterator of bounded puzzles, stopping at the first overflow.
+    ///
+    /// Fails with `LhtlpError::EmptyInput` if the iterator yields nothing.
+    pub fn try_sum<I: IntoIterator<Item = BoundedPuzzle<'a>>>(iter: I) -> Result<BoundedPuzzle<'a>, LhtlpError> {
+        let mut iter = iter.into_iter();
+        let first = iter.next().ok_or(LhtlpError::EmptyInput)?;
+        iter.try_fold(first, |acc, x| acc.try_add(&x))
+    }
+}
+
+impl<'a> FromIterator<BoundedPuzzle<'a>> for Result<BoundedPuzzle<'a>, LhtlpError> {
+    fn from_iter<I: IntoIterator<Item = BoundedPuzzle<'a>>>(iter: I) -> Self {
+        BoundedPuzzle::try_sum(iter)
+    }
+}
+
+/// A puzzle tied to the instance that generated it, so that collecting puzzles can aggregate them.
+///
+/// ```rust
+/// # use lhtlp::{InstancePuzzle, LhtlpError, LHTLP};
+/// # use num_bigint::BigUint;
+/// let lhtlp = LHTLP::setup(64, BigUint::from(1000u32));
+/// let total: Result<InstancePuzzle, LhtlpError> = (1..=3).map(|s| lhtlp.bind(lhtlp.generate(s))).collect();
+///
+/// assert!(lhtlp.solve(total.unwrap().puzzle) == BigUint::from(6u32));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InstancePuzzle<'a> {
+    lhtlp: &'a LHTLP,
+    pub puzzle: Puzzle,
+}
+
+impl<'a> InstancePuzzle<'a> {
+    /// The instance the puzzle belongs to.
+    pub fn lhtlp(&self) -> &'a LHTLP {
+        self.lhtlp
+    }
+}
+
+impl LHTLP {
+    /// Tie a `puzzle` of this instance to it.
+    pub fn bind(&self, puzzle: Puzzle) -> InstancePuzzle<'_> {
+        InstancePuzzle { lhtlp: self, puzzle }
+    }
+}
+
+/// Homomorphically add the puzzles, reducing after each step.
+///
+/// An empty iterator carries no instance to generate an encrypted zero with, so it fails with
+/// `LhtlpError::EmptyInput`; `LHTLP::evaluate` returns a puzzle of zero instead. Puzzles bound to
+/// instances with other parameters fail with `LhtlpError::ParameterMismatch`.
+impl<'a> FromIterator<InstancePuzzle<'a>> for Result<InstancePuzzle<'a>, LhtlpError> {
+    fn from_iter<I: IntoIterator<Item = InstancePuzzle<'a>>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let first = iter.next().ok_or(LhtlpError::EmptyInput)?;
+        iter.try_fold(first, |acc, x| {
+            if acc.lhtlp != x.lhtlp {
+                return Err(LhtlpError::ParameterMismatch);
+            }
+            Ok(acc.lhtlp.bind(acc.lhtlp.add(&acc.puzzle, &x.puzzle)))
+        })
+    }
+}
+
+/// A running homomorphic sum of puzzles of one instance, reduced after every puzzle added.
+///
+/// ```rust
+/// # use lhtlp::{PuzzleAccumulator, LHTLP};
+/// # use num_bigint::BigUint;
+/// let lhtlp = LHTLP::setup(64, BigUint::from(1000u32));
+/// let mut acc = PuzzleAccumulator::new(&lhtlp);
+/// acc.extend((1..=3).map(|s| lhtlp.generate(s)));
+/// acc.push(&lhtlp.generate(4));
+///
+/// assert!(lhtlp.solve(acc.into_puzzle().unwrap()) == BigUint::from(10u32));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PuzzleAccumulator<'a> {
+    lhtlp: &'a LHTLP,
+    sum: Option<Puzzle>,
+    len: usize,
+}
+
+impl<'a> PuzzleAccumulator<'a> {
+    /// An empty accumulator for puzzles of `lhtlp`.
+    pub fn new(lhtlp: &'a LHTLP) -> PuzzleAccumulator<'a> {
+        PuzzleAccumulator { lhtlp, sum: None, len: 0 }
+    }
+
+    /// Add `puzzle` to the sum.
+    pub fn push(&mut self, puzzle: &Puzzle) {
+        self.sum = Some(match self.sum.take() {
+            Some(sum) => self.lhtlp.add(&sum, puzzle),
+            None => puzzle.clone(),
+        });
+        self.len += 1;
+    }
+
+    /// The number of puzzles added.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The sum so far, `None` before any puzzle is added.
+    pub fn puzzle(&self) -> Option<&Puzzle> {
+        self.sum.as_ref()
+    }
+
+    /// The sum, failing with `LhtlpError::EmptyInput` if no puzzle was added.
+    pub fn into_puzzle(self) -> Result<Puzzle, LhtlpError> {
+        self.sum.ok_or(LhtlpError::EmptyInput)
+    }
+}
+
+impl Extend<Puzzle> for PuzzleAccumulator<'_> {
+    fn extend<I: IntoIterator<Item = Puzzle>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|puzzle| self.push(&puzzle));
+    }
+}
+
+impl<'b> Extend<&'b Puzzle> for PuzzleAccumulator<'_> {
+    fn extend<I: IntoIterator<Item = &'b Puzzle>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|puzzle| self.push(puzzle));
+    }
+}
+
+/// A tally of puzzles that releases its sum only once enough puzzles were added.
+///
+/// Sealed-bid auctions and elections must not open an aggregate of a handful of puzzles, which
+/// would reveal individual bids or votes early. `finalize` refuses to hand out the sum below the
+/// configured threshold. Each puzzle counts once, so a single voter cannot reach the threshold
+/// by submitting the same puzzle repeatedly.
+#[derive(Debug, Clone)]
+pub struct TallyBuilder<'a> {
+    accumulator: PuzzleAccumulator<'a>,
+    threshold: usize,
+    seen: HashSet<Puzzle>,
+}
+
+impl<'a> TallyBuilder<'a> {
+    /// An empty tally for puzzles of `lhtlp`, released once `threshold` puzzles were added.
+    pub fn new(lhtlp: &'a LHTLP, threshold: usize) -> TallyBuilder<'a> {
+        TallyBuilder { accumulator: PuzzleAccumulator::new(lhtlp), threshold, seen: HashSet::new() }
+    }
+
+    /// Add `puzzle` to the tally.
+    ///
+    /// Fails with `LhtlpError::DuplicatePuzzle` if the same puzzle, up to reduction of `u`
+    /// modulo `n` and of `v` modulo `n^2`, was already added. As in `evaluate_unique`, a
+    /// rerandomized copy is a different `(u, v)` pair and is not caught.
+    pub fn push(&mut self, puzzle: &Puzzle) -> Result<(), LhtlpError> {
+        let lhtlp = self.accumulator.lhtlp;
+        let reduced = Puzzle { u: &puzzle.u % &lhtlp.n, v: &puzzle.v % &lhtlp.n2 };
+        if !self.seen.insert(reduced) {
+            return Err(LhtlpError::DuplicatePuzzle);
+        }
+        self.accumulator.push(puzzle);
+        Ok(())
+    }
+
+    /// The number of puzzles added.
+    pub fn len(&self) -> usize {
+        self.accumulator.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accumulator.is_empty()
+    }
+
+    /// The number of puzzles required before the tally is released.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The aggregate of the tally.
+    ///
+    /// Fails with `LhtlpError::ThresholdNotMet` if fewer puzzles than the threshold were added,
+    /// and with `LhtlpError::EmptyInput` if none were.
+    pub fn finalize(self) -> Result<Puzzle, LhtlpError> {
+        if self.len() < self.threshold {
+            return Err(LhtlpError::ThresholdNotMet { count: self.len(), threshold: self.threshold });
+        }
+        self.accumulator.into_puzzle()
+    }
+}
+
+/// Aggregation adapters for iterators of puzzles.
+///
+/// Puzzles are folded one at a time with modular reduction after each step, so no intermediate
+/// collection is built.
+/// ```rust
+/// # use lhtlp::{LHTLP, LhtlpIteratorExt};
+/// # use num_bigint::BigUint;
+/// let lhtlp = LHTLP::setup(64, BigUint::from(1000u32));
+/// let incoming = vec![lhtlp.generate(1), lhtlp.generate(2), lhtlp.generate(3)];
+/// let total = incoming.iter().take(2).aggregate(&lhtlp).unwrap();
+///
+/// assert!(lhtlp.solve(total) == BigUint::from(3u32));
+/// ```
+pub trait LhtlpIteratorExt: Iterator + Sized
+where
+    Self::Item: Borrow<Puzzle>,
+{
+    /// Homomorphically add all the puzzles, failing with `LhtlpError::EmptyInput` if there are none.
+    fn aggregate(mut self, lhtlp: &LHTLP) -> Result<Puzzle, LhtlpError> {
+        let first = self.next().ok_or(LhtlpError::EmptyInput)?.borrow().clone();
+        Ok(self.fold(first, |acc, x| lhtlp.add(&acc, x.borrow())))
+    }
+
+    /// Homomorphically compute the sum of the puzzles, each multiplied by the matching weight.
+    ///
+    /// Fails with `LhtlpError::WeightCountMismatch` if there are more or fewer weights than puzzles.
+    fn aggregate_weighted<W>(self, lhtlp: &LHTLP, weights: W) -> Result<Puzzle, LhtlpError>
+    where
+        W: IntoIterator,
+        W::Item: Borrow<BigUint>,
+    {
+        let mut weights = weights.into_iter();
+        let mut acc: Option<Puzzle> = None;
+        for puzzle in self {
+            let weight = weights.next().ok_or(LhtlpError::WeightCountMismatch)?;
+            let term = lhtlp.scalar_mul(puzzle.borrow(), weight.borrow());
+            acc = Some(match acc {
+                Some(acc) => lhtlp.add(&acc, &term),
+                None => term,
+            });
+        }
+        if weights.next().is_some() {
+            return Err(LhtlpError::WeightCountMismatch);
+        }
+        acc.ok_or(LhtlpError::EmptyInput)
+    }
+}
+
+impl<I> LhtlpIteratorExt for I
+where
+    I: Iterator,
+    I::Item: Borrow<Puzzle>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn aggregate() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (1..=10).map(|s| lhtlp.generate(s)).collect();
+        let even = puzzles.iter().enumerate().filter(|(i, _)| i % 2 == 1).map(|(_, p)| p).aggregate(&lhtlp).unwrap();
+
+        assert!(lhtlp.solve(even) == BigUint::from(2u32 + 4 + 6 + 8 + 10));
+        assert!(std::iter::empty::<Puzzle>().aggregate(&lhtlp) == Err(LhtlpError::EmptyInput));
+    }
+
+    #[test]
+    fn aggregate_weighted() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles = [lhtlp.generate(5), lhtlp.generate(7)];
+        let weights = vec![BigUint::from(3u32), BigUint::from(2u32)];
+        let total = puzzles.iter().aggregate_weighted(&lhtlp, &weights).unwrap();
+
+        assert!(lhtlp.solve(total) == BigUint::from(29u32));
+        assert!(puzzles.iter().aggregate_weighted(&lhtlp, &weights[..1]) == Err(LhtlpError::WeightCountMismatch));
+        assert!(puzzles.iter().take(1).aggregate_weighted(&lhtlp, &weights) == Err(LhtlpError::WeightCountMismatch));
+    }
+
+    #[test]
+    fn bounded_sum() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let bound = BigUint::from(u64::MAX);
+        let result: Result<BoundedPuzzle, LhtlpError> = [10u64, 20, 30]
+            .iter()
+            .map(|s| BoundedPuzzle::new(&lhtlp, lhtlp.generate(*s), bound.clone()).unwrap())
+            .collect();
+        let sum = result.unwrap();
+
+        assert!(sum.bound == &bound * 3u32);
+        assert!(lhtlp.solve(sum.puzzle) == BigUint::from(60u32));
+    }
+
+    #[test]
+    fn collect_instance_puzzles() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (1..=10).map(|s| lhtlp.generate(s)).collect();
+        let total: Result<InstancePuzzle, LhtlpError> = puzzles.iter().map(|p| lhtlp.bind(p.clone())).collect();
+        assert!(total.unwrap().puzzle == lhtlp.evaluate(puzzles));
+
+        let empty: Result<InstancePuzzle, LhtlpError> = std::iter::empty().collect();
+        assert!(empty.is_err_and(|err| err == LhtlpError::EmptyInput));
+
+        let other = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mixed: Result<InstancePuzzle, LhtlpError> =
+            [lhtlp.bind(lhtlp.generate(1)), other.bind(other.generate(2))].into_iter().collect();
+        assert!(mixed.is_err_and(|err| err == LhtlpError::ParameterMismatch));
+    }
+
+    #[test]
+    fn bounded_sum_overflow() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let half = &lhtlp.n / 2u32;
+        let puzzles = (0..3).map(|_| BoundedPuzzle::new(&lhtlp, lhtlp.generate(1), half.clone()).unwrap());
+
+        assert!(matches!(BoundedPuzzle::try_sum(puzzles), Err(LhtlpError::Overflow)));
+        assert!(BoundedPuzzle::new(&lhtlp, lhtlp.generate(1), lhtlp.n.clone()).is_err());
+    }
+
+    #[test]
+    fn accumulator_extend() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (1..=10).map(|s| lhtlp.generate(s)).collect();
+        let mut acc = PuzzleAccumulator::new(&lhtlp);
+        assert!(acc.is_empty() && acc.puzzle().is_none());
+
+        acc.extend(&puzzles[..4]);
+        acc.extend(puzzles[4..].iter().cloned());
+        assert!(acc.len() == 10);
+        assert!(acc.puzzle() == Some(&lhtlp.evaluate(puzzles.clone())));
+        let sum = acc.into_puzzle().unwrap();
+        assert!(sum.u < lhtlp.n && sum.v < lhtlp.n2);
+        assert!(lhtlp.solve(sum) == BigUint::from(55u32));
+        assert!(PuzzleAccumulator::new(&lhtlp).into_puzzle() == Err(LhtlpError::EmptyInput));
+    }
+
+    #[test]
+    fn tally_threshold() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut tally = TallyBuilder::new(&lhtlp, 3);
+        for secret in 1..=2 {
+            tally.push(&lhtlp.generate(secret)).unwrap();
+        }
+        assert!(tally.clone().finalize() == Err(LhtlpError::ThresholdNotMet { count: 2, threshold: 3 }));
+
+        tally.push(&lhtlp.generate(3)).unwrap();
+        assert!(tally.len() == 3);
+        assert!(lhtlp.solve(tally.finalize().unwrap()) == BigUint::from(6u32));
+        assert!(TallyBuilder::new(&lhtlp, 0).finalize() == Err(LhtlpError::EmptyInput));
+    }
+
+    #[test]
+    fn tally_rejects_duplicates() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut tally = TallyBuilder::new(&lhtlp, 3);
+        let vote = lhtlp.generate(1);
+        tally.push(&vote).unwrap();
+        assert!(tally.push(&vote) == Err(LhtlpError::DuplicatePuzzle));
+        let unreduced = Puzzle { u: &vote.u + &lhtlp.n, v: &vote.v + &lhtlp.n2 };
+        assert!(tally.push(&unreduced) == Err(LhtlpError::DuplicatePuzzle));
+        assert!(tally.len() == 1);
+        assert!(tally.finalize() == Err(LhtlpError::ThresholdNotMet { count: 1, threshold: 3 }));
+    }
+}
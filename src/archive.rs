@@ -0,0 +1,166 @@
+//! Compact encoding of many puzzles of one instance.
+//!
+//! # Format
+//!
+//! The archive starts with a header like the one of `PuzzleStore` files:
+//!
+//! - the 8-byte magic `LHTLPPZA`,
+//! - a 1-byte format version, currently `1`,
+//! - the 1-byte identifier of the hash suite of the fingerprint (`HashSuite::id`),
+//! - the parameter fingerprint (`LHTLP::fingerprint`) of the instance, whose length is the digest
+//!   size of the suite (`HashSuite::output_len`), 32 bytes for SHA-256.
+//!
+//! It is followed by the number of puzzles, then `u` and `v` of each puzzle as a length and the
+//! minimal big-endian magnitude of that length. The count and the lengths are LEB128 varints, so
+//! a component takes one or two bytes of overhead instead of the four of `Puzzle::to_bytes`.
+use crate::{HashSuite, LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+const MAGIC: &[u8; 8] = b"LHTLPPZA";
+const VERSION: u8 = 1;
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Read a LEB128 varint off the front of `rest`, rejecting overlong and overflowing encodings.
+fn read_varint(rest: &mut &[u8]) -> Result<u64, LhtlpError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, tail) = rest.split_first().ok_or(LhtlpError::InvalidEncoding)?;
+        *rest = tail;
+        let bits = u64::from(byte & 0x7f);
+        if bits << shift >> shift != bits || (byte == 0 && shift > 0) {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(LhtlpError::InvalidEncoding)
+}
+
+/// Split `len` bytes off the front of `rest`.
+fn read<'a>(rest: &mut &'a [u8], len: u64) -> Result<&'a [u8], LhtlpError> {
+    if (rest.len() as u64) < len {
+        return Err(LhtlpError::InvalidEncoding);
+    }
+    let (head, tail) = rest.split_at(len as usize);
+    *rest = tail;
+    Ok(head)
+}
+
+impl LHTLP {
+    /// Encode `puzzles` of this instance into one archive, writing the fingerprint once.
+    ///
+    /// The format is described in the module documentation.
+    pub fn serialize_puzzles(&self, puzzles: &[Puzzle]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(self.hash_suite().id());
+        bytes.extend_from_slice(&self.fingerprint());
+        write_varint(&mut bytes, puzzles.len() as u64);
+        for puzzle in puzzles {
+            for value in [&puzzle.u, &puzzle.v] {
+                let magnitude = value.to_bytes_be();
+                write_varint(&mut bytes, magnitude.len() as u64);
+                bytes.extend_from_slice(&magnitude);
+            }
+        }
+        bytes
+    }
+
+    /// Decode an archive from `serialize_puzzles`.
+    ///
+    /// Fails with `LhtlpError::UnsupportedHashSuite` or `LhtlpError::ParameterMismatch` if the
+    /// archive was written for another instance, and with `LhtlpError::InvalidEncoding` if it is
+    /// truncated, has trailing data or an unknown version. The puzzles are not validated; use
+    /// `verify_puzzle` on those from untrusted sources.
+    pub fn deserialize_puzzles(&self, bytes: &[u8]) -> Result<Vec<Puzzle>, LhtlpError> {
+        let mut rest = bytes;
+        if read(&mut rest, 8)? != MAGIC || read(&mut rest, 1)? != [VERSION] {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        self.hash_suite().check(HashSuite::from_id(read(&mut rest, 1)?[0])?)?;
+        if read(&mut rest, self.hash_suite().output_len() as u64)? != self.fingerprint() {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        let count = read_varint(&mut rest)?;
+        // Every puzzle takes at least two bytes, which bounds the allocation by the input size.
+        if count > rest.len() as u64 / 2 {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        let mut next = || {
+            let len = read_varint(&mut rest)?;
+            Ok::<_, LhtlpError>(BigUint::from_bytes_be(read(&mut rest, len)?))
+        };
+        let puzzles = (0..count)
+            .map(|_| Ok(Puzzle { u: next()?, v: next()? }))
+            .collect::<Result<Vec<Puzzle>, LhtlpError>>()?;
+        if !rest.is_empty() {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        Ok(puzzles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn archive_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzles: Vec<Puzzle> = (0..20).map(|s| lhtlp.generate(s)).collect();
+        let bytes = lhtlp.serialize_puzzles(&puzzles);
+        assert!(lhtlp.deserialize_puzzles(&bytes) == Ok(puzzles.clone()));
+        assert!(bytes.len() < puzzles.iter().map(|p| p.to_bytes().len()).sum::<usize>());
+        assert!(lhtlp.deserialize_puzzles(&lhtlp.serialize_puzzles(&[])) == Ok(Vec::new()));
+
+        let trivial = vec![Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) }];
+        assert!(lhtlp.deserialize_puzzles(&lhtlp.serialize_puzzles(&trivial)) == Ok(trivial));
+    }
+
+    #[test]
+    fn archive_rejects_mismatches() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzles = [lhtlp.generate(42)];
+        let bytes = lhtlp.serialize_puzzles(&puzzles);
+
+        let other = LHTLP::setup(LAMBDA, DIFFICULTY);
+        assert!(other.deserialize_puzzles(&bytes) == Err(LhtlpError::ParameterMismatch));
+        let sha384 = lhtlp.clone().with_hash_suite(HashSuite::Sha384);
+        assert!(sha384.deserialize_puzzles(&bytes) == Err(LhtlpError::UnsupportedHashSuite));
+        let sha384_bytes = sha384.serialize_puzzles(&puzzles);
+        assert!(sha384_bytes.len() == bytes.len() + 16);
+        assert!(sha384.deserialize_puzzles(&sha384_bytes).is_ok());
+        assert!(lhtlp.deserialize_puzzles(&bytes[..bytes.len() - 1]) == Err(LhtlpError::InvalidEncoding));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(lhtlp.deserialize_puzzles(&trailing) == Err(LhtlpError::InvalidEncoding));
+        let mut version = bytes;
+        version[8] = 2;
+        assert!(lhtlp.deserialize_puzzles(&version) == Err(LhtlpError::InvalidEncoding));
+    }
+
+    #[test]
+    fn varints() {
+        for value in [0u64, 1, 127, 128, 300, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+            let mut rest = bytes.as_slice();
+            assert!(read_varint(&mut rest) == Ok(value));
+            assert!(rest.is_empty());
+        }
+        assert!(read_varint(&mut [0x80u8, 0x00].as_slice()) == Err(LhtlpError::InvalidEncoding));
+        assert!(read_varint(&mut [0xffu8; 10].as_slice()) == Err(LhtlpError::InvalidEncoding));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{Puzzle, LHTLP};
+
+/// Default number of puzzles accumulated between two audit checkpoints.
+pub const DEFAULT_AUDIT_INTERVAL: usize = 100;
+
+/// Intermediate results of a homomorphic evaluation, for auditors to check each step.
+///
+/// Each step `(count, puzzle)` holds the aggregate of the first `count` puzzles, recorded every
+/// `interval` puzzles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLog {
+    pub interval: usize,
+    pub steps: Vec<(usize, Puzzle)>,
+}
+
+impl AuditLog {
+    /// Re-evaluate `original_puzzles` from scratch and check every recorded step against it.
+    pub fn verify(&self, lhtlp: &LHTLP, original_puzzles: &[Puzzle]) -> bool {
+        if self.interval == 0 || self.steps.len() != original_puzzles.len() / self.interval {
+            return false;
+        }
+        let mut steps = self.steps.iter();
+        let mut acc: Option<Puzzle> = None;
+        for (i, puzzle) in original_puzzles.iter().enumerate() {
+            let next = match &acc {
+                Some(acc) => lhtlp.add(acc, puzzle),
+                None => puzzle.clone(),
+            };
+            let count = i + 1;
+            if count % self.interval == 0 {
+                match steps.next() {
+                    Some((recorded, step)) if *recorded == count && step == &next => {}
+                    _ => return false,
+                }
+            }
+            acc = Some(next);
+        }
+        true
+    }
+}
+
+impl LHTLP {
+    /// Evaluate puzzles like `evaluate`, recording the aggregate every 100 puzzles.
+    #[must_use]
+    pub fn evaluate_audited(&self, puzzles: Vec<Puzzle>) -> (Puzzle, AuditLog) {
+        self.evaluate_audited_with_interval(puzzles, DEFAULT_AUDIT_INTERVAL)
+    }
+
+    /// Evaluate puzzles like `evaluate`, recording the aggregate every `interval` puzzles.
+    #[must_use]
+    pub fn evaluate_audited_with_interval(&self, puzzles: Vec<Puzzle>, interval: usize) -> (Puzzle, AuditLog) {
+        assert!(interval > 0, "audit interval must be positive");
+        let mut log = AuditLog { interval, steps: Vec::new() };
+        let mut acc: Option<Puzzle> = None;
+        for (i, puzzle) in puzzles.into_iter().enumerate() {
+            let next = match acc {
+                Some(acc) => self.add(&acc, &puzzle),
+                None => puzzle,
+            };
+            if (i + 1) % interval == 0 {
+                log.steps.push((i + 1, next.clone()));
+            }
+            acc = Some(next);
+        }
+        let result = acc.unwrap_or_else(|| self.evaluate(Vec::new()));
+        (result, log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn audited_evaluation() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (0..200).map(|s| lhtlp.generate(s)).collect();
+        let (result, log) = lhtlp.evaluate_audited(puzzles.clone());
+
+        assert!(result == lhtlp.evaluate(puzzles.clone()));
+        assert!(log.steps.len() == 2);
+        assert!(log.steps[0] == (100, lhtlp.evaluate(puzzles[..100].to_vec())));
+        assert!(log.steps[1] == (200, result));
+        assert!(log.verify(&lhtlp, &puzzles));
+    }
+
+    #[test]
+    fn tampered_audit_log() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (0..30).map(|s| lhtlp.generate(s)).collect();
+        let (_, log) = lhtlp.evaluate_audited_with_interval(puzzles.clone(), 10);
+        assert!(log.steps.len() == 3);
+
+        let mut tampered = log.clone();
+        tampered.steps[1].1 = lhtlp.add(&tampered.steps[1].1, &lhtlp.generate(1));
+        assert!(!tampered.verify(&lhtlp, &puzzles));
+
+        let mut swapped = puzzles.clone();
+        swapped[5] = lhtlp.generate(5);
+        assert!(!log.verify(&lhtlp, &swapped));
+    }
+}
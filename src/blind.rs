@@ -0,0 +1,88 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// A puzzle whose secret is offset by a random blinding factor and whose randomness is refreshed,
+/// so a relay cannot link it to the puzzle it was derived from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlindedPuzzle {
+    pub puzzle: Puzzle,
+}
+
+/// The blinding factor of a `BlindedPuzzle`, kept by its sender to remove it after evaluation.
+///
+/// Together with the blinded puzzle it reveals the secret once the puzzle is solved, so it must be
+/// kept as secret as the puzzle content.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Unblinder {
+    factor: BigUint,
+}
+
+impl std::fmt::Debug for Unblinder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Unblinder(..)")
+    }
+}
+
+impl LHTLP {
+    /// Blind `puzzle` for a mix-net by adding `blind_factor` to its secret and rerandomizing it.
+    ///
+    /// `blind_factor` should be uniform modulo `n`; it is reduced modulo `n`.
+    pub fn blind(&self, puzzle: &Puzzle, blind_factor: &BigUint) -> (BlindedPuzzle, Unblinder) {
+        let factor = blind_factor % &self.n;
+        let puzzle = self.rerandomize(&self.add_constant(puzzle, &factor));
+        (BlindedPuzzle { puzzle }, Unblinder { factor })
+    }
+
+    /// Homomorphically add blinded puzzles, as a relay that cannot see the individual inputs.
+    ///
+    /// The result embeds the sum of the secrets plus the sum of the blinding factors. Fails with
+    /// `LhtlpError::EmptyInput` if there are no puzzles.
+    pub fn blind_evaluate(&self, blinded_puzzles: Vec<BlindedPuzzle>) -> Result<Puzzle, LhtlpError> {
+        if blinded_puzzles.is_empty() {
+            return Err(LhtlpError::EmptyInput);
+        }
+        Ok(self.evaluate(blinded_puzzles.into_iter().map(|blinded| blinded.puzzle).collect()))
+    }
+
+    /// Remove the blinding factors of all the inputs of `blind_evaluate` from its output.
+    #[must_use]
+    pub fn unblind(&self, combined: &Puzzle, unblinders: Vec<Unblinder>) -> Puzzle {
+        let total = unblinders.iter().fold(BigUint::from(0u32), |acc, unblinder| (acc + &unblinder.factor) % &self.n);
+        self.add_constant(combined, &((&self.n - total) % &self.n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::RandBigInt;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn mix_net() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut rng = rand::thread_rng();
+        let puzzles: Vec<Puzzle> = [5u64, 11, u64::MAX].iter().map(|&s| lhtlp.generate(s)).collect();
+        let (blinded, unblinders): (Vec<BlindedPuzzle>, Vec<Unblinder>) =
+            puzzles.iter().map(|puzzle| lhtlp.blind(puzzle, &rng.gen_biguint_below(&lhtlp.n))).unzip();
+        for (puzzle, blinded) in puzzles.iter().zip(&blinded) {
+            assert!(blinded.puzzle.u != puzzle.u && blinded.puzzle.v != puzzle.v);
+        }
+
+        let combined = lhtlp.blind_evaluate(blinded).unwrap();
+        let expected = BigUint::from(16u32) + u64::MAX;
+        assert!(lhtlp.solve(lhtlp.unblind(&combined, unblinders)) == expected);
+        assert!(lhtlp.blind_evaluate(Vec::new()) == Err(LhtlpError::EmptyInput));
+    }
+
+    #[test]
+    fn blinding_hides_the_secret() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let (blinded, unblinder) = lhtlp.blind(&lhtlp.generate(42), &BigUint::from(1000u32));
+        assert!(lhtlp.solve(blinded.puzzle.clone()) == BigUint::from(1042u32));
+        assert!(lhtlp.solve(lhtlp.unblind(&blinded.puzzle, vec![unblinder])) == BigUint::from(42u32));
+    }
+}
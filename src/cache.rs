@@ -0,0 +1,267 @@
+use crate::{LhtlpError, PartialSolution, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+/// A solved puzzle, stored together with `w = u^(2^difficulty) mod n`.
+///
+/// Keeping `w` allows a cache hit to be checked against the puzzle by re-encoding the solution,
+/// which costs a couple of exponentiations instead of a full solve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedSolution {
+    pub solution: BigUint,
+    pub w: BigUint,
+}
+
+/// Storage backend for `LHTLP::solve_cached`.
+///
+/// Keys combine the parameter fingerprint and the canonical encoding of the puzzle, so a single
+/// cache can be shared among several instances.
+pub trait SolutionCache {
+    fn get(&self, key: &[u8; 32]) -> Option<CachedSolution>;
+    fn insert(&mut self, key: [u8; 32], entry: CachedSolution) -> Result<(), LhtlpError>;
+}
+
+/// In-memory solution cache.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySolutionCache {
+    entries: HashMap<[u8; 32], CachedSolution>,
+}
+
+impl MemorySolutionCache {
+    pub fn new() -> MemorySolutionCache {
+        MemorySolutionCache::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl SolutionCache for MemorySolutionCache {
+    fn get(&self, key: &[u8; 32]) -> Option<CachedSolution> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: [u8; 32], entry: CachedSolution) -> Result<(), LhtlpError> {
+        self.entries.insert(key, entry);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "file-cache")]
+pub use self::file::FileSolutionCache;
+
+#[cfg(feature = "file-cache")]
+mod file {
+    use super::{CachedSolution, MemorySolutionCache, SolutionCache};
+    use crate::{read_value, write_value, LhtlpError};
+    use std::fs::{File, OpenOptions};
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+
+    /// File-backed solution cache.
+    ///
+    /// The file is a sequence of records, each made of the 32-byte key followed by the solution
+    /// and `w`, both as a 4-byte big-endian length and a big-endian magnitude. Records are
+    /// appended on insertion and the whole file is loaded when the cache is opened.
+    #[derive(Debug)]
+    pub struct FileSolutionCache {
+        path: PathBuf,
+        memory: MemorySolutionCache,
+    }
+
+    impl FileSolutionCache {
+        /// Open the cache stored at `path`, creating an empty one if the file does not exist.
+        pub fn open(path: impl AsRef<Path>) -> Result<FileSolutionCache, LhtlpError> {
+            let path = path.as_ref().to_path_buf();
+            let mut memory = MemorySolutionCache::new();
+            let mut contents = Vec::new();
+            match File::open(&path) {
+                Ok(mut file) => {
+                    file.read_to_end(&mut contents)?;
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            let mut bytes = contents.as_slice();
+            while !bytes.is_empty() {
+                if bytes.len() < 32 {
+                    return Err(LhtlpError::InvalidEncoding);
+                }
+                let (key, rest) = bytes.split_at(32);
+                bytes = rest;
+                let solution = read_value(&mut bytes)?;
+                let w = read_value(&mut bytes)?;
+                memory.insert(key.try_into().unwrap(), CachedSolution { solution, w })?;
+            }
+            Ok(FileSolutionCache { path, memory })
+        }
+
+        pub fn len(&self) -> usize {
+            self.memory.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.memory.is_empty()
+        }
+    }
+
+    impl SolutionCache for FileSolutionCache {
+        fn get(&self, key: &[u8; 32]) -> Option<CachedSolution> {
+            self.memory.get(key)
+        }
+
+        fn insert(&mut self, key: [u8; 32], entry: CachedSolution) -> Result<(), LhtlpError> {
+            let mut record = key.to_vec();
+            write_value(&mut record, &entry.solution);
+            write_value(&mut record, &entry.w);
+            let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            file.write_all(&record)?;
+            self.memory.insert(key, entry)
+        }
+    }
+}
+
+impl LHTLP {
+    /// Key of `puzzle` in a `SolutionCache`, binding the puzzle to the parameters of this instance.
+    ///
+    /// The key is the digest under the hash suite of the instance, truncated to 32 bytes.
+    pub fn cache_key(&self, puzzle: &Puzzle) -> [u8; 32] {
+        let mut data = self.fingerprint();
+        data.extend_from_slice(&puzzle.to_bytes());
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&self.hash_suite.digest(&data)[..32]);
+        key
+    }
+
+    /// Open a puzzle, reusing the solution stored in `cache` if any.
+    ///
+    /// A cached entry is only trusted if re-encoding its solution with its `w` gives back the
+    /// `v` component of the puzzle, up to a factor of order 2 as in decoding; otherwise the puzzle is solved again and the entry replaced.
+    /// The puzzle is checked and solved like in `solve_checked`, under the policy of the instance.
+    pub fn solve_cached<C: SolutionCache + ?Sized>(&self, puzzle: Puzzle, cache: &mut C) -> Result<BigUint, LhtlpError> {
+        self.verify_puzzle(&puzzle)?;
+        let key = self.cache_key(&puzzle);
+        if let Some(entry) = cache.get(&key) {
+            // Compared squared, like decoding, so a `v` off by a factor of order 2 still hits.
+            let n2 = &self.n2;
+            let encoded = entry.w.modpow(&self.n, n2) * self.plaintext_power(&entry.solution, n2) % n2;
+            if self.plaintext_space().fits(&entry.solution) && &encoded * &encoded % n2 == &puzzle.v * &puzzle.v % n2 {
+                return Ok(entry.solution);
+            }
+        }
+
+        let state = self.advance_with_policy(PartialSolution::new(puzzle), &self.policy, &mut 0)?;
+        let solution = self.try_decode(&state.puzzle.v, &state.w)?;
+        cache.insert(key, CachedSolution { solution: solution.clone(), w: state.w })?;
+        Ok(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashSuite, SolvePolicy};
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn miss_then_hit() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let mut cache = MemorySolutionCache::new();
+
+        assert!(lhtlp.solve_cached(puzzle.clone(), &mut cache) == Ok(BigUint::from(42u32)));
+        assert!(cache.len() == 1);
+        assert!(lhtlp.solve_cached(puzzle, &mut cache) == Ok(BigUint::from(42u32)));
+        assert!(cache.len() == 1);
+    }
+
+    #[test]
+    fn corrupted_entry_is_resolved() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let mut cache = MemorySolutionCache::new();
+        let key = lhtlp.cache_key(&puzzle);
+        lhtlp.solve_cached(puzzle.clone(), &mut cache).unwrap();
+        let mut entry = cache.get(&key).unwrap();
+        entry.solution = BigUint::from(43u32);
+        cache.insert(key, entry).unwrap();
+
+        assert!(lhtlp.solve_cached(puzzle, &mut cache) == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn negated_v_hits() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut puzzle = lhtlp.generate(42);
+        puzzle.v = &lhtlp.n2 - &puzzle.v;
+        let mut cache = MemorySolutionCache::new();
+        assert!(lhtlp.solve_cached(puzzle.clone(), &mut cache) == Ok(BigUint::from(42u32)));
+
+        // A re-solve would exceed this policy, so success means the entry was used.
+        let limited = lhtlp.with_policy(SolvePolicy::default().with_max_difficulty(DIFFICULTY - 1));
+        assert!(limited.solve_cached(puzzle, &mut cache) == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn instances_are_isolated() {
+        let first = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let second = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = first.generate(42);
+        let mut cache = MemorySolutionCache::new();
+
+        first.solve_cached(puzzle.clone(), &mut cache).unwrap();
+        assert!(first.cache_key(&puzzle) != second.cache_key(&puzzle));
+        assert!(cache.get(&second.cache_key(&puzzle)).is_none());
+    }
+
+    #[test]
+    fn policy_and_validity() {
+        let policy = SolvePolicy::default().with_max_difficulty(DIFFICULTY - 1);
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY)).with_policy(policy);
+        let mut cache = MemorySolutionCache::new();
+        let result = lhtlp.solve_cached(lhtlp.generate(42), &mut cache);
+        assert!(matches!(result, Err(LhtlpError::DifficultyTooLarge { .. })));
+
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.solve_cached(malformed, &mut cache) == Err(LhtlpError::InvalidPuzzle));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn hash_suites_are_isolated() {
+        let sha256 = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let sha384 = sha256.clone().with_hash_suite(HashSuite::Sha384);
+        let puzzle = sha256.generate(42);
+        assert!(sha256.cache_key(&puzzle) != sha384.cache_key(&puzzle));
+    }
+
+    #[cfg(feature = "file-cache")]
+    #[test]
+    fn file_cache_roundtrip() {
+        let path = std::env::temp_dir().join(format!("lhtlp-cache-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (0..3).map(|s| lhtlp.generate(s)).collect();
+        {
+            let mut cache = FileSolutionCache::open(&path).unwrap();
+            for puzzle in &puzzles {
+                lhtlp.solve_cached(puzzle.clone(), &mut cache).unwrap();
+            }
+        }
+
+        let cache = FileSolutionCache::open(&path).unwrap();
+        assert!(cache.len() == 3);
+        for (secret, puzzle) in puzzles.iter().enumerate() {
+            assert!(cache.get(&lhtlp.cache_key(puzzle)).unwrap().solution == BigUint::from(secret));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::vdf::expand;
+use crate::{distinct_safe_primes, Difficulty, HashSuite, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+
+/// Number of Miller-Rabin witnesses recorded for each prime, for an error below `2^-128`.
+const CERTIFICATE_WITNESSES: usize = 64;
+
+/// Miller-Rabin witnesses attesting that the factors of an instance modulus are prime.
+///
+/// Witnesses are not chosen by the party running the setup: they are derived by hashing the prime
+/// they attest under the hash suite of the instance, so an auditor recomputes them and cannot be
+/// handed a set of strong liars.
+///
+/// The certificate contains `p` and `q` themselves, that is the trapdoor of the instance, and
+/// must only be disclosed to auditors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimalityCertificate {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub p_witnesses: Vec<BigUint>,
+    pub q_witnesses: Vec<BigUint>,
+    pub p_bits: usize,
+    pub q_bits: usize,
+}
+
+/// Derive the `index`-th Miller-Rabin base for `candidate`, in the range `[2, candidate - 2]`.
+fn witness(hash_suite: HashSuite, candidate: &BigUint, index: usize) -> BigUint {
+    // Expand to twice the size of the candidate to make the modular bias negligible.
+    let parts: [&[u8]; 3] = [b"lhtlp-primality-witness", &candidate.to_bytes_be(), &(index as u64).to_be_bytes()];
+    let range = candidate - BigUint::from(3u32);
+    BigUint::from(2u32) + expand(hash_suite, &parts, 2 * candidate.bits() as usize) % range
+}
+
+fn witnesses(hash_suite: HashSuite, candidate: &BigUint) -> Vec<BigUint> {
+    (0..CERTIFICATE_WITNESSES).map(|i| witness(hash_suite, candidate, i)).collect()
+}
+
+/// Whether `base` is a Miller-Rabin witness of the (odd) `candidate` being a probable prime.
+fn passes_miller_rabin(candidate: &BigUint, base: &BigUint) -> bool {
+    let one = BigUint::from(1u32);
+    let minus_one = candidate - &one;
+    let s = minus_one.trailing_zeros().unwrap_or(0);
+    let d = &minus_one >> s;
+    let mut x = base.modpow(&d, candidate);
+    if x == one || x == minus_one {
+        return true;
+    }
+    for _ in 1..s {
+        x = x.modpow(&BigUint::from(2u32), candidate);
+        if x == minus_one {
+            return true;
+        }
+    }
+    false
+}
+
+fn verify_prime(hash_suite: HashSuite, candidate: &BigUint, bits: usize, recorded: &[BigUint]) -> bool {
+    candidate.bits() as usize == bits
+        && candidate > &BigUint::from(3u32)
+        && candidate.is_odd()
+        && recorded.len() == CERTIFICATE_WITNESSES
+        && recorded == witnesses(hash_suite, candidate).as_slice()
+        && recorded.iter().all(|base| passes_miller_rabin(candidate, base))
+}
+
+/// Check that `certificate` attests the primality of the factors of the modulus of `lhtlp`.
+pub fn verify_certificate(certificate: &PrimalityCertificate, lhtlp: &LHTLP) -> bool {
+    &certificate.p * &certificate.q == lhtlp.n
+        && verify_prime(lhtlp.hash_suite, &certificate.p, certificate.p_bits, &certificate.p_witnesses)
+        && verify_prime(lhtlp.hash_suite, &certificate.q, certificate.q_bits, &certificate.q_witnesses)
+}
+
+impl LHTLP {
+    /// Setup an instance like `setup`, also returning a certificate of the primality of `p` and `q`.
+    ///
+    /// The certificate is meant for audits of the setup ceremony and reveals the trapdoor, see
+    /// `PrimalityCertificate`.
+    pub fn setup_auditable(lambda: u64, difficulty: impl Into<Difficulty>) -> (LHTLP, PrimalityCertificate) {
+        let (p, q) = distinct_safe_primes(lambda);
+        let lhtlp = LHTLP::from_primes(&p, &q, difficulty.into().into());
+        let certificate = PrimalityCertificate {
+            p_witnesses: witnesses(lhtlp.hash_suite, &p),
+            q_witnesses: witnesses(lhtlp.hash_suite, &q),
+            p_bits: p.bits() as usize,
+            q_bits: q.bits() as usize,
+            p,
+            q,
+        };
+        (lhtlp, certificate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn miller_rabin() {
+        let prime = BigUint::from(1_000_000_007u64);
+        let carmichael = BigUint::from(561u32);
+        assert!((0..10).all(|i| passes_miller_rabin(&prime, &witness(HashSuite::default(), &prime, i))));
+        assert!(!passes_miller_rabin(&carmichael, &BigUint::from(2u32)));
+    }
+
+    #[test]
+    fn valid_certificate() {
+        let (lhtlp, certificate) = LHTLP::setup_auditable(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(verify_certificate(&certificate, &lhtlp));
+
+        for _ in 0..20 {
+            let (small, certificate) = LHTLP::setup_auditable(16, DIFFICULTY);
+            assert!(certificate.p != certificate.q && verify_certificate(&certificate, &small));
+        }
+    }
+
+    #[test]
+    fn tampered_certificate() {
+        let (lhtlp, certificate) = LHTLP::setup_auditable(LAMBDA, BigUint::from(DIFFICULTY));
+
+        let mut tampered = certificate.clone();
+        tampered.p_witnesses[3] += 1u32;
+        assert!(!verify_certificate(&tampered, &lhtlp));
+
+        let mut tampered = certificate.clone();
+        tampered.q_witnesses.pop();
+        assert!(!verify_certificate(&tampered, &lhtlp));
+
+        let mut tampered = certificate.clone();
+        tampered.p_bits += 1;
+        assert!(!verify_certificate(&tampered, &lhtlp));
+
+        let other = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(!verify_certificate(&certificate, &other));
+        // The witnesses are bound to the hash suite of the instance.
+        assert!(!verify_certificate(&certificate, &lhtlp.with_hash_suite(HashSuite::Sha384)));
+    }
+}
@@ -0,0 +1,67 @@
+use crate::num_primes::RandBigInt;
+use crate::vdf::expand;
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// Largest size in bits of the seed hidden in the outer puzzle of a chain.
+const SEED_BITS: u64 = 256;
+
+/// A secret released only after solving two puzzles one after the other.
+///
+/// `inner` holds the secret, but its `u` is masked with a pad derived from the seed in `outer`:
+/// the squarings of `inner` cannot start before `outer` is solved, so opening the chain takes the
+/// difficulty of both instances in sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainedPuzzle {
+    pub outer: Puzzle,
+    pub inner: Puzzle,
+}
+
+/// The additive pad of the inner `u`, uniform modulo the inner `n` up to a `2^-128` bias.
+fn chain_mask(inner: &LHTLP, seed: &BigUint) -> BigUint {
+    let bits = (inner.n.bits() + 128) as usize;
+    expand(inner.hash_suite, &[b"lhtlp-chain-mask", &seed.to_bytes_be(), &inner.fingerprint()], bits) % &inner.n
+}
+
+impl LHTLP {
+    /// Chain two instances: the puzzle of `secret` under `inner` is locked behind a puzzle of `outer`.
+    ///
+    /// Fails with `LhtlpError::InvalidSecret` if `secret` does not fit the plaintext space of `inner`.
+    pub fn chain(outer: &LHTLP, inner: &LHTLP, secret: &BigUint) -> Result<ChainedPuzzle, LhtlpError> {
+        let mut puzzle = inner.generate_biguint(secret)?;
+        let seed = rand::thread_rng().gen_biguint(SEED_BITS.min(outer.n.bits() - 1));
+        puzzle.u = (puzzle.u + chain_mask(inner, &seed)) % &inner.n;
+        Ok(ChainedPuzzle { outer: outer.generate_biguint(&seed)?, inner: puzzle })
+    }
+
+    /// Open a chain from `chain`, solving the outer puzzle to unmask the inner one, then solving it.
+    ///
+    /// Both solves are checked like `solve_checked`, under the policy of their instance.
+    pub fn solve_chained(outer: &LHTLP, inner: &LHTLP, chained: ChainedPuzzle) -> Result<BigUint, LhtlpError> {
+        let seed = outer.solve_checked(chained.outer)?;
+        let mut puzzle = chained.inner;
+        puzzle.u = (puzzle.u + &inner.n - chain_mask(inner, &seed)) % &inner.n;
+        inner.solve_checked(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn two_stage_chain() {
+        let outer = LHTLP::setup(LAMBDA, 1000u64);
+        let inner = LHTLP::setup(LAMBDA, 2000u64);
+        let secret = BigUint::from(42u32);
+        let chained = LHTLP::chain(&outer, &inner, &secret).unwrap();
+
+        assert!(LHTLP::solve_chained(&outer, &inner, chained.clone()) == Ok(secret.clone()));
+        assert!(inner.solve_checked(chained.inner.clone()) != Ok(secret.clone()));
+        assert!(LHTLP::solve_chained(&inner, &outer, chained) != Ok(secret));
+        assert!(LHTLP::chain(&outer, &inner, &inner.n) == Err(LhtlpError::InvalidSecret));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::{Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+impl LHTLP {
+    /// The secret committing to `data`: its digest under the hash suite of the instance reduced modulo `n`.
+    pub fn commitment_secret(&self, data: &[u8]) -> BigUint {
+        BigUint::from_bytes_be(&self.hash_suite.digest(data)) % &self.n
+    }
+
+    /// Timelock a commitment to `data`, returning the puzzle and the committed secret.
+    ///
+    /// With a modulus under 256 bits the digest is reduced modulo `n`, which weakens the binding
+    /// of the commitment accordingly.
+    #[must_use]
+    pub fn generate_commitment(&self, data: &[u8]) -> (Puzzle, BigUint) {
+        let secret = self.commitment_secret(data);
+        let r = self.sample_randomness(&mut rand::thread_rng());
+        (self.generate_with_randomness(&secret, &r), secret)
+    }
+
+    /// Check that a solved `secret` commits to `data`.
+    pub fn verify_commitment(&self, secret: &BigUint, data: &[u8]) -> bool {
+        secret == &self.commitment_secret(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn timelocked_commitment() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let (puzzle, digest) = lhtlp.generate_commitment(b"sealed bid: 100");
+        let secret = lhtlp.solve(puzzle);
+
+        assert!(secret == digest);
+        assert!(lhtlp.verify_commitment(&secret, b"sealed bid: 100"));
+        assert!(!lhtlp.verify_commitment(&secret, b"sealed bid: 101"));
+    }
+}
@@ -0,0 +1,41 @@
+use num_bigint::BigUint;
+use subtle::Choice;
+
+/// Whether `secret < bound`, computed in constant time.
+///
+/// Both values are encoded little-endian on the same number of bytes, the longest of the two
+/// encodings, and `bound` is subtracted from `secret` byte by byte: the final borrow is set exactly
+/// when `secret` is below `bound`. The running time depends on the lengths of the encodings only,
+/// not on their contents.
+pub fn secret_in_range_ct(secret: &BigUint, bound: &BigUint) -> Choice {
+    let mut secret = secret.to_bytes_le();
+    let mut bound = bound.to_bytes_le();
+    let width = secret.len().max(bound.len());
+    secret.resize(width, 0);
+    bound.resize(width, 0);
+
+    let mut borrow = 0u16;
+    for (a, b) in secret.iter().zip(&bound) {
+        let diff = (*a as u16).wrapping_sub(*b as u16).wrapping_sub(borrow);
+        borrow = (diff >> 15) & 1;
+    }
+    Choice::from(borrow as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_check() {
+        let cases: [(u64, u64, bool); 7] =
+            [(0, 1, true), (41, 42, true), (42, 42, false), (43, 42, false), (0, 0, false), (255, 256, true), (1 << 40, 3, false)];
+        for (secret, bound, expected) in cases {
+            let result = secret_in_range_ct(&BigUint::from(secret), &BigUint::from(bound));
+            assert!(bool::from(result) == expected, "{} < {}", secret, bound);
+        }
+        let big = BigUint::from(1u32) << 300usize;
+        assert!(bool::from(secret_in_range_ct(&(&big - 1u32), &big)));
+        assert!(!bool::from(secret_in_range_ct(&big, &(&big - 1u32))));
+    }
+}
@@ -0,0 +1,118 @@
+//! ASN.1 DER encoding of public parameters and puzzles.
+//!
+//! ```text
+//! LHTLPParams ::= SEQUENCE {
+//!     version    INTEGER,  -- 1
+//!     n          INTEGER,
+//!     g          INTEGER,
+//!     h          INTEGER,
+//!     difficulty INTEGER
+//! }
+//!
+//! LHTLPPuzzle ::= SEQUENCE {
+//!     u INTEGER,
+//!     v INTEGER
+//! }
+//! ```
+//!
+//! All fields are non-negative, so both structures are encoded as a `SEQUENCE OF INTEGER`, which
+//! has the same DER encoding.
+use crate::{LhtlpError, PublicParams, Puzzle, LHTLP};
+use der::asn1::Uint;
+use der::{Decode, Encode};
+use num_bigint::BigUint;
+
+const VERSION: u32 = 1;
+
+fn encode(values: &[&BigUint]) -> Result<Vec<u8>, LhtlpError> {
+    let integers = values
+        .iter()
+        .map(|value| Uint::new(&value.to_bytes_be()))
+        .collect::<Result<Vec<Uint>, der::Error>>()
+        .map_err(|_| LhtlpError::InvalidEncoding)?;
+    integers.to_der().map_err(|_| LhtlpError::InvalidEncoding)
+}
+
+fn decode<const N: usize>(bytes: &[u8]) -> Result<[BigUint; N], LhtlpError> {
+    let integers = Vec::<Uint>::from_der(bytes).map_err(|_| LhtlpError::InvalidEncoding)?;
+    let values: Vec<BigUint> = integers.iter().map(|integer| BigUint::from_bytes_be(integer.as_bytes())).collect();
+    values.try_into().map_err(|_| LhtlpError::InvalidEncoding)
+}
+
+impl LHTLP {
+    /// DER encoding of the public parameters as an `LHTLPParams` structure.
+    pub fn to_der(&self) -> Result<Vec<u8>, LhtlpError> {
+        encode(&[&BigUint::from(VERSION), &self.n, &self.g, &self.h, &self.difficulty])
+    }
+
+    /// Decode an instance from an `LHTLPParams` DER structure.
+    ///
+    /// Fails with `LhtlpError::InvalidEncoding` on malformed DER or an unknown version, and checks
+    /// the parameters like `from_public_params`.
+    pub fn from_der(bytes: &[u8]) -> Result<LHTLP, LhtlpError> {
+        let [version, n, g, h, difficulty] = decode(bytes)?;
+        if version != BigUint::from(VERSION) {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        LHTLP::from_public_params(PublicParams { n, g, h }, difficulty)
+    }
+}
+
+impl Puzzle {
+    /// DER encoding of the puzzle as an `LHTLPPuzzle` structure.
+    pub fn to_der(&self) -> Result<Vec<u8>, LhtlpError> {
+        encode(&[&self.u, &self.v])
+    }
+
+    /// Decode a puzzle from an `LHTLPPuzzle` DER structure.
+    pub fn from_der(bytes: &[u8]) -> Result<Puzzle, LhtlpError> {
+        let [u, v] = decode(bytes)?;
+        Ok(Puzzle { u, v })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn params_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let decoded = LHTLP::from_der(&lhtlp.to_der().unwrap()).unwrap();
+        assert!(decoded.n == lhtlp.n);
+        assert!(decoded.g == lhtlp.g);
+        assert!(decoded.h == lhtlp.h);
+        assert!(decoded.difficulty == lhtlp.difficulty);
+    }
+
+    #[test]
+    fn puzzle_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate(42);
+        let decoded = Puzzle::from_der(&puzzle.to_der().unwrap()).unwrap();
+        assert!(decoded.u == puzzle.u);
+        assert!(decoded.v == puzzle.v);
+    }
+
+    #[test]
+    fn known_encoding() {
+        let puzzle = Puzzle { u: BigUint::from(0x80u32), v: BigUint::from(0u32) };
+        let expected = [0x30, 0x07, 0x02, 0x02, 0x00, 0x80, 0x02, 0x01, 0x00];
+        assert!(puzzle.to_der().unwrap() == expected);
+        assert!(Puzzle::from_der(&expected) == Ok(puzzle));
+    }
+
+    #[test]
+    fn malformed_encodings() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let der = lhtlp.to_der().unwrap();
+        assert!(LHTLP::from_der(&der[..der.len() - 1]).is_err());
+        assert!(Puzzle::from_der(&der) == Err(LhtlpError::InvalidEncoding));
+
+        let unknown_version = encode(&[&BigUint::from(2u32), &lhtlp.n, &lhtlp.g, &lhtlp.h, &lhtlp.difficulty]).unwrap();
+        assert!(LHTLP::from_der(&unknown_version) == Err(LhtlpError::InvalidEncoding));
+    }
+}
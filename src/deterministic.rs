@@ -0,0 +1,87 @@
+use crate::{HashSuite, LhtlpError, Puzzle, LHTLP};
+use hkdf::Hkdf;
+use num_bigint::BigUint;
+use sha2::{Sha256, Sha384};
+
+impl LHTLP {
+    /// The randomness `r` of `generate_deterministic(secret, nonce)`, derived with HKDF over the hash suite of the instance.
+    ///
+    /// The input keying material is the length-prefixed `nonce`, the parameter fingerprint and
+    /// the secret; the output is reduced into `[1, n^2)`.
+    pub fn deterministic_witness(&self, secret: u64, nonce: &[u8]) -> BigUint {
+        let n2 = &self.n2;
+        let mut ikm = (nonce.len() as u64).to_be_bytes().to_vec();
+        ikm.extend_from_slice(nonce);
+        ikm.extend_from_slice(&self.fingerprint());
+        ikm.extend_from_slice(&secret.to_be_bytes());
+
+        let mut okm = vec![0u8; 2 * n2.bits().div_ceil(8) as usize];
+        let salt = Some(&b"lhtlp-deterministic-randomness"[..]);
+        match self.hash_suite {
+            HashSuite::Sha256 => Hkdf::<Sha256>::new(salt, &ikm).expand(b"r", &mut okm),
+            HashSuite::Sha384 => Hkdf::<Sha384>::new(salt, &ikm).expand(b"r", &mut okm),
+        }
+        .expect("HKDF output length is within bounds");
+        BigUint::from_bytes_be(&okm) % (n2 - 1u32) + 1u32
+    }
+
+    /// Generate a puzzle whose randomness is derived from `secret` and `nonce`.
+    ///
+    /// Resubmitting the same secret and nonce yields a byte-identical puzzle, which makes retries
+    /// idempotent, while puzzles under different nonces are unlinkable as long as the nonces are
+    /// unpredictable. The nonce acts as an opening: revealing it lets the generator prove which
+    /// secret a puzzle holds (see `open_with_witness`), and anyone who learns it can open the
+    /// puzzle without solving it, so it must be kept as secret as the puzzle content.
+    #[must_use]
+    pub fn generate_deterministic(&self, secret: u64, nonce: &[u8]) -> Puzzle {
+        self.generate_with_randomness(&BigUint::from(secret), &self.deterministic_witness(secret, nonce))
+    }
+
+    /// Open a puzzle without solving it, given the randomness `r` it was generated with.
+    ///
+    /// Since `u^(2^T) = h^r mod n`, the witness replaces the sequential squarings. Fails with
+    /// `LhtlpError::InvalidPuzzle` if `u` is not `g^r mod n`.
+    pub fn open_with_witness(&self, puzzle: &Puzzle, r: &BigUint) -> Result<BigUint, LhtlpError> {
+        if self.g.modpow(r, &self.n) != puzzle.u {
+            return Err(LhtlpError::InvalidPuzzle);
+        }
+        self.try_decode(&puzzle.v, &self.h.modpow(r, &self.n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn deterministic_generation() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate_deterministic(42, b"retry-1");
+
+        assert!(puzzle.to_bytes() == lhtlp.generate_deterministic(42, b"retry-1").to_bytes());
+        assert!(puzzle != lhtlp.generate_deterministic(42, b"retry-2"));
+        assert!(puzzle != lhtlp.generate_deterministic(43, b"retry-1"));
+        assert!(lhtlp.solve(puzzle) == BigUint::from(42u32));
+    }
+
+    #[test]
+    fn witness_opening() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate_deterministic(42, b"nonce");
+        let r = lhtlp.deterministic_witness(42, b"nonce");
+
+        assert!(lhtlp.open_with_witness(&puzzle, &r) == Ok(BigUint::from(42u32)));
+        let wrong = lhtlp.deterministic_witness(42, b"other");
+        assert!(lhtlp.open_with_witness(&puzzle, &wrong) == Err(LhtlpError::InvalidPuzzle));
+    }
+
+    #[test]
+    fn evaluate_with_randomized_puzzles() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let bundle = lhtlp.evaluate(vec![lhtlp.generate_deterministic(40, b"nonce"), lhtlp.generate(2)]);
+        assert!(lhtlp.solve(bundle) == BigUint::from(42u32));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{LHTLPEpoch, LhtlpError, LHTLP};
+use num_bigint::BigUint;
+use num_traits::{FromPrimitive, ToPrimitive};
+use std::fmt;
+use std::time::Duration;
+
+/// The difficulty of a puzzle, counted in sequential squarings.
+///
+/// Wrapping the count avoids mixing it up with a duration: durations are converted explicitly
+/// through a squaring rate. Integer and `BigUint` squaring counts convert into a `Difficulty`,
+/// so APIs taking `impl Into<Difficulty>` accept them directly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct Difficulty(BigUint);
+
+impl Difficulty {
+    /// A difficulty of `count` squarings.
+    pub fn squarings(count: u64) -> Difficulty {
+        Difficulty(BigUint::from(count))
+    }
+
+    /// The difficulty taking `duration` at `rate` squarings per second.
+    ///
+    /// Fails with `LhtlpError::InvalidRate` if the rate is negative or not finite, and with
+    /// `LhtlpError::DifficultyTooLarge` if the result does not fit in `u64` squarings, the most a
+    /// solver can perform.
+    pub fn from_rate(rate: f64, duration: Duration) -> Result<Difficulty, LhtlpError> {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(LhtlpError::InvalidRate);
+        }
+        let squarings = (rate * duration.as_secs_f64()).round();
+        if squarings >= u64::MAX as f64 {
+            // The product may not even be finite, so the reported difficulty is computed exactly.
+            let rate = BigUint::from_f64(rate.round()).unwrap_or_default();
+            return Err(LhtlpError::DifficultyTooLarge {
+                difficulty: rate * duration.as_nanos() / 1_000_000_000u32,
+                limit: BigUint::from(u64::MAX),
+            });
+        }
+        Ok(Difficulty::squarings(squarings as u64))
+    }
+
+    /// The difficulty taking `duration` on this machine, benchmarked on the modulus of `lhtlp`.
+    pub fn calibrated(lhtlp: &LHTLP, duration: Duration) -> Result<Difficulty, LhtlpError> {
+        Difficulty::from_rate(LHTLPEpoch::calibrate(lhtlp), duration)
+    }
+
+    /// The number of squarings.
+    pub fn as_squarings(&self) -> &BigUint {
+        &self.0
+    }
+
+    /// The number of squarings, if it fits in `u64`.
+    pub fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+}
+
+impl From<u64> for Difficulty {
+    fn from(count: u64) -> Difficulty {
+        Difficulty::squarings(count)
+    }
+}
+
+impl From<BigUint> for Difficulty {
+    fn from(count: BigUint) -> Difficulty {
+        Difficulty(count)
+    }
+}
+
+impl From<Difficulty> for BigUint {
+    fn from(difficulty: Difficulty) -> BigUint {
+        difficulty.0
+    }
+}
+
+/// Engineering notation: counts from a thousand up are written with an exponent that is a
+/// multiple of three, e.g. `1.5e6 squarings`.
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.0.to_string();
+        if digits.len() <= 3 {
+            return write!(f, "{} squarings", digits);
+        }
+        let exponent = (digits.len() - 1) / 3 * 3;
+        let integer = &digits[..digits.len() - exponent];
+        let fraction = digits[digits.len() - exponent..].chars().take(3).collect::<String>();
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}e{} squarings", integer, exponent)
+        } else {
+            write!(f, "{}.{}e{} squarings", integer, fraction, exponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert!(Difficulty::from(1000u64) == Difficulty::squarings(1000));
+        assert!(Difficulty::from(BigUint::from(1000u32)) == Difficulty::squarings(1000));
+        assert!(BigUint::from(Difficulty::squarings(1000)) == BigUint::from(1000u32));
+        assert!(Difficulty::squarings(1000).to_u64() == Some(1000));
+        assert!(Difficulty::from_rate(1e6, Duration::from_millis(1500)) == Ok(Difficulty::squarings(1_500_000)));
+    }
+
+    #[test]
+    fn rate_overflow() {
+        let too_large = Difficulty::from_rate(1e12, Duration::from_secs(u64::MAX / 1000));
+        assert!(matches!(too_large, Err(LhtlpError::DifficultyTooLarge { .. })));
+        let infinite = Difficulty::from_rate(f64::MAX, Duration::from_secs(u64::MAX));
+        assert!(matches!(infinite, Err(LhtlpError::DifficultyTooLarge { .. })));
+        assert!(Difficulty::from_rate(-1.0, Duration::from_secs(1)) == Err(LhtlpError::InvalidRate));
+        assert!(Difficulty::from_rate(f64::NAN, Duration::from_secs(1)) == Err(LhtlpError::InvalidRate));
+        assert!(Difficulty::from_rate(f64::INFINITY, Duration::from_secs(1)) == Err(LhtlpError::InvalidRate));
+    }
+
+    #[test]
+    fn engineering_notation() {
+        assert!(Difficulty::squarings(0).to_string() == "0 squarings");
+        assert!(Difficulty::squarings(999).to_string() == "999 squarings");
+        assert!(Difficulty::squarings(1000).to_string() == "1e3 squarings");
+        assert!(Difficulty::squarings(1_500_000).to_string() == "1.5e6 squarings");
+        assert!(Difficulty::squarings(100_000_000).to_string() == "100e6 squarings");
+        assert!(Difficulty::squarings(12_345_678_901).to_string() == "12.345e9 squarings");
+    }
+
+    #[test]
+    fn calibrated() {
+        let lhtlp = LHTLP::setup(64, 1000u64);
+        let difficulty = Difficulty::calibrated(&lhtlp, Duration::from_millis(10)).unwrap();
+        assert!(difficulty > Difficulty::squarings(0));
+    }
+}
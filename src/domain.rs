@@ -0,0 +1,80 @@
+use crate::vdf::expand;
+use crate::{LhtlpError, PartialSolution, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// Size in bits of the exponent binding a puzzle to its domain.
+const DOMAIN_EXPONENT_BITS: usize = 256;
+
+impl LHTLP {
+    /// Exponent `e` derived from `domain`, applied to the `h^(r*n)` mask of domain-separated puzzles.
+    fn domain_exponent(&self, domain: &[u8]) -> BigUint {
+        expand(self.hash_suite, &[b"lhtlp-domain-exponent", domain], DOMAIN_EXPONENT_BITS) | BigUint::from(1u32)
+    }
+
+    /// Generate a puzzle bound to a protocol `domain`, so that it cannot be replayed in another one.
+    ///
+    /// The sampled randomness is hashed together with the domain, and the mask of `v` is raised
+    /// to an exponent derived from the domain: the puzzle is `(g^r, h^(r*n*e) * (1+n)^secret)`.
+    /// It only opens to `secret` with `solve_with_domain` under the same domain. Puzzles of a
+    /// domain remain homomorphic among themselves. Fails with `LhtlpError::InvalidSecret` if
+    /// `secret` does not fit the plaintext space.
+    pub fn generate_with_domain(&self, secret: &BigUint, domain: &[u8]) -> Result<Puzzle, LhtlpError> {
+        if !self.plaintext_space().fits(secret) {
+            return Err(LhtlpError::InvalidSecret);
+        }
+        let n2 = &self.n2;
+        let r_raw = self.sample_randomness(&mut rand::thread_rng());
+        let r = expand(self.hash_suite, &[b"lhtlp-domain-randomness", domain, &r_raw.to_bytes_be()], 2 * n2.bits() as usize) % (n2 - 1u32) + 1u32;
+        let u = self.g.modpow(&r, &self.n);
+        let mask = self.h.modpow(&(&r * &self.n * self.domain_exponent(domain)), n2);
+        let v = mask * self.plaintext_power(secret, n2) % n2;
+        Ok(Puzzle { u, v })
+    }
+
+    /// Open a puzzle generated with `generate_with_domain` under `domain`.
+    ///
+    /// Under any other domain the result is unrelated to the secret. The puzzle is checked and
+    /// solved like in `solve_checked`, under the policy of the instance.
+    pub fn solve_with_domain(&self, puzzle: Puzzle, domain: &[u8]) -> Result<BigUint, LhtlpError> {
+        self.verify_puzzle(&puzzle)?;
+        let state = self.advance_with_policy(PartialSolution::new(puzzle), &self.policy, &mut 0)?;
+        let w = state.w.modpow(&self.domain_exponent(domain), &self.n);
+        self.try_decode(&state.puzzle.v, &w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn domain_separation() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let secret = BigUint::from(42u32);
+        let puzzle = lhtlp.generate_with_domain(&secret, b"voting").unwrap();
+
+        assert!(lhtlp.solve_with_domain(puzzle.clone(), b"voting") == Ok(secret.clone()));
+        assert!(lhtlp.solve_with_domain(puzzle.clone(), b"bidding") != Ok(secret.clone()));
+        assert!(lhtlp.solve(puzzle) != secret);
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        assert!(lhtlp.generate_with_domain(&lhtlp.n, b"voting") == Err(LhtlpError::InvalidSecret));
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.solve_with_domain(malformed, b"voting") == Err(LhtlpError::InvalidPuzzle));
+    }
+
+    #[test]
+    fn homomorphic_within_domain() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let first = lhtlp.generate_with_domain(&BigUint::from(1u32), b"voting").unwrap();
+        let second = lhtlp.generate_with_domain(&BigUint::from(2u32), b"voting").unwrap();
+        let sum = lhtlp.add(&first, &second);
+        assert!(lhtlp.solve_with_domain(sum, b"voting") == Ok(BigUint::from(3u32)));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Difficulty, HashSuite, LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A self-describing puzzle, carrying the metadata needed to store or transport it.
+///
+/// The envelope binds a puzzle to the difficulty and the parameter fingerprint of the instance
+/// that generated it, together with its creation time (seconds since the UNIX epoch) and an
+/// optional free-form label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PuzzleEnvelope {
+    pub puzzle: Puzzle,
+    pub difficulty: Difficulty,
+    pub fingerprint: Vec<u8>,
+    /// The hash suite of `fingerprint`.
+    pub hash_suite: HashSuite,
+    pub created_at: u64,
+    pub label: Option<String>,
+}
+
+impl PuzzleEnvelope {
+    /// Wrap a `puzzle` generated by `lhtlp`, taking the difficulty and fingerprint from the instance.
+    pub fn new(lhtlp: &LHTLP, puzzle: Puzzle) -> PuzzleEnvelope {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        PuzzleEnvelope {
+            puzzle,
+            difficulty: lhtlp.difficulty.clone().into(),
+            fingerprint: lhtlp.fingerprint(),
+            hash_suite: lhtlp.hash_suite(),
+            created_at,
+            label: None,
+        }
+    }
+
+    /// Wrap a `puzzle` generated by `lhtlp` and attach a `label` to it.
+    pub fn with_label(lhtlp: &LHTLP, puzzle: Puzzle, label: impl Into<String>) -> PuzzleEnvelope {
+        PuzzleEnvelope {
+            label: Some(label.into()),
+            ..PuzzleEnvelope::new(lhtlp, puzzle)
+        }
+    }
+}
+
+impl LHTLP {
+    /// Validate an `envelope` against this instance and take out its puzzle.
+    ///
+    /// Fails with `LhtlpError::UnsupportedHashSuite` if the envelope was fingerprinted under
+    /// another hash suite, with `LhtlpError::DifficultyMismatch` if the difficulty declared by the
+    /// envelope is not the one of this instance, with `LhtlpError::ParameterMismatch` if the
+    /// envelope was produced by an instance with other parameters, and with
+    /// `LhtlpError::InvalidPuzzle` if the puzzle is not well-formed.
+    pub fn open_envelope(&self, envelope: &PuzzleEnvelope) -> Result<Puzzle, LhtlpError> {
+        self.hash_suite.check(envelope.hash_suite)?;
+        if envelope.difficulty.as_squarings() != &self.difficulty {
+            return Err(LhtlpError::DifficultyMismatch {
+                expected: self.difficulty.clone(),
+                found: envelope.difficulty.clone().into(),
+            });
+        }
+        if envelope.fingerprint != self.fingerprint() {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        self.verify_puzzle(&envelope.puzzle)?;
+        Ok(envelope.puzzle.clone())
+    }
+
+    /// Open the puzzle contained in an `envelope`.
+    ///
+    /// The envelope is validated like in `open_envelope` before performing any squaring. The solve
+    /// policy attached to the instance is enforced as in `solve_checked`.
+    pub fn solve_envelope(&self, envelope: PuzzleEnvelope) -> Result<BigUint, LhtlpError> {
+        let puzzle = self.open_envelope(&envelope)?;
+        self.solve_checked(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn envelope_metadata() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let envelope = PuzzleEnvelope::with_label(&lhtlp, lhtlp.generate(42), "bid");
+
+        assert!(envelope.difficulty == Difficulty::squarings(DIFFICULTY));
+        assert!(envelope.fingerprint == lhtlp.fingerprint());
+        assert!(envelope.label.as_deref() == Some("bid"));
+        assert!(envelope.created_at > 0);
+    }
+
+    #[test]
+    fn solve_envelope() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let envelope = PuzzleEnvelope::new(&lhtlp, lhtlp.generate(42));
+        assert!(lhtlp.solve_envelope(envelope) == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn solve_envelope_difficulty_mismatch() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut envelope = PuzzleEnvelope::new(&lhtlp, lhtlp.generate(42));
+        envelope.difficulty = Difficulty::squarings(DIFFICULTY + 1);
+        assert!(matches!(lhtlp.solve_envelope(envelope), Err(LhtlpError::DifficultyMismatch { .. })));
+    }
+
+    #[test]
+    fn solve_envelope_parameter_mismatch() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let other = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let envelope = PuzzleEnvelope::new(&other, other.generate(42));
+        assert!(lhtlp.solve_envelope(envelope) == Err(LhtlpError::ParameterMismatch));
+
+        let slower = LHTLP::setup(LAMBDA, BigUint::from(2 * DIFFICULTY));
+        let envelope = PuzzleEnvelope::new(&slower, slower.generate(42));
+        assert!(matches!(lhtlp.solve_envelope(envelope), Err(LhtlpError::DifficultyMismatch { .. })));
+    }
+
+    #[test]
+    fn solve_envelope_hash_suite() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let sha384 = lhtlp.clone().with_hash_suite(HashSuite::Sha384);
+
+        let envelope = PuzzleEnvelope::new(&sha384, sha384.generate(42));
+        assert!(envelope.hash_suite == HashSuite::Sha384);
+        assert!(sha384.solve_envelope(envelope.clone()) == Ok(BigUint::from(42u32)));
+        assert!(lhtlp.solve_envelope(envelope) == Err(LhtlpError::UnsupportedHashSuite));
+
+        let envelope = PuzzleEnvelope::new(&lhtlp, lhtlp.generate(42));
+        assert!(sha384.solve_envelope(envelope) == Err(LhtlpError::UnsupportedHashSuite));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn envelope_serde_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let envelope = PuzzleEnvelope::with_label(&lhtlp, lhtlp.generate(42), "bid");
+        let json = serde_json::to_string(&envelope).unwrap();
+        let decoded: PuzzleEnvelope = serde_json::from_str(&json).unwrap();
+        assert!(decoded == envelope);
+        assert!(lhtlp.solve_envelope(decoded) == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn open_envelope() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        assert!(lhtlp.open_envelope(&PuzzleEnvelope::new(&lhtlp, puzzle.clone())) == Ok(puzzle.clone()));
+
+        let other = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(other.open_envelope(&PuzzleEnvelope::new(&lhtlp, puzzle.clone())) == Err(LhtlpError::ParameterMismatch));
+
+        let mut envelope = PuzzleEnvelope::new(&lhtlp, puzzle);
+        envelope.difficulty = Difficulty::squarings(DIFFICULTY + 1);
+        assert!(matches!(lhtlp.open_envelope(&envelope), Err(LhtlpError::DifficultyMismatch { .. })));
+
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.open_envelope(&PuzzleEnvelope::new(&lhtlp, malformed)) == Err(LhtlpError::InvalidPuzzle));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{Difficulty, HTable, LhtlpError, PrivateKey, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::time::{Duration, Instant};
+
+/// Puzzle generation for a target unlock time rather than a fixed difficulty.
+///
+/// The difficulty of each puzzle is the time left until its unlock multiplied by the measured
+/// `squarings_per_second`, rounded up to a whole tier of `tier_squarings`. Puzzles of one tier
+/// share `h = g^(2^difficulty) mod n`, computed once: with the trapdoor in a single
+/// exponentiation, and otherwise by continuing the squarings of `table` from the previous tier.
+/// A puzzle is opened with `lhtlp_base.solve_with_difficulty` at the difficulty returned with it.
+#[derive(Debug, Clone)]
+pub struct LHTLPEpoch {
+    pub lhtlp_base: LHTLP,
+    pub epoch_start: Instant,
+    pub squarings_per_second: f64,
+    /// Granularity of the difficulties, one second of squarings by default.
+    pub tier_squarings: u64,
+    key: Option<PrivateKey>,
+    table: HTable,
+}
+
+impl LHTLPEpoch {
+    /// Start an epoch now over `lhtlp_base`.
+    pub fn new(lhtlp_base: LHTLP, squarings_per_second: f64) -> LHTLPEpoch {
+        let table = HTable::new(lhtlp_base.n.clone(), lhtlp_base.g.clone());
+        LHTLPEpoch {
+            lhtlp_base,
+            epoch_start: Instant::now(),
+            squarings_per_second,
+            tier_squarings: tier_squarings(squarings_per_second, Duration::from_secs(1)),
+            key: None,
+            table,
+        }
+    }
+
+    /// Round the difficulties up to a multiple of the squarings done in `tier`.
+    pub fn with_tier(mut self, tier: Duration) -> LHTLPEpoch {
+        self.tier_squarings = tier_squarings(self.squarings_per_second, tier);
+        self
+    }
+
+    /// Compute `h` of each tier with the trapdoor instead of by sequential squaring.
+    ///
+    /// Fails with `LhtlpError::ParameterMismatch` if `key` is not the factorization of `n`.
+    pub fn with_trapdoor(mut self, key: PrivateKey) -> Result<LHTLPEpoch, LhtlpError> {
+        if key.modulus() != self.lhtlp_base.n {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        self.key = Some(key);
+        Ok(self)
+    }
+
+    /// Benchmark the squarings per second modulo the modulus of `lhtlp` on this machine.
+    pub fn calibrate(lhtlp: &LHTLP) -> f64 {
+        let squaring = lhtlp.time_squaring(&lhtlp.g);
+        if squaring.is_zero() {
+            return f64::MAX;
+        }
+        1.0 / squaring.as_secs_f64()
+    }
+
+    /// The difficulty taking until `target_unlock` to solve, from now.
+    pub fn difficulty_for(&self, target_unlock: Instant) -> BigUint {
+        let remaining = target_unlock.saturating_duration_since(Instant::now());
+        BigUint::from((remaining.as_secs_f64() * self.squarings_per_second) as u64)
+    }
+
+    /// Generate a puzzle of `secret` that opens around `target_unlock`, returned with its difficulty.
+    ///
+    /// The difficulty is that of `difficulty_for`, rounded up to a whole tier. The first puzzle of
+    /// a tier computes its `h`; without the trapdoor this takes the squarings from the largest tier
+    /// computed so far. Fails with `LhtlpError::InvalidSecret` if the secret does not fit the
+    /// plaintext space and with `LhtlpError::DifficultyTooLarge` if the rounded difficulty does
+    /// not fit in `u64`.
+    pub fn generate_for_target_time(&mut self, secret: &BigUint, target_unlock: Instant) -> Result<(Puzzle, BigUint), LhtlpError> {
+        let difficulty = self.difficulty_for(target_unlock);
+        let squarings = difficulty
+            .to_u64()
+            .and_then(|squarings| squarings.div_ceil(self.tier_squarings).checked_mul(self.tier_squarings))
+            .ok_or_else(|| LhtlpError::DifficultyTooLarge { difficulty, limit: BigUint::from(u64::MAX) })?;
+        let h = match &self.key {
+            Some(key) if !self.table.contains(squarings) => {
+                let exponent = BigUint::from(2u32).modpow(&BigUint::from(squarings), &key.phi_half());
+                let h = self.lhtlp_base.g.modpow(&exponent, &self.lhtlp_base.n);
+                self.table.insert(squarings, h.clone());
+                h
+            }
+            _ => self.table.h_for(squarings),
+        };
+        let tier = LHTLP { difficulty: BigUint::from(squarings), h, ..self.lhtlp_base.clone() };
+        Ok((tier.generate_biguint(secret)?, tier.difficulty))
+    }
+}
+
+/// Squarings done in `tier` at `squarings_per_second`, at least one.
+fn tier_squarings(squarings_per_second: f64, tier: Duration) -> u64 {
+    (squarings_per_second * tier.as_secs_f64()).ceil().clamp(1.0, u64::MAX as f64) as u64
+}
+
+impl LHTLP {
+    /// Generate a puzzle of `secret` for a `difficulty` at least the one of the instance.
+    ///
+    /// The puzzle is generated at the difficulty of the instance and rebased, so generation costs
+    /// the difference in squarings modulo `n^2`; `LHTLPEpoch` shares `h` between the puzzles of
+    /// one difficulty instead, for many puzzles. Fails with `LhtlpError::InvalidSecret` if the
+    /// secret does not fit the plaintext space and with `LhtlpError::DifficultyTooSmall` if
+    /// `difficulty` is below the one of the instance.
+    pub fn generate_with_difficulty(&self, secret: &BigUint, difficulty: impl Into<Difficulty>) -> Result<Puzzle, LhtlpError> {
+        let difficulty = BigUint::from(difficulty.into());
+        if difficulty < self.difficulty {
+            return Err(LhtlpError::DifficultyTooSmall { difficulty, minimum: self.difficulty.clone() });
+        }
+        let puzzle = self.generate_biguint(secret)?;
+        Ok(self.rebase(&puzzle, &(&difficulty - &self.difficulty)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::fixed_instance;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn target_time_difficulty() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut epoch = LHTLPEpoch::new(lhtlp.clone(), 1000.0);
+        let target = Instant::now() + Duration::from_secs(5);
+
+        let difficulty = epoch.difficulty_for(target);
+        assert!(difficulty <= BigUint::from(5000u32) && difficulty >= BigUint::from(4900u32));
+
+        let (puzzle, difficulty) = epoch.generate_for_target_time(&BigUint::from(42u32), target).unwrap();
+        assert!(difficulty == BigUint::from(5000u32));
+        assert!(lhtlp.solve_with_difficulty(puzzle, &difficulty) == BigUint::from(42u32));
+    }
+
+    #[test]
+    fn tiers_share_h() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut epoch = LHTLPEpoch::new(lhtlp.clone(), 1000.0).with_tier(Duration::from_millis(500));
+        let target = Instant::now() + Duration::from_secs(3);
+        let (first, difficulty) = epoch.generate_for_target_time(&BigUint::from(1u32), target).unwrap();
+        let (second, same) = epoch.generate_for_target_time(&BigUint::from(2u32), target).unwrap();
+        assert!(difficulty == same && epoch.table.difficulties().count() == 1);
+        let sum = lhtlp.evaluate(vec![first, second]);
+        assert!(lhtlp.solve_with_difficulty(sum, &difficulty) == BigUint::from(3u32));
+
+        // An unlock due now needs no squaring at all.
+        let (now, zero) = epoch.generate_for_target_time(&BigUint::from(42u32), Instant::now()).unwrap();
+        assert!(zero == BigUint::from(0u32));
+        assert!(lhtlp.solve_with_difficulty(now, &zero) == BigUint::from(42u32));
+    }
+
+    #[test]
+    fn tiers_with_trapdoor() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(DIFFICULTY));
+        let target = Instant::now() + Duration::from_secs(3);
+        let mut with_key = LHTLPEpoch::new(lhtlp.clone(), 1000.0).with_trapdoor(key.clone()).unwrap();
+        let (puzzle, difficulty) = with_key.generate_for_target_time(&BigUint::from(42u32), target).unwrap();
+        assert!(lhtlp.solve_with_difficulty(puzzle, &difficulty) == BigUint::from(42u32));
+        let squarings = difficulty.to_u64().unwrap();
+        let mut scratch = HTable::new(lhtlp.n.clone(), lhtlp.g.clone());
+        assert!(with_key.table.h_for(squarings) == scratch.h_for(squarings));
+
+        let other = PrivateKey::new(BigUint::from(1019u32), key.q().clone());
+        assert!(LHTLPEpoch::new(lhtlp, 1000.0).with_trapdoor(other).is_err());
+    }
+
+    #[test]
+    fn generate_with_difficulty() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let difficulty = BigUint::from(3 * DIFFICULTY);
+        let puzzle = lhtlp.generate_with_difficulty(&BigUint::from(42u32), difficulty.clone()).unwrap();
+        assert!(lhtlp.solve_with_difficulty(puzzle, &difficulty) == BigUint::from(42u32));
+        let below = lhtlp.generate_with_difficulty(&BigUint::from(42u32), DIFFICULTY - 1);
+        assert!(matches!(below, Err(LhtlpError::DifficultyTooSmall { .. })));
+    }
+
+    #[test]
+    fn calibrate() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(LHTLPEpoch::calibrate(&lhtlp) > 0.0);
+    }
+}
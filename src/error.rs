@@ -0,0 +1,154 @@
+use crate::PartialSolution;
+use num_bigint::BigUint;
+use std::fmt;
+
+/// Errors returned by the checked LHTLP operations.
+///
+/// New variants are added as features land, so matches outside the crate need a catch-all arm:
+///
+/// ```
+/// use lhtlp::LhtlpError;
+///
+/// fn describe(err: &LhtlpError) -> &'static str {
+///     match err {
+///         LhtlpError::InvalidPuzzle => "malformed puzzle",
+///         LhtlpError::DifficultyMismatch { .. } => "puzzle from another difficulty",
+///         _ => "other error",
+///     }
+/// }
+/// assert!(describe(&LhtlpError::EmptyInput) == "other error");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LhtlpError {
+    /// The puzzle declares a difficulty different from the one of the instance solving it.
+    DifficultyMismatch { expected: BigUint, found: BigUint },
+    /// An aggregation was requested over no puzzles.
+    EmptyInput,
+    /// The number of weights differs from the number of puzzles being aggregated.
+    WeightCountMismatch,
+    /// The bound on an aggregated secret reaches the plaintext modulus `n`, so the sum could wrap.
+    Overflow,
+    /// A byte encoding is truncated or has trailing data.
+    InvalidEncoding,
+    /// Reading or writing persistent storage failed.
+    Io(std::io::ErrorKind),
+    /// The difficulty exceeds the limit allowed by the solve policy.
+    DifficultyTooLarge { difficulty: BigUint, limit: BigUint },
+    /// The modulus exceeds the size allowed by the solve policy.
+    ModulusTooLarge { bits: u64, limit: u64 },
+    /// Solving was aborted when the wall-clock limit of the solve policy was hit.
+    ///
+    /// The partial solution can be handed back to the solver to resume.
+    DeadlineExceeded(Box<PartialSolution>),
+    /// Envelopes at these indices carry a parameter fingerprint that no registered instance has.
+    UnknownFingerprint { indices: Vec<usize> },
+    /// Envelopes at these indices failed validation against their instance.
+    InvalidEnvelopes { indices: Vec<usize> },
+    /// An imported key cannot be turned into LHTLP parameters.
+    UnsupportedKey,
+    /// Stored data was produced under the parameters of another instance.
+    ParameterMismatch,
+    /// No puzzle is stored at the requested index.
+    IndexOutOfRange { index: u64, len: u64 },
+    /// A secret cannot be encoded in a puzzle.
+    InvalidSecret,
+    /// A solution cannot be converted to the requested type without losing precision.
+    PrecisionLoss,
+    /// The requested difficulty is below the minimum the instance can produce.
+    DifficultyTooSmall { difficulty: BigUint, minimum: BigUint },
+    /// A puzzle component is out of range or not coprime to the modulus.
+    InvalidPuzzle,
+    /// The proofs at these indices failed verification.
+    InvalidProofs { indices: Vec<usize> },
+    /// An inverse modulo `n` did not exist, exposing `gcd`, a common factor of `n` and some value.
+    ///
+    /// The modulus is broken, or an attacker crafted a value sharing a factor with it.
+    ModulusCompromised { gcd: BigUint },
+    /// The modulus is a perfect power, whose root reveals its factorization.
+    PerfectPower,
+    /// The same puzzle was given more than once.
+    DuplicatePuzzle,
+    /// An artifact was produced under another hash suite than the one of the instance.
+    UnsupportedHashSuite,
+    /// Puzzle groups of different lengths were combined.
+    GroupSizeMismatch,
+    /// A ciphertext failed authentication under the key recovered from its puzzle.
+    DecryptionFailed,
+    /// An external squaring oracle disagreed with the built-in squaring modulo `n`.
+    InvalidOracle,
+    /// A tally was finalized with fewer puzzles than its reveal threshold.
+    ThresholdNotMet { count: usize, threshold: usize },
+    /// A secret or bound of `bits` bits exceeds the `limit` bits a bound check can compare.
+    BoundCheckRange { bits: u64, limit: u64 },
+    /// A squaring rate is negative or not finite.
+    InvalidRate,
+    /// The parameters of an instance break an invariant, such as an even `n` or a stale cached `n^2`.
+    InconsistentParameters,
+    /// The modulus is shorter than the size expected of the instance.
+    ModulusTooSmall { bits: u64, minimum: u64 },
+}
+
+impl From<std::io::Error> for LhtlpError {
+    fn from(err: std::io::Error) -> Self {
+        LhtlpError::Io(err.kind())
+    }
+}
+
+impl fmt::Display for LhtlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LhtlpError::DifficultyMismatch { expected, found } => {
+                write!(f, "difficulty mismatch: instance has {}, puzzle declares {}", expected, found)
+            }
+            LhtlpError::EmptyInput => write!(f, "no puzzles to aggregate"),
+            LhtlpError::WeightCountMismatch => write!(f, "number of weights does not match number of puzzles"),
+            LhtlpError::Overflow => write!(f, "aggregated secret bound exceeds the plaintext modulus"),
+            LhtlpError::InvalidEncoding => write!(f, "invalid byte encoding"),
+            LhtlpError::Io(kind) => write!(f, "i/o error: {}", kind),
+            LhtlpError::DifficultyTooLarge { difficulty, limit } => {
+                write!(f, "difficulty {} exceeds the limit of {}", difficulty, limit)
+            }
+            LhtlpError::ModulusTooLarge { bits, limit } => {
+                write!(f, "modulus of {} bits exceeds the limit of {} bits", bits, limit)
+            }
+            LhtlpError::DeadlineExceeded(partial) => {
+                write!(f, "solve deadline exceeded after {} squarings", partial.squarings)
+            }
+            LhtlpError::UnknownFingerprint { indices } => {
+                write!(f, "unknown parameter fingerprint for puzzles at indices {:?}", indices)
+            }
+            LhtlpError::InvalidEnvelopes { indices } => write!(f, "invalid envelopes at indices {:?}", indices),
+            LhtlpError::UnsupportedKey => write!(f, "key cannot be used as LHTLP parameters"),
+            LhtlpError::ParameterMismatch => write!(f, "data belongs to an instance with other parameters"),
+            LhtlpError::IndexOutOfRange { index, len } => write!(f, "index {} out of range for {} puzzles", index, len),
+            LhtlpError::InvalidSecret => write!(f, "secret cannot be encoded in a puzzle"),
+            LhtlpError::PrecisionLoss => write!(f, "solution does not fit the requested type without precision loss"),
+            LhtlpError::DifficultyTooSmall { difficulty, minimum } => {
+                write!(f, "difficulty {} is below the minimum of {}", difficulty, minimum)
+            }
+            LhtlpError::InvalidPuzzle => write!(f, "puzzle is not well-formed for this instance"),
+            LhtlpError::InvalidProofs { indices } => write!(f, "invalid proofs at indices {:?}", indices),
+            LhtlpError::ModulusCompromised { gcd } => write!(f, "modulus compromised: found common factor {}", gcd),
+            LhtlpError::PerfectPower => write!(f, "modulus is a perfect power"),
+            LhtlpError::DuplicatePuzzle => write!(f, "duplicate puzzle"),
+            LhtlpError::UnsupportedHashSuite => write!(f, "unsupported hash suite"),
+            LhtlpError::GroupSizeMismatch => write!(f, "puzzle groups have different lengths"),
+            LhtlpError::DecryptionFailed => write!(f, "ciphertext failed authentication"),
+            LhtlpError::InvalidOracle => write!(f, "squaring oracle returned a wrong square"),
+            LhtlpError::ThresholdNotMet { count, threshold } => {
+                write!(f, "tally of {} puzzles is below the reveal threshold of {}", count, threshold)
+            }
+            LhtlpError::BoundCheckRange { bits, limit } => {
+                write!(f, "value of {} bits exceeds the {}-bit range of bound checks", bits, limit)
+            }
+            LhtlpError::InvalidRate => write!(f, "squaring rate must be finite and non-negative"),
+            LhtlpError::InconsistentParameters => write!(f, "instance parameters are inconsistent"),
+            LhtlpError::ModulusTooSmall { bits, minimum } => {
+                write!(f, "modulus of {} bits is below the minimum of {} bits", bits, minimum)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LhtlpError {}
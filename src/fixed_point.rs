@@ -0,0 +1,80 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+/// Number of fractional bits of the fixed-point encoding of `f64` secrets.
+const FRACTIONAL_BITS: i32 = 32;
+
+/// Largest integer up to which every integer is exactly representable as an `f64`.
+const F64_EXACT_LIMIT: u64 = 1 << 53;
+
+impl LHTLP {
+    /// Generate a puzzle embedding a non-negative `secret` as the fixed-point value `secret * 2^32`.
+    ///
+    /// Fractional bits below `2^-32` are truncated. Fails with `LhtlpError::InvalidSecret` if
+    /// `secret` is negative, not finite or does not fit in 64 bits once scaled.
+    pub fn generate_f64(&self, secret: f64) -> Result<Puzzle, LhtlpError> {
+        let scaled = secret * 2f64.powi(FRACTIONAL_BITS);
+        if !scaled.is_finite() || scaled < 0.0 || scaled >= u64::MAX as f64 {
+            return Err(LhtlpError::InvalidSecret);
+        }
+        Ok(self.generate(scaled as u64))
+    }
+
+    /// Open a puzzle produced by `generate_f64`, or an evaluation of such puzzles.
+    ///
+    /// Fails with `LhtlpError::PrecisionLoss` if the fixed-point solution exceeds `2^53`, above
+    /// which it cannot be converted to `f64` exactly.
+    pub fn solve_as_f64_checked(&self, puzzle: Puzzle) -> Result<f64, LhtlpError> {
+        let solution = self.solve(puzzle);
+        if solution > BigUint::from(F64_EXACT_LIMIT) {
+            return Err(LhtlpError::PrecisionLoss);
+        }
+        Ok(fixed_to_f64(&solution))
+    }
+
+    /// Open a puzzle produced by `generate_f64`, rounding the solution to the nearest `f64`.
+    ///
+    /// Logs a warning when the conversion loses precision.
+    pub fn solve_as_f64_lossy(&self, puzzle: Puzzle) -> f64 {
+        let solution = self.solve(puzzle);
+        if solution > BigUint::from(F64_EXACT_LIMIT) {
+            log::warn!("solution of {} bits loses precision when converted to f64", solution.bits());
+        }
+        fixed_to_f64(&solution)
+    }
+}
+
+fn fixed_to_f64(solution: &BigUint) -> f64 {
+    solution.to_f64().unwrap_or(f64::INFINITY) / 2f64.powi(FRACTIONAL_BITS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn f64_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let bundle = lhtlp.evaluate(vec![lhtlp.generate_f64(1.5).unwrap(), lhtlp.generate_f64(0.25).unwrap()]);
+        assert!(lhtlp.solve_as_f64_checked(bundle) == Ok(1.75));
+
+        assert!(lhtlp.generate_f64(-1.0) == Err(LhtlpError::InvalidSecret));
+        assert!(lhtlp.generate_f64(f64::NAN) == Err(LhtlpError::InvalidSecret));
+        assert!(lhtlp.generate_f64(2f64.powi(32)) == Err(LhtlpError::InvalidSecret));
+    }
+
+    #[test]
+    fn precision_loss() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let exact = lhtlp.generate(F64_EXACT_LIMIT);
+        assert!(lhtlp.solve_as_f64_checked(exact) == Ok(2f64.powi(21)));
+
+        let inexact = lhtlp.generate(F64_EXACT_LIMIT + 1);
+        assert!(lhtlp.solve_as_f64_checked(inexact.clone()) == Err(LhtlpError::PrecisionLoss));
+        assert!(lhtlp.solve_as_f64_lossy(inexact) == 2f64.powi(21));
+    }
+}
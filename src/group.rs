@@ -0,0 +1,125 @@
+use crate::{LhtlpError, Puzzle, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+
+/// Puzzles generated together with one shared randomness `r`, hence one shared `u = g^r`.
+///
+/// A single squaring chain on `u` opens every puzzle of the group, which is the point of grouping.
+///
+/// **Sharing `r` leaks the differences between the secrets of a group to anyone, without any
+/// squaring:** `v_i / v_j = (1+n)^(s_i - s_j) mod n^2` decodes to `s_i - s_j`. Group only secrets
+/// whose pairwise differences may be disclosed before the puzzles are opened.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PuzzleGroup {
+    pub u: BigUint,
+    pub vs: Vec<BigUint>,
+}
+
+impl PuzzleGroup {
+    /// The number of puzzles in the group.
+    pub fn len(&self) -> usize {
+        self.vs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vs.is_empty()
+    }
+
+    /// The puzzle at `index`, as a standalone puzzle.
+    pub fn puzzle(&self, index: usize) -> Option<Puzzle> {
+        self.vs.get(index).map(|v| Puzzle { u: self.u.clone(), v: v.clone() })
+    }
+}
+
+impl LHTLP {
+    /// Generate a group of puzzles, one per secret, all sharing the same randomness.
+    ///
+    /// See `PuzzleGroup` for what sharing the randomness reveals.
+    #[must_use]
+    pub fn generate_group(&self, secrets: &[u64]) -> PuzzleGroup {
+        let r = self.sample_randomness(&mut rand::thread_rng());
+        let n2 = &self.n2;
+        let mask = self.h.modpow(&(&r * &self.n), n2);
+        let vs = secrets
+            .iter()
+            .map(|&secret| &mask * self.plaintext_power(&BigUint::from(secret), n2) % n2)
+            .collect();
+        PuzzleGroup { u: self.g.modpow(&r, &self.n), vs }
+    }
+
+    /// Open every puzzle of `group` with a single squaring chain.
+    #[must_use]
+    pub fn solve_group(&self, group: &PuzzleGroup) -> Vec<BigUint> {
+        self.try_solve_group(group, &mut 0).unwrap_or_else(|err| panic!("cannot decode puzzle: {}", err))
+    }
+
+    /// Open a group like `solve_group`, failing with `LhtlpError::ModulusCompromised` instead of
+    /// panicking and adding the squarings performed to `squarings`.
+    ///
+    /// The mask is inverted once and shared by every item of the group.
+    pub(crate) fn try_solve_group(&self, group: &PuzzleGroup, squarings: &mut u64) -> Result<Vec<BigUint>, LhtlpError> {
+        let w = LHTLP::square_in_chunks(&group.u, &self.difficulty, &self.n, SQUARING_CHUNK, squarings);
+        let unmask = self.unmask(&w)?;
+        Ok(group.vs.iter().map(|v| self.decode_unmasked(v, &unmask)).collect())
+    }
+
+    /// Homomorphically add two groups item by item.
+    ///
+    /// The result shares randomness like its inputs. Fails with `LhtlpError::GroupSizeMismatch`
+    /// if the groups have different lengths.
+    pub fn add_groups(&self, first: &PuzzleGroup, second: &PuzzleGroup) -> Result<PuzzleGroup, LhtlpError> {
+        if first.len() != second.len() {
+            return Err(LhtlpError::GroupSizeMismatch);
+        }
+        let n2 = &self.n2;
+        Ok(PuzzleGroup {
+            u: &first.u * &second.u % &self.n,
+            vs: first.vs.iter().zip(&second.vs).map(|(a, b)| a * b % n2).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn solve_group() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let secrets: Vec<u64> = (0..10).map(|i| i * 1000 + 7).collect();
+        let group = lhtlp.generate_group(&secrets);
+        assert!(group.len() == 10);
+
+        let solutions = lhtlp.solve_group(&group);
+        assert!(solutions == secrets.iter().map(|&s| BigUint::from(s)).collect::<Vec<_>>());
+        assert!(lhtlp.solve(group.puzzle(3).unwrap()) == BigUint::from(3007u32));
+        assert!(group.puzzle(10).is_none());
+    }
+
+    #[test]
+    fn add_groups() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let first = lhtlp.generate_group(&[1, 2, 3]);
+        let second = lhtlp.generate_group(&[10, 20, 30]);
+        let sum = lhtlp.add_groups(&first, &second).unwrap();
+
+        assert!(lhtlp.solve_group(&sum) == vec![BigUint::from(11u32), BigUint::from(22u32), BigUint::from(33u32)]);
+        for i in 0..3 {
+            let expected = lhtlp.add(&first.puzzle(i).unwrap(), &second.puzzle(i).unwrap());
+            assert!(sum.puzzle(i).unwrap() == expected);
+        }
+        assert!(lhtlp.add_groups(&first, &lhtlp.generate_group(&[1])) == Err(LhtlpError::GroupSizeMismatch));
+    }
+
+    #[test]
+    fn shared_randomness_leaks_differences() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let group = lhtlp.generate_group(&[50, 8]);
+        let n2 = &lhtlp.n2;
+        let ratio = &group.vs[0] * group.vs[1].modinv(n2).unwrap() % n2;
+        assert!((ratio - 1u32) / &lhtlp.n == BigUint::from(42u32));
+    }
+}
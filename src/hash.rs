@@ -0,0 +1,103 @@
+use crate::{LhtlpError, LHTLP};
+use sha2::{Digest, Sha256, Sha384};
+
+/// Hash function used for parameter fingerprints and the other hashes binding data to an instance.
+///
+/// Fiat-Shamir challenges, hash-derived generators and masks, and commitment secrets of an
+/// instance are all computed under its suite. The suite is chosen per instance with
+/// `LHTLP::with_hash_suite` and its identifier is written
+/// into puzzle envelopes and store headers, so artifacts produced under one suite are rejected with
+/// `LhtlpError::UnsupportedHashSuite` by an instance configured with another.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashSuite {
+    #[default]
+    Sha256,
+    Sha384,
+}
+
+impl HashSuite {
+    /// The identifier of the suite in serialized artifacts.
+    pub fn id(self) -> u8 {
+        match self {
+            HashSuite::Sha256 => 1,
+            HashSuite::Sha384 => 2,
+        }
+    }
+
+    /// The suite with identifier `id`, failing with `LhtlpError::UnsupportedHashSuite` for unknown ones.
+    pub fn from_id(id: u8) -> Result<HashSuite, LhtlpError> {
+        match id {
+            1 => Ok(HashSuite::Sha256),
+            2 => Ok(HashSuite::Sha384),
+            _ => Err(LhtlpError::UnsupportedHashSuite),
+        }
+    }
+
+    /// Size in bytes of a digest under the suite.
+    pub fn output_len(self) -> usize {
+        match self {
+            HashSuite::Sha256 => 32,
+            HashSuite::Sha384 => 48,
+        }
+    }
+
+    /// Digest of `data` under the suite.
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashSuite::Sha256 => Sha256::digest(data).to_vec(),
+            HashSuite::Sha384 => Sha384::digest(data).to_vec(),
+        }
+    }
+
+    /// Fail with `LhtlpError::UnsupportedHashSuite` unless `found` is this suite.
+    pub(crate) fn check(self, found: HashSuite) -> Result<(), LhtlpError> {
+        if self != found {
+            return Err(LhtlpError::UnsupportedHashSuite);
+        }
+        Ok(())
+    }
+}
+
+impl LHTLP {
+    /// Select the hash suite used for the parameter fingerprint and the other hashes of the instance.
+    pub fn with_hash_suite(mut self, hash_suite: HashSuite) -> LHTLP {
+        self.hash_suite = hash_suite;
+        self
+    }
+
+    /// The hash suite used for the parameter fingerprint and the other hashes of the instance.
+    pub fn hash_suite(&self) -> HashSuite {
+        self.hash_suite
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn suite_identifiers() {
+        for suite in [HashSuite::Sha256, HashSuite::Sha384] {
+            assert!(HashSuite::from_id(suite.id()) == Ok(suite));
+        }
+        assert!(HashSuite::from_id(0) == Err(LhtlpError::UnsupportedHashSuite));
+        for suite in [HashSuite::Sha256, HashSuite::Sha384] {
+            assert!(suite.digest(b"abc").len() == suite.output_len());
+        }
+    }
+
+    #[test]
+    fn fingerprint_depends_on_suite() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(lhtlp.hash_suite() == HashSuite::Sha256);
+        let sha384 = lhtlp.clone().with_hash_suite(HashSuite::Sha384);
+        assert!(sha384.fingerprint() != lhtlp.fingerprint());
+        assert!(sha384.fingerprint() == sha384.clone().fingerprint());
+        assert!(sha384.fingerprint().len() == 48 && lhtlp.fingerprint().len() == 32);
+    }
+}
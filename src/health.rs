@@ -0,0 +1,183 @@
+use crate::num_primes::RandBigInt;
+use crate::{HTable, LhtlpError, PrivateKey, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use std::time::{Duration, Instant};
+
+/// Difficulty of the instance used by `verify_homomorphism` in place of the real one.
+const SELF_TEST_DIFFICULTY: u64 = 16;
+
+/// Outcome of a successful `LHTLP::health_check`, for logging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    pub fingerprint: Vec<u8>,
+    pub modulus_bits: u64,
+    /// Time spent on the whole check.
+    pub elapsed: Duration,
+    /// Time spent generating and opening a puzzle with the trapdoor, when one was given.
+    pub trapdoor_roundtrip: Option<Duration>,
+}
+
+impl LHTLP {
+    /// Cheap self-test of the parameters, to run before trusting a long-lived or deserialized instance.
+    ///
+    /// Checks that `n` is odd, that the cached `n^2` matches it, that `g` and `h` lie in `(1, n)`
+    /// and are coprime to `n`, and that the instance satisfies its solve policy. A broken
+    /// invariant fails with `LhtlpError::InconsistentParameters`, and a generator sharing a factor
+    /// with `n` with `LhtlpError::ModulusCompromised`. No squaring is performed.
+    pub fn health_check(&self) -> Result<HealthReport, LhtlpError> {
+        let start = Instant::now();
+        let one = BigUint::from(1u32);
+        if self.n.is_even() || self.g <= one || self.g >= self.n || self.h <= one || self.h >= self.n {
+            return Err(LhtlpError::InconsistentParameters);
+        }
+        if self.n2 != &self.n * &self.n {
+            return Err(LhtlpError::InconsistentParameters);
+        }
+        for value in [&self.g, &self.h] {
+            let gcd = value.gcd(&self.n);
+            if gcd != one {
+                return Err(LhtlpError::ModulusCompromised { gcd });
+            }
+        }
+        self.policy().check(self)?;
+        Ok(HealthReport {
+            fingerprint: self.fingerprint(),
+            modulus_bits: self.n.bits(),
+            elapsed: start.elapsed(),
+            trapdoor_roundtrip: None,
+        })
+    }
+
+    /// Run `health_check`, also checking that `n` has at least `min_bits` bits.
+    ///
+    /// A modulus from `setup` with `lambda` has `2 * lambda - 1` or `2 * lambda` bits; a shorter
+    /// one fails with `LhtlpError::ModulusTooSmall`.
+    pub fn health_check_with_min_bits(&self, min_bits: u64) -> Result<HealthReport, LhtlpError> {
+        let bits = self.n.bits();
+        if bits < min_bits {
+            return Err(LhtlpError::ModulusTooSmall { bits, minimum: min_bits });
+        }
+        self.health_check()
+    }
+
+    /// Run `health_check`, then check that a random secret round-trips through `generate` and
+    /// `solve_with_trapdoor`.
+    ///
+    /// A failed round trip means `h` does not match the trapdoor and the difficulty, and is
+    /// reported as `LhtlpError::ParameterMismatch`.
+    pub fn health_check_with_trapdoor(&self, key: &PrivateKey) -> Result<HealthReport, LhtlpError> {
+        let start = Instant::now();
+        let mut report = self.health_check()?;
+        let roundtrip = Instant::now();
+        let secret = rand::thread_rng().gen_biguint_below(&self.n);
+        let puzzle = self.generate_biguint(&secret)?;
+        if self.solve_with_trapdoor(puzzle, key)? != secret {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        report.trapdoor_roundtrip = Some(roundtrip.elapsed());
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+
+    /// Self-test that `generate`, `evaluate` and `solve` agree: two random puzzles must open to
+    /// the sum of their secrets after evaluation.
+    ///
+    /// Without the trapdoor this runs on the modulus and generator of the instance at a tiny
+    /// difficulty, with `h` recomputed by sequential squaring, so it does not check `h` itself;
+    /// `verify_homomorphism_with_trapdoor` does.
+    pub fn verify_homomorphism(&self) -> bool {
+        let h = HTable::new(self.n.clone(), self.g.clone()).h_for(SELF_TEST_DIFFICULTY);
+        let small = LHTLP { difficulty: BigUint::from(SELF_TEST_DIFFICULTY), h, ..self.clone() };
+        small.homomorphism_holds(|puzzle| small.solve_checked(puzzle))
+    }
+
+    /// Self-test like `verify_homomorphism` on the instance itself, opening the puzzles with the trapdoor.
+    pub fn verify_homomorphism_with_trapdoor(&self, key: &PrivateKey) -> bool {
+        self.homomorphism_holds(|puzzle| self.solve_with_trapdoor(puzzle, key))
+    }
+
+    fn homomorphism_holds(&self, open: impl Fn(Puzzle) -> Result<BigUint, LhtlpError>) -> bool {
+        let mut rng = rand::thread_rng();
+        let secrets = [rng.gen_biguint(32), rng.gen_biguint(32)];
+        let Ok(puzzles) = secrets.iter().map(|secret| self.generate_biguint(secret)).collect::<Result<Vec<Puzzle>, _>>() else {
+            return false;
+        };
+        open(self.evaluate(puzzles)).is_ok_and(|sum| sum == &secrets[0] + &secrets[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::fixed_instance;
+    use crate::{PublicParams, SolvePolicy};
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn healthy_instance() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let report = lhtlp.health_check().unwrap();
+        assert!(report.fingerprint == lhtlp.fingerprint());
+        assert!(report.modulus_bits == lhtlp.n.bits());
+        assert!(report.trapdoor_roundtrip.is_none());
+
+        let limited = lhtlp.with_policy(SolvePolicy::default().with_max_difficulty(DIFFICULTY - 1));
+        assert!(matches!(limited.health_check(), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+
+    #[test]
+    fn corrupted_params() {
+        let p = BigUint::from(1019u32);
+        let n = &p * BigUint::from(1187u32);
+        let params = PublicParams { g: p.clone(), h: BigUint::from(4u32), n };
+        let lhtlp = LHTLP::from_public_params(params, DIFFICULTY).unwrap();
+        assert!(lhtlp.health_check() == Err(LhtlpError::ModulusCompromised { gcd: p }));
+
+        let mut stale = LHTLP::setup(LAMBDA, DIFFICULTY);
+        stale.n2 += 1u32;
+        assert!(stale.health_check() == Err(LhtlpError::InconsistentParameters));
+        stale.n2 = &stale.n * &stale.n;
+        stale.h = stale.n.clone();
+        assert!(stale.health_check() == Err(LhtlpError::InconsistentParameters));
+    }
+
+    #[test]
+    fn modulus_bit_length() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        assert!(lhtlp.health_check_with_min_bits(2 * LAMBDA - 1).is_ok());
+        let bits = lhtlp.n.bits();
+        assert!(lhtlp.health_check_with_min_bits(4 * LAMBDA) == Err(LhtlpError::ModulusTooSmall { bits, minimum: 4 * LAMBDA }));
+    }
+
+    #[test]
+    fn trapdoor_roundtrip() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(DIFFICULTY));
+        let report = lhtlp.health_check_with_trapdoor(&key).unwrap();
+        assert!(report.trapdoor_roundtrip.is_some());
+
+        let mut params = lhtlp.public_params();
+        params.h = &params.g * &params.g % &params.n;
+        let tampered = LHTLP::from_public_params(params, DIFFICULTY).unwrap();
+        assert!(tampered.health_check().is_ok());
+        assert!(tampered.health_check_with_trapdoor(&key) == Err(LhtlpError::ParameterMismatch));
+
+        let other = PrivateKey::new(BigUint::from(1019u32), key.q().clone());
+        assert!(lhtlp.health_check_with_trapdoor(&other) == Err(LhtlpError::ParameterMismatch));
+    }
+
+    #[test]
+    fn homomorphism_self_test() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(1u64 << 40));
+        assert!(lhtlp.verify_homomorphism());
+        assert!(lhtlp.verify_homomorphism_with_trapdoor(&key));
+
+        let mut params = lhtlp.public_params();
+        params.h = &params.g * &params.g % &params.n;
+        let tampered = LHTLP::from_public_params(params, 1u64 << 40).unwrap();
+        assert!(tampered.verify_homomorphism());
+        assert!(!tampered.verify_homomorphism_with_trapdoor(&key));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{read_value, write_value, Difficulty, LhtlpError, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::ToPrimitive;
+use std::collections::BTreeMap;
+
+/// Memoized values `h = g^(2^T) mod n` of one `(n, g)` pair, indexed by the difficulty `T`.
+///
+/// Without the trapdoor, `h` for a difficulty `T` costs `T` sequential squarings. The table keeps
+/// every computed `h` and continues from the largest stored difficulty below the requested one,
+/// so computing `h` for `2T` after `T` costs only `T` more squarings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HTable {
+    n: BigUint,
+    g: BigUint,
+    entries: BTreeMap<u64, BigUint>,
+}
+
+impl HTable {
+    /// An empty table for the modulus `n` and generator `g`.
+    pub fn new(n: BigUint, g: BigUint) -> HTable {
+        HTable { n, g, entries: BTreeMap::new() }
+    }
+
+    /// Whether the table holds values for the modulus `n` and generator `g`.
+    pub fn matches(&self, n: &BigUint, g: &BigUint) -> bool {
+        &self.n == n && &self.g == g
+    }
+
+    /// The stored difficulties, in increasing order.
+    pub fn difficulties(&self) -> impl Iterator<Item = u64> + '_ {
+        self.entries.keys().copied()
+    }
+
+    /// Whether `h` for `difficulty` is stored.
+    pub(crate) fn contains(&self, difficulty: u64) -> bool {
+        self.entries.contains_key(&difficulty)
+    }
+
+    /// Store `h` for `difficulty`, computed elsewhere, such as with the trapdoor.
+    pub(crate) fn insert(&mut self, difficulty: u64, h: BigUint) {
+        self.entries.insert(difficulty, h);
+    }
+
+    /// `h` for `difficulty`, computed and stored if it is not already in the table.
+    pub fn h_for(&mut self, difficulty: u64) -> BigUint {
+        if let Some(h) = self.entries.get(&difficulty) {
+            return h.clone();
+        }
+        let (start, h) = match self.entries.range(..difficulty).next_back() {
+            Some((&start, h)) => (start, h),
+            None => (0, &self.g),
+        };
+        let h = LHTLP::square_in_chunks(h, &BigUint::from(difficulty - start), &self.n, SQUARING_CHUNK, &mut 0);
+        self.entries.insert(difficulty, h.clone());
+        h
+    }
+
+    /// Encoding of the table: `n`, `g`, then each `(difficulty, h)` pair in increasing difficulty.
+    ///
+    /// Values and difficulties alike are written as a 4-byte big-endian length followed by their
+    /// big-endian magnitude, as in `Puzzle::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &self.n);
+        write_value(&mut bytes, &self.g);
+        for (difficulty, h) in &self.entries {
+            write_value(&mut bytes, &BigUint::from(*difficulty));
+            write_value(&mut bytes, h);
+        }
+        bytes
+    }
+
+    /// Decode a table from its encoding.
+    ///
+    /// The stored values are taken as they are; they are only as trustworthy as their source.
+    pub fn from_bytes(bytes: &[u8]) -> Result<HTable, LhtlpError> {
+        let mut rest = bytes;
+        let n = read_value(&mut rest)?;
+        let g = read_value(&mut rest)?;
+        let mut table = HTable::new(n, g);
+        while !rest.is_empty() {
+            let difficulty = read_value(&mut rest)?.to_u64().ok_or(LhtlpError::InvalidEncoding)?;
+            let h = read_value(&mut rest)?;
+            table.entries.insert(difficulty, h);
+        }
+        Ok(table)
+    }
+}
+
+impl LHTLP {
+    /// Build an instance from a modulus and generator without the trapdoor, taking `h` from `table`.
+    ///
+    /// `h` is computed by sequential squaring unless the table already holds it, and the table is
+    /// extended with it. Fails with `LhtlpError::ParameterMismatch` if the table is for another
+    /// `(n, g)`, with `LhtlpError::DifficultyTooLarge` if the difficulty does not fit in `u64`
+    /// squarings, and like `from_public_params` on malformed parameters.
+    pub fn from_untrusted_modulus(n: BigUint, g: BigUint, difficulty: impl Into<Difficulty>, table: &mut HTable) -> Result<LHTLP, LhtlpError> {
+        let one = BigUint::from(1u32);
+        if n.is_even() || g <= one || g >= n {
+            return Err(LhtlpError::InconsistentParameters);
+        }
+        LHTLP::verify_public_params(&n)?;
+        if !table.matches(&n, &g) {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        let difficulty: BigUint = difficulty.into().into();
+        let squarings = difficulty.to_u64().ok_or_else(|| LhtlpError::DifficultyTooLarge {
+            difficulty: difficulty.clone(),
+            limit: BigUint::from(u64::MAX),
+        })?;
+        let h = table.h_for(squarings);
+        Ok(LHTLP { difficulty, n2: &n * &n, n, g, h, policy: Default::default(), hash_suite: Default::default() })
+    }
+
+    /// This instance at another `difficulty`, with `h` taken from `table`.
+    pub fn recompute_for_difficulty(&self, difficulty: impl Into<Difficulty>, table: &mut HTable) -> Result<LHTLP, LhtlpError> {
+        let lhtlp = LHTLP::from_untrusted_modulus(self.n.clone(), self.g.clone(), difficulty, table)?;
+        Ok(lhtlp.with_policy(self.policy.clone()).with_hash_suite(self.hash_suite))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn continuation_matches_scratch() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut table = HTable::new(lhtlp.n.clone(), lhtlp.g.clone());
+        assert!(table.h_for(DIFFICULTY) == lhtlp.h);
+
+        let mut scratch = HTable::new(lhtlp.n.clone(), lhtlp.g.clone());
+        for difficulty in [2 * DIFFICULTY, 1500, 10, 0] {
+            let continued = table.h_for(difficulty);
+            assert!(continued == HTable::new(lhtlp.n.clone(), lhtlp.g.clone()).h_for(difficulty));
+            assert!(continued == scratch.h_for(difficulty));
+        }
+        assert!(table.difficulties().collect::<Vec<_>>() == vec![0, 10, DIFFICULTY, 1500, 2 * DIFFICULTY]);
+        assert!(table.h_for(0) == lhtlp.g);
+    }
+
+    #[test]
+    fn untrusted_instances() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut table = HTable::new(lhtlp.n.clone(), lhtlp.g.clone());
+        let rebuilt = LHTLP::from_untrusted_modulus(lhtlp.n.clone(), lhtlp.g.clone(), DIFFICULTY, &mut table).unwrap();
+        assert!(rebuilt == lhtlp);
+
+        let slower = lhtlp.recompute_for_difficulty(3 * DIFFICULTY, &mut table).unwrap();
+        assert!(slower.difficulty == BigUint::from(3 * DIFFICULTY));
+        assert!(slower.solve(slower.generate(42)) == BigUint::from(42u32));
+
+        let mut other = HTable::new(lhtlp.n.clone(), &lhtlp.g + 1u32);
+        assert!(lhtlp.recompute_for_difficulty(DIFFICULTY, &mut other) == Err(LhtlpError::ParameterMismatch));
+
+        let even = &lhtlp.n + 1u32;
+        let result = LHTLP::from_untrusted_modulus(even.clone(), lhtlp.g.clone(), DIFFICULTY, &mut HTable::new(even, lhtlp.g.clone()));
+        assert!(result == Err(LhtlpError::InconsistentParameters));
+
+        let huge = BigUint::from(u64::MAX) + 1u32;
+        assert!(matches!(lhtlp.recompute_for_difficulty(huge, &mut table), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut table = HTable::new(lhtlp.n.clone(), lhtlp.g.clone());
+        assert!(HTable::from_bytes(&table.to_bytes()) == Ok(table.clone()));
+        table.h_for(DIFFICULTY);
+        table.h_for(10);
+
+        let bytes = table.to_bytes();
+        assert!(HTable::from_bytes(&bytes) == Ok(table.clone()));
+        assert!(HTable::from_bytes(&bytes[..bytes.len() - 1]) == Err(LhtlpError::InvalidEncoding));
+        assert!(HTable::from_bytes(&bytes[..bytes.len() - 3]) == Err(LhtlpError::InvalidEncoding));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use num_bigint::{BigUint, RandBigInt};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// A message encrypted under a key encapsulated in a puzzle.
+///
+/// The key is the SHA-256 digest of the secret of `puzzle`, so the message can only be decrypted
+/// once the puzzle is solved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncryptedMessage {
+    pub puzzle: Puzzle,
+    pub ciphertext: Vec<u8>,
+    pub nonce: [u8; 12],
+}
+
+/// The ChaCha20-Poly1305 cipher keyed by the digest of `secret`.
+fn cipher(secret: &BigUint) -> ChaCha20Poly1305 {
+    let key: Zeroizing<[u8; 32]> = Zeroizing::new(Sha256::digest(secret.to_bytes_be()).into());
+    ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+}
+
+impl LHTLP {
+    /// Timelock `message`: encapsulate a fresh key in a puzzle and encrypt with ChaCha20-Poly1305.
+    ///
+    /// The encapsulated secret is uniform modulo `n`, so the key has as many bits of entropy as
+    /// the modulus, capped at 256.
+    #[must_use]
+    pub fn generate_encrypted_message(&self, message: &[u8]) -> EncryptedMessage {
+        let mut rng = rand::thread_rng();
+        let secret = rng.gen_biguint_below(&self.n);
+        let r = self.sample_randomness(&mut rng);
+        let mut nonce = [0u8; 12];
+        rng.fill_bytes(&mut nonce);
+        let ciphertext = cipher(&secret)
+            .encrypt(Nonce::from_slice(&nonce), message)
+            .expect("message exceeds the ChaCha20-Poly1305 length limit");
+        EncryptedMessage { puzzle: self.generate_with_randomness(&secret, &r), ciphertext, nonce }
+    }
+
+    /// Solve the puzzle of `em` and decrypt its message.
+    ///
+    /// The puzzle is solved with `solve_checked`. Fails with `LhtlpError::DecryptionFailed` if the
+    /// ciphertext does not authenticate under the recovered key.
+    pub fn decrypt_message(&self, em: &EncryptedMessage) -> Result<Vec<u8>, LhtlpError> {
+        let secret = self.solve_checked(em.puzzle.clone())?;
+        cipher(&secret)
+            .decrypt(Nonce::from_slice(&em.nonce), em.ciphertext.as_slice())
+            .map_err(|_| LhtlpError::DecryptionFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut message = vec![0u8; 1 << 20];
+        rand::thread_rng().fill_bytes(&mut message);
+        let em = lhtlp.generate_encrypted_message(&message);
+        assert!(em.ciphertext.len() == message.len() + 16);
+        assert!(lhtlp.decrypt_message(&em) == Ok(message));
+
+        let empty = lhtlp.generate_encrypted_message(b"");
+        assert!(lhtlp.decrypt_message(&empty) == Ok(Vec::new()));
+    }
+
+    #[test]
+    fn tampering() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let em = lhtlp.generate_encrypted_message(b"released at noon");
+
+        let mut ciphertext = em.clone();
+        ciphertext.ciphertext[0] ^= 1;
+        assert!(lhtlp.decrypt_message(&ciphertext) == Err(LhtlpError::DecryptionFailed));
+
+        let mut puzzle = em.clone();
+        puzzle.puzzle = lhtlp.add(&em.puzzle, &lhtlp.generate(1));
+        assert!(lhtlp.decrypt_message(&puzzle) == Err(LhtlpError::DecryptionFailed));
+
+        let mut nonce = em;
+        nonce.nonce[0] ^= 1;
+        assert!(lhtlp.decrypt_message(&nonce) == Err(LhtlpError::DecryptionFailed));
+    }
+}
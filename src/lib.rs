@@ -4,18 +4,19 @@
 //! ```rust
 //! use lhtlp::LHTLP;
 //! use num_bigint::BigUint;
-//! const TIME_HARDNESS: u64 = 100000000;
+//! const TIME_HARDNESS: u64 = 1000;
 //!
 //! let lhtlp = LHTLP::setup(64, BigUint::from(TIME_HARDNESS));
 //! let secret = 42;
 //! let puzzle = lhtlp.generate(secret);
 //! let solution = lhtlp.solve(puzzle);
+//! assert!(BigUint::from(42u32) == solution);
 //! ```
 //! ## Homomorphic evaluation of multiple puzzles
 //! ```rust
 //! # use lhtlp::LHTLP;
 //! # use num_bigint::BigUint;
-//! # const TIME_HARDNESS: u64 = 100000000;
+//! # const TIME_HARDNESS: u64 = 1000;
 //! # let lhtlp = LHTLP::setup(64, BigUint::from(TIME_HARDNESS));
 //! let first = lhtlp.generate(42);
 //! let second = lhtlp.generate(13);
@@ -26,11 +27,169 @@
 //! ```
 //!
 pub mod num_primes;
+mod advisor;
+mod aggregate;
+mod archive;
+mod audit;
+mod blind;
+mod cache;
+mod certificate;
+mod chain;
+mod deterministic;
+#[cfg(feature = "der")]
+mod der;
+mod difficulty;
+mod domain;
+#[cfg(feature = "commitment")]
+mod commitment;
+#[cfg(feature = "subtle")]
+mod ct;
+mod envelope;
+mod epoch;
+mod error;
+mod fixed_point;
+mod group;
+mod hash;
+mod health;
+mod htable;
+#[cfg(feature = "kem")]
+mod kem;
+mod math;
+mod merkle;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod multi;
+mod observer;
+mod oracle;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod params;
+mod plaintext;
+mod policy;
+mod puzzle_ref;
+mod randomness;
+mod range;
+mod registry;
+#[cfg(feature = "rsa-compat")]
+mod rsa_compat;
+mod solver;
+#[cfg(feature = "puzzle-store")]
+mod store;
+mod stream;
+#[cfg(all(test, feature = "statistical_tests"))]
+mod statistical_tests;
+#[cfg(test)]
+mod test_vectors;
+mod timelock;
+mod trapdoor;
+mod transcript;
+mod validity;
+mod vdf;
+mod wellformed;
+
+/// Compiles and runs the examples of the README as doctests.
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
+
+pub use crate::advisor::{DifficultyAdvisor, SolveSample, SolveStats};
+pub use crate::aggregate::{BoundedPuzzle, InstancePuzzle, LhtlpIteratorExt, PuzzleAccumulator, TallyBuilder};
+pub use crate::audit::{AuditLog, DEFAULT_AUDIT_INTERVAL};
+pub use crate::blind::{BlindedPuzzle, Unblinder};
+#[cfg(feature = "file-cache")]
+pub use crate::cache::FileSolutionCache;
+pub use crate::cache::{CachedSolution, MemorySolutionCache, SolutionCache};
+pub use crate::certificate::{verify_certificate, PrimalityCertificate};
+pub use crate::chain::ChainedPuzzle;
+#[cfg(feature = "subtle")]
+pub use crate::ct::secret_in_range_ct;
+pub use crate::difficulty::Difficulty;
+pub use crate::envelope::PuzzleEnvelope;
+pub use crate::epoch::LHTLPEpoch;
+pub use crate::error::LhtlpError;
+pub use crate::group::PuzzleGroup;
+pub use crate::hash::HashSuite;
+pub use crate::health::HealthReport;
+pub use crate::htable::HTable;
+#[cfg(feature = "kem")]
+pub use crate::kem::EncryptedMessage;
+pub use crate::merkle::{set_root, verify_membership, EvalProof, MembershipProof};
+#[cfg(feature = "metrics")]
+pub use crate::metrics::Metrics;
+pub use crate::multi::MultiDifficultyLHTLP;
+pub use crate::observer::squaring_digest;
+pub use crate::params::{GeneratorConvention, PublicParams};
+pub use crate::plaintext::PlaintextSpace;
+pub use crate::policy::{SolvePolicy, DEFAULT_DIFFICULTY_CEILING};
+pub use crate::puzzle_ref::PuzzleRef;
+pub use crate::randomness::{GenerateOutput, Randomness};
+pub use crate::range::{BitProof, BoundCheckResult, BoundProof};
+pub use crate::registry::{Fingerprint, InstanceRegistry};
+pub use crate::solver::{CheckpointStride, PartialSolution, SolveCache};
+#[cfg(feature = "puzzle-store")]
+pub use crate::store::PuzzleStore;
+pub use crate::transcript::{verify_transcript, SetupTranscript};
+pub use crate::timelock::TimelockPuzzle;
+pub use crate::trapdoor::PrivateKey;
+pub use crate::vdf::{Vdf, WesolowskiProof};
+pub use crate::wellformed::WellFormednessProof;
 
 use crate::num_primes::{Generator, RandBigInt};
 use num_bigint::BigUint;
 use num_traits::pow::Pow;
+use num_traits::ToPrimitive;
 use num_integer::Integer;
+use sha2::{Digest, Sha256};
+
+/// Number of squarings performed by each `modpow` of a solve.
+const SQUARING_CHUNK: u64 = 1 << 16;
+
+/// Two distinct safe primes of `lambda` bits.
+pub(crate) fn distinct_safe_primes(lambda: u64) -> (BigUint, BigUint) {
+    let p = Generator::safe_prime(lambda);
+    // With a small lambda the second prime may well equal the first, giving a square modulus.
+    let q = loop {
+        let q = Generator::safe_prime(lambda);
+        if q != p {
+            break q;
+        }
+    };
+    (p, q)
+}
+
+/// Append `value` to `bytes` as a 4-byte big-endian length followed by its big-endian magnitude,
+/// the encoding of each component in `Puzzle::to_bytes`.
+pub(crate) fn write_value(bytes: &mut Vec<u8>, value: &BigUint) {
+    let magnitude = value.to_bytes_be();
+    bytes.extend_from_slice(&(magnitude.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&magnitude);
+}
+
+/// Split the magnitude of a value written by `write_value` off the front of `rest`.
+pub(crate) fn read_magnitude<'a>(rest: &mut &'a [u8]) -> Result<&'a [u8], LhtlpError> {
+    if rest.len() < 4 {
+        return Err(LhtlpError::InvalidEncoding);
+    }
+    let (len, tail) = rest.split_at(4);
+    let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+    if tail.len() < len {
+        return Err(LhtlpError::InvalidEncoding);
+    }
+    let (magnitude, tail) = tail.split_at(len);
+    *rest = tail;
+    Ok(magnitude)
+}
+
+/// Modular multiplications of a square-and-multiply exponentiation by `exponent`: one squaring
+/// per bit and one multiplication per set bit.
+pub(crate) fn pow_multiplications(exponent: &BigUint) -> u64 {
+    exponent.bits() + exponent.count_ones()
+}
+
+/// Read a value written by `write_value` off the front of `rest`.
+pub(crate) fn read_value(rest: &mut &[u8]) -> Result<BigUint, LhtlpError> {
+    read_magnitude(rest).map(BigUint::from_bytes_be)
+}
 
 /// A Linearly Homomorphic Timelock Puzzle.
 ///
@@ -42,7 +201,66 @@ pub struct LHTLP {
     difficulty: BigUint,
     n: BigUint,
     g: BigUint,
-    h: BigUint
+    h: BigUint,
+    /// `n^2`, cached for checks that must not allocate.
+    n2: BigUint,
+    policy: SolvePolicy,
+    hash_suite: HashSuite,
+}
+
+/// Two instances are equal when their public parameters `(n, g, h, difficulty)` and their hash
+/// suite are; the solve policy and any trapdoor or cached values held by the instance do not
+/// affect equality.
+impl PartialEq for LHTLP {
+    fn eq(&self, other: &LHTLP) -> bool {
+        self.n == other.n
+            && self.g == other.g
+            && self.h == other.h
+            && self.difficulty == other.difficulty
+            && self.hash_suite == other.hash_suite
+    }
+}
+
+impl Eq for LHTLP {}
+
+/// A puzzle `(u, v)` embedding a secret value.
+///
+/// Puzzles are totally ordered lexicographically, first by `u` and then by `v`, so that a set of
+/// puzzles can be sorted into a deterministic order before evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Puzzle {
+    pub u: BigUint,
+    pub v: BigUint,
+}
+
+impl Puzzle {
+    /// Canonical byte encoding of the puzzle.
+    ///
+    /// Each of `u` and `v` is written as a 4-byte big-endian length followed by its big-endian
+    /// magnitude, so equal puzzles always have identical encodings.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &self.u);
+        write_value(&mut bytes, &self.v);
+        bytes
+    }
+
+    /// Decode a puzzle from its canonical byte encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Puzzle, LhtlpError> {
+        let mut rest = bytes;
+        let u = read_value(&mut rest)?;
+        let v = read_value(&mut rest)?;
+        if !rest.is_empty() {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        Ok(Puzzle { u, v })
+    }
+
+    /// SHA-256 digest of the canonical encoding of the puzzle.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
 }
 
 impl LHTLP {
@@ -50,67 +268,450 @@ impl LHTLP {
     ///
     /// The security parameter `lambda` sets the number of bits of the randomly generated safe primes. \
     /// Setting `difficulty` to 100000000 results in roughly 5 seconds of computation when
-    /// opening a puzzle with `solve`.
-    pub fn setup(lambda: u64, difficulty: BigUint) -> LHTLP {
-        let p = Generator::safe_prime(lambda);
-        let q = Generator::safe_prime(lambda);
+    /// opening a puzzle with `solve`. It accepts a `Difficulty` or a plain squaring count.
+    /// The generator is `g = (x^2)^-1 mod n` for a random `x`, see `GeneratorConvention`.
+    pub fn setup(lambda: u64, difficulty: impl Into<Difficulty>) -> LHTLP {
+        let (p, q) = distinct_safe_primes(lambda);
+        LHTLP::from_primes(&p, &q, difficulty.into().into())
+    }
+
+    /// **Insecure.** Setup an instance whose modulus has exactly `bits` bits, built from ordinary
+    /// primes instead of safe primes.
+    ///
+    /// This is only meant for benchmarks and teaching, where generating safe primes would dominate
+    /// the run time. Without safe primes the squaring group may have small subgroups and a
+    /// structure that makes the difficulty meaningless. **Never use it for real timelocks.**
+    /// Panics if `bits` is below 16.
+    pub fn setup_fast_insecure(bits: u64, difficulty: impl Into<Difficulty>) -> LHTLP {
+        assert!(bits >= 16, "modulus must have at least 16 bits");
+        let mut rng = rand::thread_rng();
+        let mut prime = |bits: u64| loop {
+            let mut candidate = rng.gen_biguint(bits);
+            candidate.set_bit(bits - 1, true);
+            candidate.set_bit(0, true);
+            if num_primes::is_prime(&candidate, 16) {
+                break candidate;
+            }
+        };
+        let (p, q) = loop {
+            let p = prime(bits / 2);
+            let q = prime(bits - bits / 2);
+            if p != q && (&p * &q).bits() == bits {
+                break (p, q);
+            }
+        };
+        LHTLP::from_primes(&p, &q, difficulty.into().into())
+    }
 
-        let n = &p * &q;
+    /// Setup an instance from the primes `p` and `q` making up the modulus.
+    pub(crate) fn from_primes(p: &BigUint, q: &BigUint, difficulty: BigUint) -> LHTLP {
+        LHTLP::from_primes_with_rng(p, q, difficulty, &mut rand::thread_rng())
+    }
+
+    /// Setup an instance from the primes `p` and `q`, drawing the generator from `rng`.
+    pub(crate) fn from_primes_with_rng<R: rand::Rng + ?Sized>(p: &BigUint, q: &BigUint, difficulty: BigUint, rng: &mut R) -> LHTLP {
+        let n = p * q;
         let one = BigUint::from(1u32);
         let two = BigUint::from(2u32);
 
-        let mut rng = rand::thread_rng();
+        // Draw a new base whenever its square is not invertible modulo n, instead of panicking.
+        // The inverse of a square always has Jacobi symbol 1; checking it guards the generator
+        // against a broken inversion.
         let g = loop {
             let rand = rng.gen_biguint_range(&one, &n);
-            if rand.gcd(&n) == one {
-                break rand;
+            if let Some(g) = rand.pow(&two).modinv(&n) {
+                if math::jacobi_symbol(&g, &n) == 1 {
+                    break g;
+                }
             }
         };
 
-        let g = &g.pow(&two);
-        let g = g.modinv(&n).unwrap();
-
         // phi(n) = p-1 * q-1
         // phi(n) / 2
-        let tot_div_2 = (&p-&one) * (&q-&one) / &BigUint::from(2u32);
+        let tot_div_2 = (p - &one) * (q - &one) / &BigUint::from(2u32);
         let pow = &two.modpow(&difficulty, &tot_div_2);
         let h = g.modpow(pow, &n);
 
         LHTLP { 
             difficulty,
+            n2: &n * &n,
             n,
             g,
             h,
+            policy: SolvePolicy::default(),
+            hash_suite: HashSuite::default(),
+        }
+    }
+
+    /// Whether both instances share the modulus `n`, regardless of generator and difficulty.
+    pub fn same_modulus(&self, other: &LHTLP) -> bool {
+        self.n == other.n
+    }
+
+    /// The number of sequential squarings required to open a puzzle.
+    pub fn difficulty(&self) -> &BigUint {
+        &self.difficulty
+    }
+
+    /// `n^2`, the modulus of the `v` component of puzzles.
+    ///
+    /// It is the plaintext-times-ciphertext modulus: `Z_(n^2)^*` splits into the plaintext part
+    /// `(1+n)^s`, with `s` modulo `n`, times the masking part `h^(r*n)`. Homomorphic operations on
+    /// `v`, such as evaluation and rerandomization, reduce modulo this value. It is cached, so
+    /// calling it does not allocate.
+    pub fn modulus_squared(&self) -> &BigUint {
+        &self.n2
+    }
+
+    /// Fingerprint of the public parameters `(n, g, h, difficulty)`.
+    ///
+    /// Two instances share a fingerprint only if puzzles generated by one can be solved by the other.
+    /// It is the full digest under the hash suite of the instance, SHA-256 by default, so its
+    /// length is `HashSuite::output_len` of that suite.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        let mut data = b"lhtlp-params-v1".to_vec();
+        for value in [&self.n, &self.g, &self.h, &self.difficulty] {
+            let bytes = value.to_bytes_be();
+            data.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+            data.extend_from_slice(&bytes);
         }
+        self.hash_suite.digest(&data)
+    }
+
+    /// The largest `Puzzle::to_bytes` encoding of a puzzle of this instance.
+    ///
+    /// `u` takes at most `ceil(bits(n) / 8)` bytes and `v` at most `ceil(2 bits(n) / 8)`, each
+    /// after a 4-byte length. Encodings are minimal, so a component with leading zero bytes
+    /// encodes shorter.
+    pub fn puzzle_size_bytes(&self) -> usize {
+        let bits = self.n.bits();
+        (8 + bits.div_ceil(8) + (2 * bits).div_ceil(8)) as usize
+    }
+
+    /// The memory taken by the instance: the struct and the digits of its big integers.
+    pub fn params_size_bytes(&self) -> usize {
+        let digits = |value: &BigUint| value.bits().div_ceil(64) as usize * 8;
+        std::mem::size_of::<LHTLP>() + [&self.n, &self.g, &self.h, &self.n2, &self.difficulty].into_iter().map(digits).sum::<usize>()
     }
 
     /// Generate a puzzle `(u: BigUint, v: BigUint)` embedding a `secret` value.
     ///
-    pub fn generate(&self, secret: u64) -> (BigUint, BigUint) {
-        let mut rng = rand::thread_rng();
-        let n2 = (&self.n).pow(2u32);
-        let one = BigUint::from(1u32);
-        let r = rng.gen_biguint_range(&one, &n2);
-        let u = self.g.modpow(&r, &self.n);
-        let v = ((&self.h).modpow(&(&r * &self.n), &n2) * (&one + &self.n).modpow(&BigUint::from(secret), &n2)) % n2;
-        (u, v)
+    /// Debug builds check that the puzzle passes `verify_puzzle`.
+    #[must_use]
+    pub fn generate(&self, secret: u64) -> Puzzle {
+        self.generate_with_rng(secret, &mut rand::thread_rng())
+    }
+
+    /// Generate a puzzle embedding `secret`, drawing its randomness from `rng`.
+    ///
+    /// Generation is a pure function of the instance, the secret and the output of `rng`: two
+    /// identically seeded generators yield byte-identical puzzles. The security of the puzzle
+    /// rests on `rng`, which must be cryptographically secure outside of tests.
+    #[must_use]
+    pub fn generate_with_rng<R: rand::Rng + ?Sized>(&self, secret: u64, rng: &mut R) -> Puzzle {
+        self.generate_with_rng_counted(secret, rng, &mut 0)
+    }
+
+    /// Generate like `generate_with_rng`, adding the multiplications performed to `multiplications`.
+    pub(crate) fn generate_with_rng_counted<R: rand::Rng + ?Sized>(&self, secret: u64, rng: &mut R, multiplications: &mut u64) -> Puzzle {
+        let r = self.sample_randomness(rng);
+        let puzzle = self.generate_with_randomness_counted(&BigUint::from(secret), &r, multiplications);
+        debug_assert!(self.verify_puzzle(&puzzle).is_ok(), "generated puzzle is not well-formed");
+        puzzle
+    }
+
+    /// Sample the randomness `r` of a puzzle, uniformly in `[1, n^2)`.
+    pub(crate) fn sample_randomness<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> BigUint {
+        let n2 = &self.n2;
+        rng.gen_biguint_range(&BigUint::from(1u32), n2)
+    }
+
+    /// Generate the puzzle embedding `secret` with randomness `r`.
+    pub(crate) fn generate_with_randomness(&self, secret: &BigUint, r: &BigUint) -> Puzzle {
+        self.generate_with_randomness_counted(secret, r, &mut 0)
+    }
+
+    /// Generate like `generate_with_randomness`, adding the multiplications performed to
+    /// `multiplications`: those of the exponentiations by `r` and `r * n`, the product `secret * n`
+    /// of `plaintext_power` and the final product.
+    pub(crate) fn generate_with_randomness_counted(&self, secret: &BigUint, r: &BigUint, multiplications: &mut u64) -> Puzzle {
+        let n2 = &self.n2;
+        let rn = r * &self.n;
+        let u = self.g.modpow(r, &self.n);
+        let v = (self.h.modpow(&rn, n2) * self.plaintext_power(secret, n2)) % n2;
+        *multiplications = multiplications.saturating_add(pow_multiplications(r) + pow_multiplications(&rn) + 2);
+        Puzzle { u, v }
     }
 
     /// Open a puzzle `(u: BigUint, v: BigUint)` by performing sequential squaring, revealing a `secret` value.
     ///
-    pub fn solve(&self, puzzle: (BigUint, BigUint)) -> BigUint {
-        let n2 = (&self.n).pow(2u32);
-        let w = puzzle.0.modpow(&BigUint::from(2u32).pow(&self.difficulty), &self.n);
-        let s = ((&puzzle.1 * w.modpow(&self.n, &n2).modinv(&n2).unwrap()) % (&self.n).pow(2u32) -  BigUint::from(1u32))/ &self.n;
-        s
+    #[must_use]
+    pub fn solve(&self, puzzle: Puzzle) -> BigUint {
+        self.solve_with_difficulty(puzzle, &self.difficulty)
+    }
+
+    /// Open a puzzle generated, or rebased, for a `difficulty` other than the one of this instance.
+    ///
+    #[must_use]
+    pub fn solve_with_difficulty(&self, puzzle: Puzzle, difficulty: &BigUint) -> BigUint {
+        // With u = 1 every squaring gives 1 again, so `square` stops at once and the puzzle is
+        // decoded as a plain Paillier-style ciphertext. A v of 1 on top decodes to zero.
+        let w = self.square(&puzzle.u, difficulty);
+        self.decode(&puzzle.v, &w)
+    }
+
+    /// Compute `u^(2^difficulty) mod n`.
+    fn square(&self, u: &BigUint, difficulty: &BigUint) -> BigUint {
+        self.square_modulo(u, difficulty, &self.n)
+    }
+
+    /// Compute `u^(2^difficulty) mod modulus`, for `modulus` either `n` or `n^2`.
+    ///
+    /// The squarings run in chunks of `SQUARING_CHUNK`, each a `modpow` by `2^SQUARING_CHUNK`, so
+    /// memory stays constant instead of materializing the `difficulty`-bit exponent `2^difficulty`.
+    /// Once the value reaches 1, a fixed point of squaring, the remaining chunks are skipped.
+    pub(crate) fn square_modulo(&self, u: &BigUint, difficulty: &BigUint, modulus: &BigUint) -> BigUint {
+        LHTLP::square_in_chunks(u, difficulty, modulus, SQUARING_CHUNK, &mut 0)
+    }
+
+    /// Compute `u^(2^difficulty) mod modulus` like `square_modulo`, in chunks of `chunk` squarings,
+    /// adding the number of squarings performed to `squarings`.
+    ///
+    /// Every `u^(2^T)` of the crate goes through here, except `solve_with_oracle`, whose squarings
+    /// are performed by the caller's oracle. The count is the sum of the chunk sizes, so it falls
+    /// short of `difficulty` only when the value reached 1 early. Panics if `chunk` is zero.
+    pub(crate) fn square_in_chunks(u: &BigUint, difficulty: &BigUint, modulus: &BigUint, chunk: u64, squarings: &mut u64) -> BigUint {
+        assert!(chunk > 0, "squaring chunk must be positive");
+        let one = BigUint::from(1u32);
+        let chunk = BigUint::from(chunk);
+        let mut remaining = difficulty.clone();
+        let mut w = u % modulus;
+        while remaining > BigUint::from(0u32) && w != one {
+            let steps = remaining.clone().min(chunk.clone()).to_u64().expect("chunk fits in u64");
+            w = w.modpow(&(BigUint::from(1u32) << steps), modulus);
+            remaining -= steps;
+            *squarings = squarings.saturating_add(steps);
+        }
+        w
+    }
+
+    /// Convert a puzzle of difficulty `T` into a puzzle of difficulty `T + delta` embedding the same secret.
+    ///
+    /// This requires neither the trapdoor nor the secret, so a relay can forward a puzzle with
+    /// increased hardness. With `a = (2^delta)^-1 mod n`, computed as `(2^-1)^delta mod n`, the
+    /// rebased puzzle is `(u^a mod n, (v^a)^(2^delta) mod n^2)`: since `a * 2^delta = 1 mod n` the
+    /// secret is preserved, while the `h^(r*n)` mask of `v` now matches `u^a` squared `T + delta`
+    /// times. Raising to `2^delta` takes `delta` sequential squarings modulo `n^2`.
+    #[must_use]
+    pub fn rebase(&self, puzzle: &Puzzle, delta: &BigUint) -> Puzzle {
+        let half = (&self.n + 1u32) / 2u32;
+        let a = half.modpow(delta, &self.n);
+        Puzzle {
+            u: puzzle.u.modpow(&a, &self.n),
+            v: self.square_modulo(&puzzle.v.modpow(&a, &self.n2), delta, &self.n2),
+        }
+    }
+
+    /// Recover the secret from `v` once `w = u^(2^difficulty) mod n` has been computed.
+    ///
+    /// Panics if `w` shares a factor with `n`; checked APIs use `try_decode` instead.
+    fn decode(&self, v: &BigUint, w: &BigUint) -> BigUint {
+        self.try_decode(v, w).unwrap_or_else(|err| panic!("cannot decode puzzle: {}", err))
+    }
+
+    /// Recover the secret like `decode`, failing with `LhtlpError::ModulusCompromised` if `w`
+    /// shares a factor with `n`.
+    pub(crate) fn try_decode(&self, v: &BigUint, w: &BigUint) -> Result<BigUint, LhtlpError> {
+        let unmask = self.unmask(w)?;
+        Ok(self.decode_unmasked(v, &unmask))
+    }
+
+    /// The inverse of the squared mask `w^(2n) mod n^2`, shared by every `v` solved with the same `w`.
+    pub(crate) fn unmask(&self, w: &BigUint) -> Result<BigUint, LhtlpError> {
+        let n2 = &self.n2;
+        self.invert(&w.modpow(&(&self.n << 1), n2), n2)
+    }
+
+    /// The secret of `v` given the inverse of its squared mask from `unmask`.
+    ///
+    /// Decoding `v^2` gives `2*secret mod n`, which is then halved modulo the odd `n`. Squaring
+    /// removes any factor of order 2 in `v`, such as `-1`, which a well-formedness proof cannot
+    /// rule out: a puzzle whose proof verifies always decodes to the secret it was proven for.
+    pub(crate) fn decode_unmasked(&self, v: &BigUint, unmask: &BigUint) -> BigUint {
+        let n2 = &self.n2;
+        let double = (v * v % n2 * unmask % n2 + n2 - BigUint::from(1u32)) % n2 / &self.n;
+        if double.is_even() {
+            double >> 1
+        } else {
+            (double + &self.n) >> 1
+        }
+    }
+
+    /// Inverse of `value` modulo `modulus`, a power of `n`.
+    ///
+    /// An inverse only fails to exist when `value` shares a factor with `n`, meaning that the
+    /// modulus is broken or was probed with a crafted value: the factor is reported in
+    /// `LhtlpError::ModulusCompromised`.
+    pub(crate) fn invert(&self, value: &BigUint, modulus: &BigUint) -> Result<BigUint, LhtlpError> {
+        value.modinv(modulus).ok_or_else(|| LhtlpError::ModulusCompromised { gcd: value.gcd(&self.n) })
     }
 
     /// Linearly homomorphic evaluate a vector of puzzles.
     ///
     /// The resulting puzzle embeds a secret equivalent to the sum of the secrets embedded in the single puzzles.
-    pub fn evaluate(&self, puzzles: Vec<(BigUint, BigUint)>) -> (BigUint, BigUint) {
-        let one = BigUint::from(1u32);
-        puzzles.iter().fold((one.clone(), one), |acc, x| ((acc.0 * &x.0), (acc.1 * &x.1)))
+    /// The output is not rerandomized: a single puzzle is returned as is, and the output of a
+    /// given input is deterministic. Use `evaluate_rerandomized` when outputs must look fresh.
+    /// The evaluation of no puzzles is a freshly generated puzzle of zero.
+    #[must_use]
+    pub fn evaluate(&self, puzzles: Vec<Puzzle>) -> Puzzle {
+        self.evaluate_counted(puzzles, &mut 0)
+    }
+
+    /// Evaluate like `evaluate`, adding the multiplications performed to `multiplications`: two
+    /// per addition, or those of generating the puzzle of zero for no input.
+    pub(crate) fn evaluate_counted(&self, puzzles: Vec<Puzzle>, multiplications: &mut u64) -> Puzzle {
+        let mut puzzles = puzzles.into_iter();
+        match puzzles.next() {
+            Some(first) => puzzles.fold(first, |acc, x| {
+                *multiplications = multiplications.saturating_add(2);
+                self.add(&acc, &x)
+            }),
+            None => self.generate_with_rng_counted(0, &mut rand::thread_rng(), multiplications),
+        }
+    }
+
+    /// Evaluate puzzles like `evaluate`, then rerandomize the output.
+    ///
+    /// The output is unlinkable to the inputs, which a single-input `evaluate` would hand back unchanged.
+    #[must_use]
+    pub fn evaluate_rerandomized(&self, puzzles: Vec<Puzzle>) -> Puzzle {
+        self.rerandomize(&self.evaluate(puzzles))
+    }
+
+    /// A fresh puzzle embedding the same secret as `puzzle`, obtained by adding a puzzle of zero.
+    #[must_use]
+    pub fn rerandomize(&self, puzzle: &Puzzle) -> Puzzle {
+        self.add(puzzle, &self.generate(0))
+    }
+
+    /// Evaluate puzzles like `evaluate`, failing with `LhtlpError::DuplicatePuzzle` if the same
+    /// puzzle appears twice, so that no secret is counted more than once.
+    ///
+    /// Duplicates are detected by ciphertext equality: a rerandomized copy of a puzzle embeds the
+    /// same secret but is a different `(u, v)` pair and is not caught.
+    pub fn evaluate_unique(&self, puzzles: &[Puzzle]) -> Result<Puzzle, LhtlpError> {
+        let mut seen = std::collections::HashSet::with_capacity(puzzles.len());
+        if !puzzles.iter().all(|puzzle| seen.insert(puzzle)) {
+            return Err(LhtlpError::DuplicatePuzzle);
+        }
+        Ok(self.evaluate(puzzles.to_vec()))
+    }
+
+    /// Running evaluation of a list of puzzles: element `i` embeds the sum of the first `i + 1` secrets.
+    #[must_use]
+    pub fn evaluate_prefix(&self, puzzles: &[Puzzle]) -> Vec<Puzzle> {
+        let mut prefix: Vec<Puzzle> = Vec::with_capacity(puzzles.len());
+        for puzzle in puzzles {
+            let next = match prefix.last() {
+                Some(acc) => self.add(acc, puzzle),
+                None => puzzle.clone(),
+            };
+            prefix.push(next);
+        }
+        prefix
+    }
+
+    /// Homomorphically add two puzzles, reducing the components modulo `n` and `n^2`.
+    #[must_use]
+    pub fn add(&self, first: &Puzzle, second: &Puzzle) -> Puzzle {
+        let n2 = &self.n2;
+        Puzzle {
+            u: (&first.u * &second.u) % &self.n,
+            v: (&first.v * &second.v) % n2,
+        }
+    }
+
+    /// Homomorphically multiply the secret embedded in `puzzle` by a public `scalar`.
+    #[must_use]
+    pub fn scalar_mul(&self, puzzle: &Puzzle, scalar: &BigUint) -> Puzzle {
+        let n2 = &self.n2;
+        Puzzle {
+            u: puzzle.u.modpow(scalar, &self.n),
+            v: puzzle.v.modpow(scalar, n2),
+        }
+    }
+
+    /// Homomorphically compute `acc + weight * puzzle`, the step of a running weighted sum.
+    ///
+    /// The result equals `add(acc, &scalar_mul(puzzle, weight))`, without the intermediate puzzle
+    /// and reusing the cached `n^2`.
+    #[must_use]
+    pub fn mul_add(&self, acc: &Puzzle, puzzle: &Puzzle, weight: &BigUint) -> Puzzle {
+        Puzzle {
+            u: puzzle.u.modpow(weight, &self.n) * &acc.u % &self.n,
+            v: puzzle.v.modpow(weight, &self.n2) * &acc.v % &self.n2,
+        }
+    }
+
+    /// **Toy.** Candidate puzzles for the product of the secrets `a` of `pa` and `b` of `pb`, both
+    /// at most `max_secret`.
+    ///
+    /// The scheme is only additively homomorphic, so the product is found by exhaustive search
+    /// over `a`. With `M = max_secret^2 + 1`, candidate `i` embeds `i * b + (a - i) * M mod n`:
+    /// the candidate `i = a` embeds `a * b < M`, while every other one embeds at least `M` or
+    /// wraps around to nearly `n`. The product is thus the single solution below `M`.
+    ///
+    /// There are `max_secret + 1` candidates, each to be solved, so the work is exponential in the
+    /// bit length of the secrets, and solving all candidates reveals `a` and `b` as well. This is
+    /// only practical for tiny secrets in examples.
+    ///
+    /// Panics unless `(max_secret + 1) * M <= n`: a wrong candidate `i > a` embeds
+    /// `n - ((i - a) * M - i * b)`, which stays at least `M` only under that bound.
+    #[must_use]
+    pub fn evaluate_product_small(&self, pa: &Puzzle, pb: &Puzzle, max_secret: u64) -> Vec<Puzzle> {
+        let max = BigUint::from(max_secret);
+        let m = &max * &max + 1u32;
+        assert!((&max + 1u32) * &m <= self.n, "max_secret is too large for the modulus");
+        let step = &self.n - &m;
+        let mut candidate = self.scalar_mul(pa, &m);
+        let mut candidates = Vec::with_capacity(max_secret as usize + 1);
+        for _ in 0..max_secret {
+            let next = self.add_constant(&self.add(&candidate, pb), &step);
+            candidates.push(std::mem::replace(&mut candidate, next));
+        }
+        candidates.push(candidate);
+        candidates
+    }
+
+    /// Homomorphically add a public `constant` to the secret embedded in `puzzle`.
+    #[must_use]
+    pub fn add_constant(&self, puzzle: &Puzzle, constant: &BigUint) -> Puzzle {
+        let n2 = &self.n2;
+        Puzzle {
+            u: puzzle.u.clone(),
+            v: (&puzzle.v * self.plaintext_power(constant, n2)) % n2,
+        }
+    }
+
+    /// `(1+n)^exponent mod n^2`, computed as `1 + exponent*n` by the binomial expansion, in which
+    /// every further term is a multiple of `n^2`. This is exact for any exponent.
+    pub(crate) fn plaintext_power(&self, exponent: &BigUint, n2: &BigUint) -> BigUint {
+        (BigUint::from(1u32) + (exponent % &self.n) * &self.n) % n2
+    }
+
+    /// Homomorphically negate the secret embedded in `puzzle`, giving a puzzle of `n - secret`.
+    ///
+    /// Adding the negated puzzle to another one subtracts the secrets modulo `n`.
+    #[must_use]
+    pub fn negate(&self, puzzle: &Puzzle) -> Puzzle {
+        let n2 = &self.n2;
+        let invert = |value: &BigUint, modulus: &BigUint| {
+            self.invert(value, modulus).unwrap_or_else(|err| panic!("cannot negate puzzle: {}", err))
+        };
+        Puzzle {
+            u: invert(&puzzle.u, &self.n),
+            v: invert(&puzzle.v, n2),
+        }
     }
 }
 
@@ -138,7 +739,7 @@ mod tests {
         let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
         let mut rng = rand::thread_rng();
         let mut secrets: Vec<u64> = Vec::new();
-        let mut puzzles: Vec<(BigUint, BigUint)> = Vec::new();
+        let mut puzzles: Vec<Puzzle> = Vec::new();
         let mut solution = BigUint::from(0u32);
         for _i in 0..40 {
             let secret: u64 = rng.gen();
@@ -154,4 +755,386 @@ mod tests {
         assert!(result == solution);
     }
 
+    #[test]
+    fn setup_with_tiny_primes() {
+        // With n = 15 a random base shares a factor with n almost half of the time.
+        for _ in 0..200 {
+            let lhtlp = LHTLP::from_primes(&BigUint::from(3u32), &BigUint::from(5u32), BigUint::from(10u32));
+            assert!(lhtlp.g.gcd(&lhtlp.n) == BigUint::from(1u32));
+            assert!(math::jacobi_symbol(&lhtlp.g, &lhtlp.n) == 1);
+            assert!(lhtlp.solve(lhtlp.generate(7)) == BigUint::from(7u32));
+        }
+    }
+
+    #[test]
+    fn setup_with_small_lambda() {
+        for lambda in [16u64, 24, 32] {
+            for _ in 0..50 {
+                let lhtlp = LHTLP::setup(lambda, BigUint::from(100u32));
+                assert!(lhtlp.n.bits() >= 2 * lambda - 1);
+                let puzzles = vec![lhtlp.generate(1000), lhtlp.generate(234)];
+                assert!(lhtlp.solve(lhtlp.evaluate(puzzles)) == BigUint::from(1234u32));
+            }
+        }
+    }
+
+    /// An instance whose generator shares the factor `p` with `n`, so decoding hits a missing inverse.
+    fn compromised_instance() -> (LHTLP, BigUint) {
+        let p = BigUint::from(1019u32);
+        let n = &p * BigUint::from(1187u32);
+        let params = PublicParams { g: p.clone(), h: &p * &p % &n, n };
+        (LHTLP::from_public_params(params, 10u64).unwrap(), p)
+    }
+
+    #[test]
+    fn modulus_compromised() {
+        let (lhtlp, p) = compromised_instance();
+        let puzzle = lhtlp.generate_with_randomness(&BigUint::from(42u32), &BigUint::from(12345u32));
+        match lhtlp.solve_with_policy(PartialSolution::new(puzzle), &SolvePolicy::default()) {
+            Err(LhtlpError::ModulusCompromised { gcd }) => {
+                assert!(gcd == p);
+                assert!((&lhtlp.n % &gcd) == BigUint::from(0u32));
+            }
+            other => panic!("expected a compromised modulus, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus compromised")]
+    fn unchecked_solve_panics_on_compromised_modulus() {
+        let (lhtlp, _) = compromised_instance();
+        let _ = lhtlp.solve(lhtlp.generate_with_randomness(&BigUint::from(42u32), &BigUint::from(12345u32)));
+    }
+
+    #[test]
+    fn evaluate_edge_cases() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let empty = lhtlp.evaluate(vec![]);
+        assert!(empty != Puzzle { u: BigUint::from(1u32), v: BigUint::from(1u32) });
+        assert!(empty != lhtlp.evaluate(vec![]));
+        assert!(lhtlp.solve(empty) == BigUint::from(0u32));
+
+        let puzzle = lhtlp.generate(42);
+        assert!(lhtlp.evaluate(vec![puzzle.clone()]) == puzzle);
+        let rerandomized = lhtlp.evaluate_rerandomized(vec![puzzle.clone()]);
+        assert!(rerandomized != puzzle);
+        assert!(lhtlp.solve(rerandomized) == BigUint::from(42u32));
+
+        let pair = vec![lhtlp.generate(1), lhtlp.generate(2)];
+        let rerandomized = lhtlp.evaluate_rerandomized(pair.clone());
+        assert!(rerandomized != lhtlp.evaluate(pair));
+        assert!(lhtlp.solve(rerandomized) == BigUint::from(3u32));
+    }
+
+    #[test]
+    fn plaintext_power_matches_modpow() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let n2 = &lhtlp.n2;
+        let base = BigUint::from(1u32) + &lhtlp.n;
+        let exponents = [BigUint::from(0u32), BigUint::from(1u32), BigUint::from(u64::MAX), &lhtlp.n - 1u32, n2 + 7u32];
+        for exponent in exponents {
+            assert!(lhtlp.plaintext_power(&exponent, n2) == base.modpow(&exponent, n2));
+        }
+    }
+
+    #[test]
+    fn setup_fast_insecure() {
+        for bits in [16, 17, 128, 257] {
+            let lhtlp = LHTLP::setup_fast_insecure(bits, 1000u64);
+            assert!(lhtlp.n.bits() == bits);
+            let puzzles = vec![lhtlp.generate(2), lhtlp.generate(3)];
+            assert!(lhtlp.solve(lhtlp.evaluate(puzzles)) == BigUint::from(5u32));
+        }
+    }
+
+    #[test]
+    fn generate_with_rng_is_deterministic() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        for secret in [0, 42, u64::MAX] {
+            let first = lhtlp.generate_with_rng(secret, &mut StdRng::seed_from_u64(7));
+            let second = lhtlp.generate_with_rng(secret, &mut StdRng::seed_from_u64(7));
+            assert!(first.to_bytes() == second.to_bytes());
+            assert!(first != lhtlp.generate_with_rng(secret, &mut StdRng::seed_from_u64(8)));
+            assert!(lhtlp.solve(first) == BigUint::from(secret));
+        }
+    }
+
+    #[test]
+    fn puzzle_size_bytes() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let size = lhtlp.puzzle_size_bytes();
+        let largest = Puzzle { u: &lhtlp.n - 1u32, v: &lhtlp.n * &lhtlp.n - 1u32 };
+        assert!(largest.to_bytes().len() == size);
+        for secret in 0..20 {
+            assert!(lhtlp.generate(secret).to_bytes().len() <= size);
+        }
+        assert!(lhtlp.params_size_bytes() > 5 * LAMBDA as usize / 8);
+    }
+
+    #[test]
+    fn trivial_u_skips_squarings() {
+        // A difficulty far beyond what could be squared in a test.
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1u64 << 60));
+        let one = BigUint::from(1u32);
+        let puzzle = Puzzle { u: one.clone(), v: lhtlp.plaintext_power(&BigUint::from(42u32), &lhtlp.n2) };
+        assert!(lhtlp.solve(puzzle) == BigUint::from(42u32));
+        assert!(lhtlp.solve(Puzzle { u: one.clone(), v: one }) == BigUint::from(0u32));
+    }
+
+    #[test]
+    fn mul_add_matches_two_steps() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let mut acc = lhtlp.generate(0);
+        for (secret, weight) in [(3u64, 2u32), (5, 0), (7, 10)] {
+            let puzzle = lhtlp.generate(secret);
+            let weight = BigUint::from(weight);
+            let next = lhtlp.mul_add(&acc, &puzzle, &weight);
+            assert!(next == lhtlp.add(&acc, &lhtlp.scalar_mul(&puzzle, &weight)));
+            acc = next;
+        }
+        assert!(lhtlp.solve(acc) == BigUint::from(76u32));
+    }
+
+    #[test]
+    fn evaluate_product_small() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let max = 6u64;
+        let m = BigUint::from(max * max + 1);
+        for (a, b) in [(0u64, 5u64), (3, 4), (6, 6), (5, 0)] {
+            let candidates = lhtlp.evaluate_product_small(&lhtlp.generate(a), &lhtlp.generate(b), max);
+            assert!(candidates.len() == max as usize + 1);
+            let solutions: Vec<BigUint> = candidates.into_iter().map(|c| lhtlp.solve(c)).collect();
+            let below: Vec<&BigUint> = solutions.iter().filter(|s| **s < m).collect();
+            assert!(below == [&BigUint::from(a * b)]);
+            assert!(solutions[a as usize] == BigUint::from(a * b));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "max_secret is too large")]
+    fn evaluate_product_small_bound() {
+        // 4 * 17 < 77 < 5 * 17: with a = 0 and b = 0, candidate 4 would embed 77 - 68 < 17.
+        let n = BigUint::from(77u32);
+        let lhtlp = LHTLP {
+            difficulty: BigUint::from(1u32),
+            n2: &n * &n,
+            n,
+            g: BigUint::from(4u32),
+            h: BigUint::from(16u32),
+            policy: SolvePolicy::default(),
+            hash_suite: HashSuite::default(),
+        };
+        let puzzle = Puzzle { u: BigUint::from(1u32), v: BigUint::from(1u32) };
+        let _ = lhtlp.evaluate_product_small(&puzzle, &puzzle, 4);
+    }
+
+    #[test]
+    #[ignore = "timing test, run with --release --ignored"]
+    fn solve_time_scales_linearly() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let puzzle = lhtlp.generate(42);
+        let per_squaring: Vec<f64> = [1u64 << 20, 1 << 21, 1 << 22, 1 << 23]
+            .iter()
+            .map(|&difficulty| {
+                let start = std::time::Instant::now();
+                std::hint::black_box(lhtlp.solve_with_difficulty(puzzle.clone(), &BigUint::from(difficulty)));
+                start.elapsed().as_secs_f64() / difficulty as f64
+            })
+            .collect();
+        let fastest = per_squaring.iter().cloned().fold(f64::INFINITY, f64::min);
+        let slowest = per_squaring.iter().cloned().fold(0.0, f64::max);
+        assert!(slowest < 1.5 * fastest, "time per squaring varies from {:e}s to {:e}s", fastest, slowest);
+
+        let per_rebase_squaring: Vec<f64> = [1u64 << 18, 1 << 19, 1 << 20, 1 << 21]
+            .iter()
+            .map(|&delta| {
+                let start = std::time::Instant::now();
+                std::hint::black_box(lhtlp.rebase(&puzzle, &BigUint::from(delta)));
+                start.elapsed().as_secs_f64() / delta as f64
+            })
+            .collect();
+        let fastest = per_rebase_squaring.iter().cloned().fold(f64::INFINITY, f64::min);
+        let slowest = per_rebase_squaring.iter().cloned().fold(0.0, f64::max);
+        assert!(slowest < 1.5 * fastest, "rebase time per squaring varies from {:e}s to {:e}s", fastest, slowest);
+    }
+
+    #[test]
+    fn evaluate_unique() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let puzzles: Vec<Puzzle> = [3u64, 5, 7].iter().map(|&s| lhtlp.generate(s)).collect();
+        let result = lhtlp.evaluate_unique(&puzzles).unwrap();
+        assert!(lhtlp.solve(result) == BigUint::from(15u32));
+
+        let mut duplicated = puzzles.clone();
+        duplicated.push(puzzles[1].clone());
+        assert!(lhtlp.evaluate_unique(&duplicated) == Err(LhtlpError::DuplicatePuzzle));
+
+        let mut rerandomized = puzzles.clone();
+        rerandomized.push(lhtlp.rerandomize(&puzzles[1]));
+        assert!(lhtlp.evaluate_unique(&rerandomized).is_ok());
+    }
+
+    #[test]
+    fn evaluate_prefix() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let puzzles: Vec<Puzzle> = [3u64, 5, 7, 11].iter().map(|&s| lhtlp.generate(s)).collect();
+        let prefix = lhtlp.evaluate_prefix(&puzzles);
+
+        assert!(prefix.len() == 4);
+        assert!(prefix[3] == lhtlp.evaluate(puzzles.clone()));
+        let sums: Vec<BigUint> = prefix.into_iter().map(|p| lhtlp.solve(p)).collect();
+        assert!(sums == [3u32, 8, 15, 26].map(BigUint::from));
+        assert!(lhtlp.evaluate_prefix(&[]).is_empty());
+    }
+
+    /// Whether every order of `puzzles` evaluates to the same solution, trying all orders of up
+    /// to 5 puzzles and `samples` random shuffles of larger batches.
+    fn commutative_check_sampled(lhtlp: &LHTLP, puzzles: Vec<Puzzle>, samples: usize) -> bool {
+        use rand::seq::SliceRandom;
+        let expected = lhtlp.solve(lhtlp.evaluate(puzzles.clone()));
+        let mut orders = Vec::new();
+        if puzzles.len() <= 5 {
+            // Heap's algorithm, yielding each of the n! orders once.
+            let mut order = puzzles;
+            let mut counters = vec![0; order.len()];
+            orders.push(order.clone());
+            let mut i = 1;
+            while i < order.len() {
+                if counters[i] < i {
+                    order.swap(if i % 2 == 0 { 0 } else { counters[i] }, i);
+                    orders.push(order.clone());
+                    counters[i] += 1;
+                    i = 1;
+                } else {
+                    counters[i] = 0;
+                    i += 1;
+                }
+            }
+        } else {
+            let mut rng = rand::thread_rng();
+            for _ in 0..samples {
+                let mut order = puzzles.clone();
+                order.shuffle(&mut rng);
+                orders.push(order);
+            }
+        }
+        orders.into_iter().all(|order| lhtlp.solve(lhtlp.evaluate(order)) == expected)
+    }
+
+    fn commutative_check(lhtlp: &LHTLP, puzzles: Vec<Puzzle>) -> bool {
+        commutative_check_sampled(lhtlp, puzzles, 1000)
+    }
+
+    #[test]
+    fn evaluate_commutative_check() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let small: Vec<Puzzle> = [1u64, u64::MAX, 0, 7, u64::MAX - 1].iter().map(|&s| lhtlp.generate(s)).collect();
+        assert!(commutative_check(&lhtlp, small));
+
+        let batch: Vec<Puzzle> = (0..10).map(|s| lhtlp.generate(u64::MAX - s)).collect();
+        assert!(commutative_check_sampled(&lhtlp, batch, 5));
+    }
+
+    #[test]
+    fn sort_then_evaluate() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut rng = rand::thread_rng();
+        let puzzles: Vec<Puzzle> = (0..10).map(|_| lhtlp.generate(rng.gen())).collect();
+        let mut sorted = puzzles.clone();
+        sorted.sort();
+
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        assert!(lhtlp.evaluate(sorted) == lhtlp.evaluate(puzzles));
+    }
+
+    #[test]
+    fn puzzle_bytes_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let bytes = puzzle.to_bytes();
+
+        assert!(Puzzle::from_bytes(&bytes) == Ok(puzzle));
+        assert!(Puzzle::from_bytes(&bytes[..bytes.len() - 1]) == Err(LhtlpError::InvalidEncoding));
+        assert!(Puzzle::from_bytes(&[bytes.as_slice(), &[0]].concat()) == Err(LhtlpError::InvalidEncoding));
+    }
+
+    #[test]
+    fn instance_equality() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let other = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let restricted = lhtlp.clone().with_policy(SolvePolicy { max_modulus_bits: Some(LAMBDA), ..SolvePolicy::default() });
+        let mut harder = lhtlp.clone();
+        harder.difficulty += 1u32;
+
+        assert!(lhtlp == restricted);
+        assert!(lhtlp != other && !lhtlp.same_modulus(&other));
+        assert!(lhtlp != harder && lhtlp.same_modulus(&harder));
+        assert!(lhtlp != lhtlp.clone().with_hash_suite(HashSuite::Sha384));
+    }
+
+    #[test]
+    fn rebase() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let puzzle = lhtlp.generate(42);
+        let delta = BigUint::from(500u32);
+        let rebased = lhtlp.rebase(&puzzle, &delta);
+
+        assert!(lhtlp.solve_with_difficulty(rebased.clone(), &BigUint::from(1500u32)) == lhtlp.solve(puzzle));
+        assert!(lhtlp.solve(rebased) != BigUint::from(42u32));
+    }
+
+    #[test]
+    fn rebase_huge_delta() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        // 2^delta would take 128 GiB; the trivial puzzle of zero keeps the squarings at 1.
+        let delta = BigUint::from(1u64 << 40);
+        let trivial = Puzzle { u: BigUint::from(1u32), v: BigUint::from(1u32) };
+        let rebased = lhtlp.rebase(&trivial, &delta);
+        assert!(rebased == trivial);
+        assert!(lhtlp.solve_with_difficulty(rebased, &(delta + 1000u32)) == BigUint::from(0u32));
+    }
+
+    #[test]
+    fn add_constant_and_negate() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1000u32));
+        let puzzle = lhtlp.add_constant(&lhtlp.generate(42), &BigUint::from(8u32));
+        assert!(lhtlp.solve(puzzle.clone()) == BigUint::from(50u32));
+
+        let difference = lhtlp.add(&puzzle, &lhtlp.negate(&lhtlp.generate(20)));
+        assert!(lhtlp.solve(difference) == BigUint::from(30u32));
+    }
+
+    #[test]
+    fn scalar_mul() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.scalar_mul(&lhtlp.generate(42), &BigUint::from(3u32));
+        assert!(lhtlp.solve(puzzle) == BigUint::from(126u32));
+    }
+
+    #[test]
+    fn sort_equal_puzzles() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let first = lhtlp.generate(42);
+        let second = lhtlp.generate(13);
+        let mut puzzles = vec![first.clone(), second.clone(), first.clone()];
+        puzzles.sort();
+
+        let expected = if first < second {
+            vec![first.clone(), first, second]
+        } else {
+            vec![second, first.clone(), first]
+        };
+        assert!(puzzles == expected);
+    }
+
+    #[test]
+    fn modulus_squared() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(lhtlp.modulus_squared() == &(&lhtlp.n * &lhtlp.n));
+        let puzzle = lhtlp.generate(42);
+        assert!(puzzle.v < *lhtlp.modulus_squared());
+    }
 }
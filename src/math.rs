@@ -0,0 +1,81 @@
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::ToPrimitive;
+
+/// Jacobi symbol `(a / n)` for an odd `n`, computed with the binary variant of the Euclidean
+/// algorithm based on quadratic reciprocity.
+///
+/// Returns `0` when `a` and `n` share a factor. Panics if `n` is even.
+pub(crate) fn jacobi_symbol(a: &BigUint, n: &BigUint) -> i8 {
+    assert!(n.is_odd(), "the Jacobi symbol is only defined for odd n");
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut result = 1i8;
+    while a.bits() > 0 {
+        let twos = a.trailing_zeros().unwrap_or(0);
+        a >>= twos;
+        // (2 / n) = -1 exactly when n = 3 or 5 mod 8.
+        let n_mod_8 = n.iter_u64_digits().next().unwrap_or(0) % 8;
+        if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            result = -result;
+        }
+        // Reciprocity flips the sign when both are 3 mod 4.
+        let a_mod_4 = a.iter_u64_digits().next().unwrap_or(0) % 4;
+        if a_mod_4 == 3 && n_mod_8 % 4 == 3 {
+            result = -result;
+        }
+        std::mem::swap(&mut a, &mut n);
+        a %= &n;
+    }
+    if n == BigUint::from(1u32) {
+        result
+    } else {
+        0
+    }
+}
+
+/// Base-2 logarithm of a positive `value`, accurate to the precision of `f64` at any size.
+pub(crate) fn log2(value: &BigUint) -> f64 {
+    let shift = value.bits().saturating_sub(f64::MANTISSA_DIGITS as u64);
+    (value >> shift).to_f64().unwrap_or(f64::INFINITY).log2() + shift as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jacobi_matches_euler_criterion() {
+        for p in [3u32, 5, 7, 11, 13, 1019] {
+            let p = BigUint::from(p);
+            let exponent = (&p - 1u32) / 2u32;
+            for a in 0u32..60 {
+                let a = BigUint::from(a);
+                let euler = a.modpow(&exponent, &p);
+                let expected = if (&a % &p).bits() == 0 {
+                    0
+                } else if euler == BigUint::from(1u32) {
+                    1
+                } else {
+                    -1
+                };
+                assert!(jacobi_symbol(&a, &p) == expected, "({} / {})", a, p);
+            }
+        }
+    }
+
+    #[test]
+    fn jacobi_composite_moduli() {
+        let cases = [(2u32, 15u32, 1i8), (7, 15, -1), (5, 15, 0), (1001, 9907, -1), (19, 45, 1), (8, 21, -1), (1, 1, 1)];
+        for (a, n, expected) in cases {
+            assert!(jacobi_symbol(&BigUint::from(a), &BigUint::from(n)) == expected, "({} / {})", a, n);
+        }
+    }
+
+    #[test]
+    fn log2_of_large_values() {
+        assert!(log2(&BigUint::from(1u32)) == 0.0);
+        assert!(log2(&BigUint::from(1024u32)) == 10.0);
+        assert!((log2(&(BigUint::from(3u32) << 1000)) - (1000.0 + 3f64.log2())).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{Puzzle, LHTLP};
+use sha2::{Digest, Sha256};
+
+/// Merkle leaf of a puzzle, domain-separated from inner nodes.
+pub(crate) fn leaf(puzzle: &Puzzle) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(puzzle.fingerprint());
+    hasher.finalize().into()
+}
+
+/// Merkle inner node over two children.
+pub(crate) fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Pair the nodes of a level into the level above, carrying up the last node of an odd count.
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root over `puzzles`, in order.
+///
+/// Nodes are paired level by level; the last node of a level with an odd count is carried up
+/// unchanged. The root of an empty set is the SHA-256 digest of the empty string.
+pub(crate) fn root(puzzles: &[Puzzle]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = puzzles.iter().map(leaf).collect();
+    if level.is_empty() {
+        return Sha256::digest([]).into();
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Merkle root committing to a set of puzzles, in order, against which membership is proven.
+pub fn set_root(set: &[Puzzle]) -> [u8; 32] {
+    root(set)
+}
+
+/// Merkle inclusion proof of the puzzle at `index` in a set of `len` puzzles.
+///
+/// `siblings` lists the sibling hashes from the leaf up to the root, skipping the levels where
+/// the node has no sibling and is carried up unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MembershipProof {
+    pub index: usize,
+    pub len: usize,
+    pub siblings: Vec<[u8; 32]>,
+}
+
+/// Check that `puzzle` is in the set committed to by `set_root`, at the index given by `proof`.
+pub fn verify_membership(set_root: &[u8; 32], puzzle: &Puzzle, proof: &MembershipProof) -> bool {
+    if proof.index >= proof.len {
+        return false;
+    }
+    let mut siblings = proof.siblings.iter();
+    let mut hash = leaf(puzzle);
+    let (mut index, mut len) = (proof.index, proof.len);
+    while len > 1 {
+        let sibling = index ^ 1;
+        if sibling < len {
+            let Some(sibling_hash) = siblings.next() else {
+                return false;
+            };
+            hash = if index % 2 == 0 { node(&hash, sibling_hash) } else { node(sibling_hash, &hash) };
+        }
+        index /= 2;
+        len = len.div_ceil(2);
+    }
+    siblings.next().is_none() && &hash == set_root
+}
+
+/// Proof that an evaluated puzzle combines a committed list of puzzles, unmodified and in order.
+///
+/// `root` is a SHA-256 Merkle root over the input puzzles, so a single input can later be
+/// disclosed against it without revealing the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvalProof {
+    pub root: [u8; 32],
+    pub count: usize,
+}
+
+impl LHTLP {
+    /// Evaluate puzzles like `evaluate`, committing to the inputs in an `EvalProof`.
+    #[must_use]
+    pub fn eval_and_prove(&self, puzzles: Vec<Puzzle>) -> (Puzzle, EvalProof) {
+        let proof = EvalProof { root: root(&puzzles), count: puzzles.len() };
+        (self.evaluate(puzzles), proof)
+    }
+
+    /// Prove that the puzzle at `index` belongs to `set`, as committed to by `set_root(set)`.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn prove_membership(&self, set: &[Puzzle], index: usize) -> MembershipProof {
+        assert!(index < set.len(), "membership index out of bounds");
+        let mut level: Vec<[u8; 32]> = set.iter().map(leaf).collect();
+        let mut siblings = Vec::new();
+        let mut position = index;
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        MembershipProof { index, len: set.len(), siblings }
+    }
+
+    /// Check that `combined` is the evaluation of `original` and that `proof` commits to exactly these puzzles.
+    pub fn verify_eval_proof(&self, combined: &Puzzle, proof: &EvalProof, original: &[Puzzle]) -> bool {
+        original.len() == proof.count
+            && root(original) == proof.root
+            && &self.evaluate(original.to_vec()) == combined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn eval_proof() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (0..8).map(|s| lhtlp.generate(s)).collect();
+        let (combined, proof) = lhtlp.eval_and_prove(puzzles.clone());
+
+        assert!(lhtlp.verify_eval_proof(&combined, &proof, &puzzles));
+        assert!(lhtlp.solve(combined.clone()) == BigUint::from(28u32));
+
+        let mut tampered = puzzles.clone();
+        tampered[3] = lhtlp.generate(3);
+        assert!(!lhtlp.verify_eval_proof(&combined, &proof, &tampered));
+
+        let (tampered_combined, _) = lhtlp.eval_and_prove(tampered);
+        assert!(!lhtlp.verify_eval_proof(&tampered_combined, &proof, &puzzles));
+    }
+
+    #[test]
+    fn membership() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let set: Vec<Puzzle> = (0..16).map(|s| lhtlp.generate(s)).collect();
+        let root = set_root(&set);
+
+        for index in [0, 1, 7, 10, 15] {
+            let proof = lhtlp.prove_membership(&set, index);
+            assert!(verify_membership(&root, &set[index], &proof));
+            assert!(!verify_membership(&root, &set[(index + 1) % 16], &proof));
+            assert!(!verify_membership(&root, &lhtlp.generate(index as u64), &proof));
+
+            let mut moved = proof.clone();
+            moved.index = (index + 3) % 16;
+            assert!(!verify_membership(&root, &set[index], &moved));
+        }
+
+        let mut truncated = lhtlp.prove_membership(&set, 5);
+        truncated.siblings.pop();
+        assert!(!verify_membership(&root, &set[5], &truncated));
+    }
+
+    #[test]
+    fn membership_in_odd_sized_set() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let set: Vec<Puzzle> = (0..5).map(|s| lhtlp.generate(s)).collect();
+        let root = set_root(&set);
+        for index in 0..5 {
+            assert!(verify_membership(&root, &set[index], &lhtlp.prove_membership(&set, index)));
+        }
+    }
+
+    #[test]
+    fn odd_sized_root() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (0..3).map(|s| lhtlp.generate(s)).collect();
+        let expected = node(&node(&leaf(&puzzles[0]), &leaf(&puzzles[1])), &leaf(&puzzles[2]));
+        assert!(root(&puzzles) == expected);
+        assert!(root(&puzzles[..1]) == leaf(&puzzles[0]));
+    }
+}
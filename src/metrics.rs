@@ -0,0 +1,128 @@
+use crate::{pow_multiplications, LhtlpError, Puzzle, PuzzleGroup, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+
+/// Count of the modular operations performed by a single call.
+///
+/// `squarings` only counts the sequential squarings of the time-lock chain, which are the
+/// operations an attacker has to perform one after the other. Exponentiations done outside of
+/// the chain are counted in `multiplications` as the steps of square-and-multiply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub multiplications: u64,
+    pub squarings: u64,
+    pub inversions: u64,
+}
+
+impl Metrics {
+    /// Count the decoding of `count` values sharing one mask `w^n`: the steps of the exponentiation
+    /// by `n`, one inversion, and one multiplication per value.
+    fn count_decode(&mut self, n: &BigUint, count: u64) {
+        self.multiplications += pow_multiplications(n) + count;
+        self.inversions += 1;
+    }
+}
+
+impl LHTLP {
+    /// Generate a puzzle like `generate`, counting the operations performed.
+    pub fn generate_with_metrics(&self, secret: u64) -> (Puzzle, Metrics) {
+        let mut metrics = Metrics::default();
+        let puzzle = self.generate_with_rng_counted(secret, &mut rand::thread_rng(), &mut metrics.multiplications);
+        (puzzle, metrics)
+    }
+
+    /// Open a puzzle like `solve`, counting the operations performed.
+    ///
+    /// The squarings of `solve` are counted as they are performed, so `Metrics::squarings` equals
+    /// the difficulty unless the chain reaches 1 early. Fails with
+    /// `LhtlpError::ModulusCompromised` where `solve` would panic.
+    pub fn solve_with_metrics(&self, puzzle: Puzzle) -> Result<(BigUint, Metrics), LhtlpError> {
+        let mut metrics = Metrics::default();
+        let w = LHTLP::square_in_chunks(&puzzle.u, &self.difficulty, &self.n, SQUARING_CHUNK, &mut metrics.squarings);
+        let secret = self.try_decode(&puzzle.v, &w)?;
+        metrics.count_decode(&self.n, 1);
+        Ok((secret, metrics))
+    }
+
+    /// Open a group of puzzles like `solve_group`, counting the operations performed.
+    ///
+    /// The squaring chain is shared, so `Metrics::squarings` equals the difficulty whatever the
+    /// size of the group. Fails with `LhtlpError::ModulusCompromised` where `solve_group` would
+    /// panic.
+    pub fn solve_group_with_metrics(&self, group: &PuzzleGroup) -> Result<(Vec<BigUint>, Metrics), LhtlpError> {
+        let mut metrics = Metrics::default();
+        let secrets = self.try_solve_group(group, &mut metrics.squarings)?;
+        metrics.count_decode(&self.n, secrets.len() as u64);
+        Ok((secrets, metrics))
+    }
+
+    /// Evaluate puzzles like `evaluate`, counting the operations performed.
+    pub fn evaluate_with_metrics(&self, puzzles: Vec<Puzzle>) -> (Puzzle, Metrics) {
+        let mut metrics = Metrics::default();
+        let result = self.evaluate_counted(puzzles, &mut metrics.multiplications);
+        (result, metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn solve_squarings() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let (puzzle, _) = lhtlp.generate_with_metrics(42);
+        let (secret, metrics) = lhtlp.solve_with_metrics(puzzle).unwrap();
+
+        assert!(secret == BigUint::from(42u32));
+        assert!(metrics.squarings == DIFFICULTY);
+        assert!(metrics.inversions == 1);
+        assert!(metrics.multiplications == lhtlp.n.bits() + lhtlp.n.count_ones() + 1);
+
+        let malformed = Puzzle { u: lhtlp.n.clone(), v: BigUint::from(0u32) };
+        assert!(matches!(lhtlp.solve_with_metrics(malformed), Err(LhtlpError::ModulusCompromised { .. })));
+    }
+
+    #[test]
+    fn generate_counts() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let (puzzle, metrics) = lhtlp.generate_with_metrics(42);
+
+        assert!(lhtlp.solve(puzzle) == BigUint::from(42u32));
+        assert!(metrics.squarings == 0);
+        assert!(metrics.inversions == 0);
+        assert!(metrics.multiplications > 0);
+    }
+
+    #[test]
+    fn solve_group_single_chain() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let secrets: Vec<u64> = (0..10).collect();
+        let group = lhtlp.generate_group(&secrets);
+        let (solutions, metrics) = lhtlp.solve_group_with_metrics(&group).unwrap();
+
+        assert!(solutions == secrets.iter().map(|&s| BigUint::from(s)).collect::<Vec<_>>());
+        assert!(solutions == lhtlp.solve_group(&group));
+        assert!(metrics.squarings == DIFFICULTY);
+        assert!(metrics.inversions == 1);
+
+        let malformed = PuzzleGroup { u: lhtlp.n.clone(), vs: vec![BigUint::from(0u32)] };
+        assert!(matches!(lhtlp.solve_group_with_metrics(&malformed), Err(LhtlpError::ModulusCompromised { .. })));
+    }
+
+    #[test]
+    fn evaluate_multiplications() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = (0..7).map(|s| lhtlp.generate(s)).collect();
+        let (result, metrics) = lhtlp.evaluate_with_metrics(puzzles.clone());
+
+        assert!(metrics.multiplications == 2 * (7 - 1));
+        assert!(result == lhtlp.evaluate(puzzles));
+
+        let (zero, metrics) = lhtlp.evaluate_with_metrics(Vec::new());
+        assert!(lhtlp.solve(zero) == BigUint::from(0u32));
+        assert!(metrics.multiplications > 2 * lhtlp.n.bits());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{HTable, LhtlpError, PrivateKey, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// Puzzles at several hardness levels sharing one modulus and generator, for protocols releasing
+/// information in stages.
+///
+/// Level `i` uses its own `h_i = g^(2^difficulties[i]) mod n`, so a puzzle of one level can only
+/// be solved at that level's difficulty. Puzzles of different levels must not be combined.
+#[derive(Debug, Clone)]
+pub struct MultiDifficultyLHTLP {
+    lhtlp: LHTLP,
+    difficulties: Vec<BigUint>,
+    levels: Vec<LHTLP>,
+}
+
+impl MultiDifficultyLHTLP {
+    /// Levels at `difficulties` over the modulus and generator of `lhtlp`, without the trapdoor.
+    ///
+    /// Each `h_i` is computed by sequential squaring from `g`, continuing from the previous level,
+    /// so this costs as much as solving a puzzle of the largest difficulty. Fails with
+    /// `LhtlpError::DifficultyTooLarge` if a difficulty does not fit in `u64` squarings.
+    pub fn from_lhtlp_and_difficulties(lhtlp: LHTLP, difficulties: Vec<BigUint>) -> Result<MultiDifficultyLHTLP, LhtlpError> {
+        let mut table = HTable::new(lhtlp.n.clone(), lhtlp.g.clone());
+        let levels = difficulties
+            .iter()
+            .map(|difficulty| lhtlp.recompute_for_difficulty(difficulty.clone(), &mut table))
+            .collect::<Result<Vec<LHTLP>, LhtlpError>>()?;
+        Ok(MultiDifficultyLHTLP { lhtlp, difficulties, levels })
+    }
+
+    /// Levels at `difficulties`, computing each `h_i` with the trapdoor in a single exponentiation.
+    ///
+    /// Fails with `LhtlpError::ParameterMismatch` if `key` is not the factorization of `n`.
+    pub fn from_lhtlp_and_difficulties_with_trapdoor(
+        lhtlp: LHTLP,
+        difficulties: Vec<BigUint>,
+        key: &PrivateKey,
+    ) -> Result<MultiDifficultyLHTLP, LhtlpError> {
+        if key.modulus() != lhtlp.n {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        let levels = difficulties
+            .iter()
+            .map(|difficulty| {
+                let exponent = BigUint::from(2u32).modpow(difficulty, &key.phi_half());
+                let h = lhtlp.g.modpow(&exponent, &lhtlp.n);
+                LHTLP { difficulty: difficulty.clone(), h, ..lhtlp.clone() }
+            })
+            .collect();
+        Ok(MultiDifficultyLHTLP { lhtlp, difficulties, levels })
+    }
+
+    /// The instance the levels were derived from.
+    pub fn lhtlp(&self) -> &LHTLP {
+        &self.lhtlp
+    }
+
+    /// The difficulty of each level.
+    pub fn difficulties(&self) -> &[BigUint] {
+        &self.difficulties
+    }
+
+    /// The instance of `level`, failing with `LhtlpError::IndexOutOfRange` past the last level.
+    pub fn level(&self, level: usize) -> Result<&LHTLP, LhtlpError> {
+        self.levels.get(level).ok_or(LhtlpError::IndexOutOfRange { index: level as u64, len: self.levels.len() as u64 })
+    }
+
+    /// Generate a puzzle embedding `secret` at `level`.
+    ///
+    /// Fails with `LhtlpError::IndexOutOfRange` for an unknown level and with
+    /// `LhtlpError::InvalidSecret` if `secret` does not fit the plaintext space.
+    pub fn generate_at_level(&self, secret: &BigUint, level: usize) -> Result<Puzzle, LhtlpError> {
+        self.level(level)?.generate_biguint(secret)
+    }
+
+    /// Solve a puzzle of `level` with `solve_checked`.
+    pub fn solve_at_level(&self, puzzle: &Puzzle, level: usize) -> Result<BigUint, LhtlpError> {
+        self.level(level)?.solve_checked(puzzle.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::fixed_instance;
+
+    #[test]
+    fn levels() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(1000u32));
+        let difficulties: Vec<BigUint> = [100u32, 2000, 500].map(BigUint::from).to_vec();
+
+        let multi = MultiDifficultyLHTLP::from_lhtlp_and_difficulties(lhtlp.clone(), difficulties.clone()).unwrap();
+        let trapdoor = MultiDifficultyLHTLP::from_lhtlp_and_difficulties_with_trapdoor(lhtlp, difficulties.clone(), &key).unwrap();
+        assert!(multi.difficulties() == difficulties.as_slice());
+        for level in 0..difficulties.len() {
+            assert!(multi.level(level).unwrap() == trapdoor.level(level).unwrap());
+            let puzzle = multi.generate_at_level(&BigUint::from(42u32 + level as u32), level).unwrap();
+            assert!(multi.solve_at_level(&puzzle, level) == Ok(BigUint::from(42u32 + level as u32)));
+            assert!(multi.solve_at_level(&puzzle, (level + 1) % 3) != Ok(BigUint::from(42u32 + level as u32)));
+        }
+
+        let out_of_range = LhtlpError::IndexOutOfRange { index: 3, len: 3 };
+        assert!(multi.generate_at_level(&BigUint::from(1u32), 3) == Err(out_of_range.clone()));
+        assert!(multi.solve_at_level(&multi.lhtlp().generate(1), 3) == Err(out_of_range));
+    }
+}
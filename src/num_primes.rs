@@ -1,5 +1,20 @@
-#![no_std]
+//! Prime generation and primality testing.
+//!
+//! `Generator` produces random primes and safe primes for `LHTLP::setup`. The standalone
+//! `is_prime` and `is_safe_prime` functions test arbitrary values with a chosen number of
+//! Miller-Rabin rounds, for instance to validate imported parameters:
+//!
+//! ```
+//! use lhtlp::num_primes::{is_prime, is_safe_prime};
+//! use num_bigint::BigUint;
+//!
+//! assert!(is_prime(&BigUint::from(1019u32), 32));
+//! assert!(is_safe_prime(&BigUint::from(1019u32), 32));
+//! assert!(!is_prime(&BigUint::from(561u32), 32));
+//! ```
 #![forbid(unsafe_code)]
+// Vendored from num-primes, kept close to upstream.
+#![allow(clippy::needless_return, clippy::bool_comparison, clippy::needless_bool, clippy::assign_op_pattern, clippy::needless_borrow, clippy::collapsible_if)]
 
 extern crate rand;
 extern crate num_bigint as bigint;
@@ -12,10 +27,6 @@ pub use bigint::{BigUint,RandBigInt};
 use num_traits::{Zero, One};
 use num_traits::*;
 
-use log::debug;
-use log::error;
-use log::info;
-
 // Settings
 // NIST recomends 5 rounds for miller rabin. This implementation does 8. Apple uses 16. Three iterations has a probability of 2^80 of failing
 const MILLER_RABIN_ROUNDS: usize = 8usize;
@@ -101,7 +112,7 @@ impl Generator {
             let candidate: BigUint = rng.gen_biguint(n);
             //candidate.set_bit(0, true);
             //candidate.set_bit((n-1) as u32, true);
-            if is_prime(&candidate) == false { 
+            if probably_prime(&candidate) == false { 
                 return candidate;
             }
         }
@@ -153,7 +164,7 @@ impl Generator {
             //candidate.set_bit(0, true);
             //candidate.set_bit((n-1) as u32, true);
             
-            if is_prime(&candidate) == true { 
+            if probably_prime(&candidate) == true { 
                 return candidate;
             }
         }
@@ -173,11 +184,12 @@ impl Generator {
         let mut rng = rand::thread_rng();
         loop {
             // Make mutable and set LSB and MSB
-            let candidate: BigUint = rng.gen_biguint(n);
-            //candidate.set_bit(0, true);
-            //candidate.set_bit((n-1) as u32, true);
-            if is_prime(&candidate) == true {
-                if is_safe_prime(&candidate) == true {
+            // Set the MSB so the safe prime has exactly n bits, and the LSB as it is odd
+            let mut candidate: BigUint = rng.gen_biguint(n);
+            candidate.set_bit(0, true);
+            candidate.set_bit(n - 1, true);
+            if probably_prime(&candidate) == true {
+                if half_is_prime(&candidate) == true {
                     // checks with (p-1/n)
                     return candidate
                 }
@@ -188,10 +200,10 @@ impl Generator {
 
 impl Verification {
     pub fn is_prime(n: &BigUint) -> bool {
-        return is_prime(n);
+        return probably_prime(n);
     }
     pub fn is_composite(n: &BigUint) -> bool {
-        let x: bool = is_prime(n);
+        let x: bool = probably_prime(n);
         
         if x == true {
             return false
@@ -204,7 +216,7 @@ impl Verification {
         }
     }
     pub fn is_safe_prime(n: &BigUint) -> bool {
-        return is_safe_prime(n);
+        return half_is_prime(n);
     }
     /// # Very Smooth Number
     /// This Function Is Deprecated And Should Rarely Be Used
@@ -251,7 +263,7 @@ impl Factorization {
     /// ```
     pub fn prime_factor(mut n: BigUint) -> Option<BigUint> {
         // Check Primality and if prime, returns prime
-        if is_prime(&n) {
+        if probably_prime(&n) {
             return Some(n)
         }
 
@@ -266,11 +278,10 @@ impl Factorization {
         // STEP 2 | 3..sqrt(n) | Divide i by n. On failure, add 2 to i
         let n_sqrt = n.sqrt().to_usize().unwrap();
         
-        for mut i in 3..n_sqrt {
-            while n.divides(&BigUint::from(i)) {
+        for i in 3..n_sqrt {
+            while n.is_multiple_of(&BigUint::from(i)) {
                 n = n / BigUint::from(i);
             }
-            i = i + 2usize;
         }
 
         // Step 3
@@ -285,6 +296,34 @@ impl Factorization {
     }
 }
 
+/// Primes used to settle small values and strip small factors before Miller-Rabin.
+const TRIAL_PRIMES: [u32; 15] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+
+/// Test `n` for primality with trial division and `certainty` Miller-Rabin rounds on random bases.
+///
+/// Primes are always accepted. A composite passes with probability at most `4^-certainty`,
+/// Carmichael numbers included.
+pub fn is_prime(n: &BigUint, certainty: u32) -> bool {
+    for p in TRIAL_PRIMES {
+        if *n == BigUint::from(p) {
+            return true;
+        }
+        if (n % p).is_zero() {
+            return false;
+        }
+    }
+    if *n < BigUint::from(2u32) {
+        return false;
+    }
+    miller_rabin(n, certainty as usize)
+}
+
+/// Test that `n` is a safe prime, that is both `n` and `(n - 1) / 2` are prime, with
+/// `certainty` Miller-Rabin rounds each.
+pub fn is_safe_prime(n: &BigUint, certainty: u32) -> bool {
+    n.is_odd() && is_prime(n, certainty) && is_prime(&(n >> 1), certainty)
+}
+
 // if true, then is not prime
 // if false, then maybe prime
 fn div_small_primes(numb: &BigUint) -> bool {
@@ -366,7 +405,6 @@ fn miller_rabin(candidate: &BigUint, limit: usize) -> bool {
         else {
             // Convert To Usizes For Loop
             // step = (s - 1)
-            let one_usize = one.to_usize().unwrap();
             let zero_usize = zero.to_usize().unwrap();
             
             let mut break_early = false;
@@ -419,7 +457,7 @@ fn rewrite(n: &BigUint) -> (BigUint,BigUint) {
 
 // true = probably prime
 // false = not prime (composite)
-fn is_prime(candidate: &BigUint) -> bool {
+fn probably_prime(candidate: &BigUint) -> bool {
     let zero: BigUint = Zero::zero();
     let one: BigUint = One::one();
     let two: BigUint = &one + &one;
@@ -449,11 +487,11 @@ fn is_prime(candidate: &BigUint) -> bool {
     else {
         return true
     }
-    return true
 }
 
 // p = 2q + 1
 #[deprecated]
+#[allow(dead_code)]
 fn is_safe_prime_add(number: &BigUint) -> bool {    
     // number == q
     
@@ -465,7 +503,7 @@ fn is_safe_prime_add(number: &BigUint) -> bool {
     // p
     let p = x + one;
     
-    if is_prime(&p) {
+    if probably_prime(&p) {
         return true;
     }
     else {
@@ -474,13 +512,13 @@ fn is_safe_prime_add(number: &BigUint) -> bool {
 }
 
 // (p - 1)/2
-fn is_safe_prime(number: &BigUint) -> bool {
+fn half_is_prime(number: &BigUint) -> bool {
     let one = BigUint::one();
     let two = &one + &one;
 
     let result = (number - one) / two;
 
-    if is_prime(&result) {
+    if probably_prime(&result) {
         return true
     }
     else {
@@ -508,6 +546,7 @@ fn vsn(m: &BigUint,n: f64, c: u32) -> bool {
     }
 }
 
+#[allow(dead_code, unused)]
 fn pollard_rho(mut n: BigUint) {
     // Initialize Random Number Generator
     let mut rng = rand::thread_rng();
@@ -546,3 +585,54 @@ fn prime_factor(){
     let x = Generator::new_uint(16);
     let _prime_factor = Factorization::prime_factor(x);
 }
+
+#[test]
+fn is_prime_small_values() {
+    let mut sieve = vec![true; 2000];
+    sieve[0] = false;
+    sieve[1] = false;
+    for i in 2..sieve.len() {
+        if sieve[i] {
+            for multiple in (2 * i..sieve.len()).step_by(i) {
+                sieve[multiple] = false;
+            }
+        }
+    }
+    for (i, &prime) in sieve.iter().enumerate() {
+        assert!(is_prime(&BigUint::from(i), 8) == prime, "{}", i);
+        let safe = prime && i % 2 == 1 && sieve[i / 2];
+        assert!(is_safe_prime(&BigUint::from(i), 8) == safe, "{}", i);
+    }
+}
+
+#[test]
+fn is_prime_large_values() {
+    let one = BigUint::one();
+    for exponent in [61u32, 89, 107, 127] {
+        let mersenne = (&one << exponent) - &one;
+        assert!(is_prime(&mersenne, 32));
+        assert!(!is_prime(&(&mersenne * &mersenne), 32));
+        // (2^k - 2) / 2 = 2^(k-1) - 1 is composite, as k - 1 is composite.
+        assert!(!is_safe_prime(&mersenne, 32));
+    }
+    // 2^64 - 59 is prime and 2^64 - 1 is not.
+    assert!(is_prime(&BigUint::from(u64::MAX - 58), 32));
+    assert!(!is_prime(&BigUint::from(u64::MAX), 32));
+
+    let safe = Generator::safe_prime(64);
+    assert!(is_safe_prime(&safe, 32));
+    assert!(!is_safe_prime(&(&safe - 2u32), 32));
+}
+
+#[test]
+fn is_prime_rejects_carmichael_numbers() {
+    let carmichael: [u64; 10] = [
+        561, 1105, 1729, 2465, 2821, 6601, 8911, 41041, 825265, 3215031751,
+    ];
+    for n in carmichael {
+        assert!(!is_prime(&BigUint::from(n), 32), "{}", n);
+    }
+    // Strong pseudoprime to every prime base up to 23, and to every prime base up to 37.
+    assert!(!is_prime(&BigUint::from(3825123056546413051u64), 32));
+    assert!(!is_prime(&"318665857834031151167461".parse::<BigUint>().unwrap(), 32));
+}
@@ -0,0 +1,108 @@
+use crate::{LhtlpError, PartialSolution, Puzzle, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+/// SHA-256 digest of an intermediate squaring value `w`.
+pub fn squaring_digest(w: &BigUint) -> [u8; 32] {
+    Sha256::digest(w.to_bytes_be()).into()
+}
+
+impl LHTLP {
+    /// Open a puzzle, reporting `(i, digest)` to `observer` every `every_n` squarings.
+    ///
+    /// The digest is `squaring_digest` of `w = u^(2^i) mod n`, letting a monitor follow the
+    /// progress of a long solve without receiving the full values. Fails with
+    /// `LhtlpError::DifficultyTooLarge` if the difficulty does not fit in `u64`.
+    pub fn solve_with_observer(&self, puzzle: Puzzle, every_n: u64, observer: impl FnMut(u64, [u8; 32])) -> Result<BigUint, LhtlpError> {
+        self.solve_with_snapshots(puzzle, every_n, None, observer).map(|(solution, _)| solution)
+    }
+
+    /// Like `solve_with_observer`, also keeping the full state every `snapshot_every` squarings.
+    ///
+    /// The snapshots make recorded digests cheap to spot-check with `verify_checkpoint`.
+    pub fn solve_with_snapshots(
+        &self,
+        puzzle: Puzzle,
+        every_n: u64,
+        snapshot_every: Option<u64>,
+        mut observer: impl FnMut(u64, [u8; 32]),
+    ) -> Result<(BigUint, Vec<PartialSolution>), LhtlpError> {
+        assert!(every_n > 0, "observer interval must be positive");
+        let total = self.try_squarings()?;
+        let mut state = PartialSolution::new(puzzle);
+        let mut snapshots = Vec::new();
+        let next_multiple = |done: u64, every: u64| (done / every + 1).saturating_mul(every);
+        while state.squarings < total {
+            // Square up to the next reported or snapshotted count in one call.
+            let mut next = total.min(next_multiple(state.squarings, every_n));
+            if let Some(every) = snapshot_every.filter(|&every| every > 0) {
+                next = next.min(next_multiple(state.squarings, every));
+            }
+            state.w = LHTLP::square_in_chunks(&state.w, &BigUint::from(next - state.squarings), &self.n, SQUARING_CHUNK, &mut 0);
+            state.squarings = next;
+            if state.squarings.is_multiple_of(every_n) {
+                observer(state.squarings, squaring_digest(&state.w));
+            }
+            if snapshot_every.is_some_and(|every| every > 0 && state.squarings.is_multiple_of(every)) {
+                snapshots.push(state.clone());
+            }
+        }
+        Ok((self.try_decode(&state.puzzle.v, &state.w)?, snapshots))
+    }
+
+    /// Check a recorded `(i, expected)` digest of a solve of `puzzle`.
+    ///
+    /// Recomputes `w` from the latest of the `snapshots` of this puzzle taken at or before `i`,
+    /// or from the start if there is none, so the cost is bounded by the snapshot interval.
+    pub fn verify_checkpoint(&self, puzzle: &Puzzle, snapshots: &[PartialSolution], i: u64, expected: &[u8; 32]) -> bool {
+        let mut state = snapshots
+            .iter()
+            .filter(|snapshot| &snapshot.puzzle == puzzle && snapshot.squarings <= i)
+            .max_by_key(|snapshot| snapshot.squarings)
+            .cloned()
+            .unwrap_or_else(|| PartialSolution::new(puzzle.clone()));
+        if state.squarings < i {
+            state.w = LHTLP::square_in_chunks(&state.w, &BigUint::from(i - state.squarings), &self.n, SQUARING_CHUNK, &mut 0);
+        }
+        &squaring_digest(&state.w) == expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn observed_digests() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let mut recorded = Vec::new();
+        let solution = lhtlp.solve_with_observer(puzzle.clone(), 100, |i, digest| recorded.push((i, digest)));
+
+        assert!(solution == Ok(BigUint::from(42u32)));
+        assert!(recorded.len() == 10);
+        for (i, digest) in recorded {
+            assert!(digest == squaring_digest(&lhtlp.square(&puzzle.u, &BigUint::from(i))));
+        }
+    }
+
+    #[test]
+    fn spot_check_from_snapshots() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let mut recorded = Vec::new();
+        let (solution, snapshots) =
+            lhtlp.solve_with_snapshots(puzzle.clone(), 50, Some(300), |i, digest| recorded.push((i, digest))).unwrap();
+
+        assert!(solution == BigUint::from(42u32));
+        assert!(snapshots.iter().map(|s| s.squarings).collect::<Vec<_>>() == vec![300, 600, 900]);
+        for (i, digest) in &recorded {
+            assert!(lhtlp.verify_checkpoint(&puzzle, &snapshots, *i, digest));
+            assert!(!lhtlp.verify_checkpoint(&puzzle, &snapshots, *i + 1, digest));
+        }
+        assert!(lhtlp.verify_checkpoint(&puzzle, &[], recorded[0].0, &recorded[0].1));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::vdf::expand;
+use crate::{Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+impl LHTLP {
+    /// Generate a puzzle whose secret and randomness are derived from `seed` by hashing.
+    ///
+    /// The secret is uniform modulo `n` and the randomness uniform in `[1, n^2)`, up to a
+    /// negligible bias.
+    pub(crate) fn generate_from_seed(&self, seed: &[u8]) -> (BigUint, Puzzle) {
+        let n2 = &self.n2;
+        let secret = expand(self.hash_suite, &[b"lhtlp-oracle-secret", seed], 2 * self.n.bits() as usize) % &self.n;
+        let r = expand(self.hash_suite, &[b"lhtlp-oracle-randomness", seed], 2 * n2.bits() as usize) % (n2 - 1u32) + 1u32;
+        let puzzle = self.generate_with_randomness(&secret, &r);
+        (secret, puzzle)
+    }
+
+    /// A well-formed puzzle embedding a secret unknown to the caller, derived from `seed`.
+    ///
+    /// Meant for tests and benchmarks of code receiving puzzles from another party: the secret
+    /// is discarded, so the result can only be learned by solving the puzzle.
+    #[must_use]
+    pub fn random_puzzle_oracle(&self, seed: &[u8]) -> Puzzle {
+        self.generate_from_seed(seed).1
+    }
+
+    /// One oracle puzzle per seed, see `random_puzzle_oracle`.
+    #[must_use]
+    pub fn random_puzzle_oracle_batch(&self, seeds: &[[u8; 32]]) -> Vec<Puzzle> {
+        seeds.iter().map(|seed| self.random_puzzle_oracle(seed)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn oracle_puzzles() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let seeds = [[1u8; 32], [2u8; 32]];
+        let batch = lhtlp.random_puzzle_oracle_batch(&seeds);
+
+        assert!(batch[0] == lhtlp.random_puzzle_oracle(&seeds[0]));
+        assert!(batch[0] != batch[1]);
+
+        let (secret, puzzle) = lhtlp.generate_from_seed(&seeds[1]);
+        assert!(puzzle == batch[1]);
+        assert!(lhtlp.solve(puzzle) == secret);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+/// The generator of the `i`-th item of a batch seeded with `rng_seed`: stream `i` of the ChaCha
+/// keyed by `rng_seed`, independent of every other stream and of every other seed.
+fn item_rng(rng_seed: [u8; 32], i: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::from_seed(rng_seed);
+    rng.set_stream(i);
+    rng
+}
+
+/// Run `op` in `pool`, or in the global rayon pool if `None`.
+fn install<R: Send>(pool: Option<&ThreadPool>, op: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+impl LHTLP {
+    /// Evaluate puzzles like `evaluate`, reducing in parallel.
+    ///
+    /// Work runs in `pool` when given, which bounds the threads used, and in the global rayon
+    /// pool otherwise.
+    #[must_use]
+    pub fn evaluate_par(&self, puzzles: Vec<Puzzle>, pool: Option<&ThreadPool>) -> Puzzle {
+        if puzzles.is_empty() {
+            return self.evaluate(puzzles);
+        }
+        install(pool, || puzzles.into_par_iter().reduce_with(|a, b| self.add(&a, &b)).unwrap())
+    }
+
+    /// Solve puzzles in parallel, one per thread, returning the solutions in order.
+    ///
+    /// Work runs in `pool` when given and in the global rayon pool otherwise.
+    #[must_use]
+    pub fn solve_many_par(&self, puzzles: Vec<Puzzle>, pool: Option<&ThreadPool>) -> Vec<BigUint> {
+        install(pool, || puzzles.into_par_iter().map(|puzzle| self.solve(puzzle)).collect())
+    }
+
+    /// Generate puzzles of `secrets` in parallel, returning them in order.
+    ///
+    /// The puzzle of `secrets[i]` draws its randomness from stream `i` of a `ChaCha8Rng` seeded
+    /// with `rng_seed`, so threads share no generator and the output does not depend on the
+    /// scheduling: it is identical to generating each puzzle serially from the same streams. The
+    /// seed keys all the randomness of the batch, so whoever recovers it opens every puzzle
+    /// without squaring: it must be drawn uniformly from a cryptographic generator outside of
+    /// tests, kept secret, and never reused, as two calls with the same seed give their puzzles at
+    /// equal indices the same randomness. Fails with
+    /// `LhtlpError::InvalidSecret` before generating anything if a secret does not fit the
+    /// plaintext space.
+    pub fn generate_many_par(&self, secrets: &[BigUint], rng_seed: [u8; 32], pool: Option<&ThreadPool>) -> Result<Vec<Puzzle>, LhtlpError> {
+        let space = self.plaintext_space();
+        if !secrets.iter().all(|secret| space.fits(secret)) {
+            return Err(LhtlpError::InvalidSecret);
+        }
+        Ok(install(pool, || {
+            secrets
+                .par_iter()
+                .enumerate()
+                .map(|(i, secret)| {
+                    let r = self.sample_randomness(&mut item_rng(rng_seed, i as u64));
+                    self.generate_with_randomness(secret, &r)
+                })
+                .collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn parallel_evaluation() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzles: Vec<Puzzle> = (0..100).map(|s| lhtlp.generate(s)).collect();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+
+        assert!(lhtlp.evaluate_par(puzzles.clone(), Some(&pool)) == lhtlp.evaluate(puzzles.clone()));
+        assert!(lhtlp.solve(lhtlp.evaluate_par(puzzles, None)) == BigUint::from(4950u32));
+    }
+
+    #[test]
+    fn parallel_solving_in_pool() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzles: Vec<Puzzle> = (0..8).map(|s| lhtlp.generate(s)).collect();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+        let expected: Vec<BigUint> = (0..8u32).map(BigUint::from).collect();
+        assert!(lhtlp.solve_many_par(puzzles.clone(), Some(&pool)) == expected);
+        assert!(lhtlp.solve_many_par(puzzles, None) == expected);
+    }
+
+    #[test]
+    fn parallel_generation_matches_serial() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let secrets: Vec<BigUint> = (0..50u32).map(BigUint::from).collect();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+
+        let parallel = lhtlp.generate_many_par(&secrets, [7; 32], Some(&pool)).unwrap();
+        let serial: Vec<Puzzle> = (0..50u64)
+            .map(|i| lhtlp.generate_with_rng(i, &mut item_rng([7; 32], i)))
+            .collect();
+        assert!(parallel == serial);
+        let nearby = lhtlp.generate_many_par(&secrets, [6; 32], None).unwrap();
+        assert!(nearby.iter().all(|puzzle| parallel.iter().all(|other| puzzle.u != other.u)));
+        assert!(lhtlp.generate_many_par(&secrets, [7; 32], None).unwrap() == serial);
+        assert!(lhtlp.solve_many_par(parallel, None) == secrets);
+
+        let too_large = vec![BigUint::from(1u32), lhtlp.n.clone()];
+        assert!(lhtlp.generate_many_par(&too_large, [7; 32], None) == Err(LhtlpError::InvalidSecret));
+    }
+
+    /// Run with `cargo test --release --features rayon -- --ignored generation_speedup --nocapture`.
+    #[test]
+    #[ignore]
+    fn generation_speedup() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let secrets: Vec<BigUint> = (0..10_000u32).map(BigUint::from).collect();
+
+        let start = std::time::Instant::now();
+        let serial: Vec<Puzzle> = (0..10_000u64)
+            .map(|i| lhtlp.generate_with_rng(i, &mut item_rng([0; 32], i)))
+            .collect();
+        let serial_time = start.elapsed();
+        let start = std::time::Instant::now();
+        let parallel = lhtlp.generate_many_par(&secrets, [0; 32], None).unwrap();
+        let parallel_time = start.elapsed();
+
+        assert!(parallel == serial);
+        println!(
+            "{} threads: serial {:?}, parallel {:?}, speedup {:.2}",
+            rayon::current_num_threads(),
+            serial_time,
+            parallel_time,
+            serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{Difficulty, LhtlpError, SolvePolicy, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::Pow;
+
+/// How a foreign implementation derived its generator `g` from a random base `x`.
+///
+/// This crate uses `GeneratorConvention::InverseSquare`: `g = (x^2)^-1 mod n`, with
+/// `h = g^(2^T) mod n`. Both conventions generate the same subgroup of squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GeneratorConvention {
+    /// `g = (x^2)^-1 mod n`, the convention of this crate.
+    #[default]
+    InverseSquare,
+    /// `g = x^2 mod n`, used by some other LHTLP implementations.
+    Square,
+}
+
+/// The public parameters `(n, g, h)` of an instance, enough to generate, evaluate and solve puzzles.
+///
+/// `h` is tied to the difficulty the instance was set up with, which is carried alongside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PublicParams {
+    pub n: BigUint,
+    pub g: BigUint,
+    pub h: BigUint,
+}
+
+impl LHTLP {
+    /// The public parameters of the instance.
+    pub fn public_params(&self) -> PublicParams {
+        PublicParams { n: self.n.clone(), g: self.g.clone(), h: self.h.clone() }
+    }
+
+    /// Check that the modulus `n` is not a perfect power `p^k` with `k > 1`, whose root would
+    /// reveal the factorization.
+    ///
+    /// It suffices to try prime exponents up to `log2(n)`. Each root is computed exactly with
+    /// integer Newton iterations, without floating point. Fails with `LhtlpError::PerfectPower`.
+    pub fn verify_public_params(n: &BigUint) -> Result<(), LhtlpError> {
+        let max_exponent = n.bits().saturating_sub(1) as u32;
+        for k in (2..=max_exponent).filter(|&k| (2..k).take_while(|d| d * d <= k).all(|d| k % d != 0)) {
+            if n.nth_root(k).pow(k) == *n {
+                return Err(LhtlpError::PerfectPower);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check by sequential squaring that `h = g^(2^T) mod n`, without the `phi(n)` shortcut of setup.
+    ///
+    /// This performs the full `T` squarings, as much work as solving a puzzle, so it is meant for
+    /// key ceremonies rather than routine use. Fails with `LhtlpError::ParameterMismatch` if `h`
+    /// does not match.
+    pub fn verify_setup_consistency(&self) -> Result<(), LhtlpError> {
+        if self.square(&self.g, &self.difficulty) != self.h {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+        Ok(())
+    }
+
+    /// Rebuild an instance from its public parameters and difficulty.
+    ///
+    /// Fails with `LhtlpError::InconsistentParameters` if `n` is even or `g` and `h` are not in `(1, n)`,
+    /// and with `LhtlpError::PerfectPower` if `n` is a perfect power.
+    pub fn from_public_params(params: PublicParams, difficulty: impl Into<Difficulty>) -> Result<LHTLP, LhtlpError> {
+        let one = BigUint::from(1u32);
+        let PublicParams { n, g, h } = params;
+        if n.is_even() || g <= one || g >= n || h <= one || h >= n {
+            return Err(LhtlpError::InconsistentParameters);
+        }
+        LHTLP::verify_public_params(&n)?;
+        Ok(LHTLP { difficulty: difficulty.into().into(), n2: &n * &n, n, g, h, policy: SolvePolicy::default(), hash_suite: Default::default() })
+    }
+
+    /// Rebuild an instance from public parameters produced under another generator `convention`.
+    ///
+    /// Parameters under `GeneratorConvention::Square` are adapted by inverting both `g` and `h`
+    /// modulo `n`; since `(g^-1)^(2^T) = (g^(2^T))^-1`, the pair stays consistent. The result
+    /// is then checked like `from_public_params`.
+    pub fn from_public_params_with_convention(
+        params: PublicParams,
+        difficulty: impl Into<Difficulty>,
+        convention: GeneratorConvention,
+    ) -> Result<LHTLP, LhtlpError> {
+        let params = match convention {
+            GeneratorConvention::InverseSquare => params,
+            GeneratorConvention::Square => {
+                let PublicParams { n, g, h } = params;
+                let g = g.modinv(&n).ok_or(LhtlpError::InconsistentParameters)?;
+                let h = h.modinv(&n).ok_or(LhtlpError::InconsistentParameters)?;
+                PublicParams { n, g, h }
+            }
+        };
+        LHTLP::from_public_params(params, difficulty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_powers() {
+        let p = BigUint::from(1_000_003u32);
+        let q = BigUint::from(999_983u32);
+        assert!(LHTLP::verify_public_params(&(&p * &q)).is_ok());
+        for k in [2u32, 3, 4, 5, 7] {
+            assert!(LHTLP::verify_public_params(&p.clone().pow(k)) == Err(LhtlpError::PerfectPower));
+        }
+
+        let n = p.clone().pow(3u32);
+        let params = PublicParams { g: BigUint::from(4u32), h: BigUint::from(16u32), n };
+        assert!(LHTLP::from_public_params(params, 10u64) == Err(LhtlpError::PerfectPower));
+    }
+
+    #[test]
+    fn setup_modulus_is_not_a_perfect_power() {
+        let lhtlp = LHTLP::setup(64, 10u64);
+        assert!(LHTLP::verify_public_params(&lhtlp.n).is_ok());
+    }
+
+    #[test]
+    fn setup_consistency() {
+        let lhtlp = LHTLP::setup(64, 1000u64);
+        assert!(lhtlp.verify_setup_consistency().is_ok());
+
+        let mut params = lhtlp.public_params();
+        params.h = &params.h * &params.g % &params.n;
+        let tampered = LHTLP::from_public_params(params, 1000u64).unwrap();
+        assert!(tampered.verify_setup_consistency() == Err(LhtlpError::ParameterMismatch));
+    }
+
+    #[test]
+    fn generator_conventions() {
+        let lhtlp = LHTLP::setup(64, 1000u64);
+        let params = lhtlp.public_params();
+        let native = LHTLP::from_public_params_with_convention(params.clone(), 1000u64, GeneratorConvention::InverseSquare);
+        assert!(native == Ok(lhtlp.clone()));
+
+        // The same parameters as another implementation squaring x instead of inverting x^2.
+        let foreign = PublicParams { g: params.g.modinv(&params.n).unwrap(), h: params.h.modinv(&params.n).unwrap(), n: params.n };
+        let imported = LHTLP::from_public_params_with_convention(foreign.clone(), 1000u64, GeneratorConvention::Square).unwrap();
+        assert!(imported == lhtlp);
+        assert!(imported.solve(lhtlp.generate(42)) == BigUint::from(42u32));
+        assert!(imported.verify_setup_consistency().is_ok());
+
+        let misread = LHTLP::from_public_params(foreign, 1000u64).unwrap();
+        assert!(misread != lhtlp);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::{BigInt, BigUint};
+
+/// The space of secrets of an instance: integers modulo `n`.
+///
+/// Gathers the arithmetic about what fits in a puzzle, so that bounds checks, signed decoding and
+/// packing all agree on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaintextSpace {
+    modulus: BigUint,
+}
+
+impl PlaintextSpace {
+    pub fn new(modulus: BigUint) -> PlaintextSpace {
+        PlaintextSpace { modulus }
+    }
+
+    /// The plaintext modulus `n`.
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    /// Number of bits of `n`.
+    pub fn bits(&self) -> u64 {
+        self.modulus.bits()
+    }
+
+    /// How many slots of `slot_bits` bits can be packed side by side in a secret.
+    ///
+    /// Only the `bits() - 1` low bits are used, as every value below `2^(bits() - 1)` fits.
+    pub fn max_slots(&self, slot_bits: u64) -> u64 {
+        assert!(slot_bits > 0, "slot width must be positive");
+        self.bits().saturating_sub(1) / slot_bits
+    }
+
+    /// Largest magnitude of a signed secret: values in `[-bound, bound]` decode unambiguously.
+    pub fn signed_bound(&self) -> BigUint {
+        (&self.modulus - 1u32) / 2u32
+    }
+
+    /// Whether `value` is a secret of this space, that is below `n`.
+    pub fn fits(&self, value: &BigUint) -> bool {
+        value < &self.modulus
+    }
+}
+
+impl LHTLP {
+    /// The space of secrets of the instance.
+    pub fn plaintext_space(&self) -> PlaintextSpace {
+        PlaintextSpace::new(self.n.clone())
+    }
+
+    /// Generate a puzzle embedding an arbitrary-size `secret`.
+    ///
+    /// Fails with `LhtlpError::InvalidSecret` if `secret` does not fit the plaintext space.
+    pub fn generate_biguint(&self, secret: &BigUint) -> Result<Puzzle, LhtlpError> {
+        if !self.plaintext_space().fits(secret) {
+            return Err(LhtlpError::InvalidSecret);
+        }
+        let r = self.sample_randomness(&mut rand::thread_rng());
+        Ok(self.generate_with_randomness(secret, &r))
+    }
+
+    /// Generate a puzzle embedding a byte-string secret, such as a session key, read as a
+    /// big-endian unsigned integer.
+    ///
+    /// Leading zero bytes are not preserved; `solve_as_bytes` pads them back. Fails with
+    /// `LhtlpError::InvalidSecret` if the integer does not fit the plaintext space.
+    pub fn generate_bytes(&self, secret_bytes: &[u8]) -> Result<Puzzle, LhtlpError> {
+        self.generate_biguint(&BigUint::from_bytes_be(secret_bytes))
+    }
+
+    /// Open a puzzle produced by `generate_bytes`, zero-padding the solution to `expected_len`
+    /// big-endian bytes.
+    ///
+    /// The puzzle is solved like in `solve_checked`, whose errors are passed through. Fails with
+    /// `LhtlpError::PrecisionLoss` if the solution takes more than `expected_len` bytes.
+    pub fn solve_as_bytes(&self, puzzle: Puzzle, expected_len: usize) -> Result<Vec<u8>, LhtlpError> {
+        let solution = self.solve_checked(puzzle)?;
+        let magnitude = if solution == BigUint::from(0u32) { Vec::new() } else { solution.to_bytes_be() };
+        if magnitude.len() > expected_len {
+            return Err(LhtlpError::PrecisionLoss);
+        }
+        let mut bytes = vec![0u8; expected_len - magnitude.len()];
+        bytes.extend_from_slice(&magnitude);
+        Ok(bytes)
+    }
+
+    /// Open a puzzle whose secret is a signed value, with `-x` encoded as `n - x`.
+    ///
+    /// Solutions above the signed bound of the plaintext space decode as negative.
+    pub fn solve_signed(&self, puzzle: Puzzle) -> BigInt {
+        let solution = self.solve(puzzle);
+        if solution > self.plaintext_space().signed_bound() {
+            BigInt::from(solution) - BigInt::from(self.n.clone())
+        } else {
+            BigInt::from(solution)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiny_modulus_values() {
+        let lhtlp = LHTLP::from_primes(&BigUint::from(5u32), &BigUint::from(7u32), BigUint::from(10u32));
+        let space = lhtlp.plaintext_space();
+
+        assert!(space.modulus() == &BigUint::from(35u32));
+        assert!(space.bits() == 6);
+        assert!(space.max_slots(1) == 5);
+        assert!(space.max_slots(2) == 2);
+        assert!(space.max_slots(8) == 0);
+        assert!(space.signed_bound() == BigUint::from(17u32));
+        assert!(space.fits(&BigUint::from(34u32)));
+        assert!(!space.fits(&BigUint::from(35u32)));
+    }
+
+    #[test]
+    fn dependent_apis_use_the_space() {
+        let lhtlp = LHTLP::from_primes(&BigUint::from(5u32), &BigUint::from(7u32), BigUint::from(10u32));
+
+        let puzzle = lhtlp.generate_biguint(&BigUint::from(34u32)).unwrap();
+        assert!(lhtlp.solve(puzzle) == BigUint::from(34u32));
+        assert!(lhtlp.generate_biguint(&BigUint::from(35u32)) == Err(LhtlpError::InvalidSecret));
+
+        assert!(crate::BoundedPuzzle::new(&lhtlp, lhtlp.generate(1), BigUint::from(34u32)).is_ok());
+        assert!(crate::BoundedPuzzle::new(&lhtlp, lhtlp.generate(1), BigUint::from(35u32)).is_err());
+
+        let minus_one = lhtlp.generate_biguint(&BigUint::from(34u32)).unwrap();
+        assert!(lhtlp.solve_signed(minus_one) == BigInt::from(-1));
+        assert!(lhtlp.solve_signed(lhtlp.generate(17)) == BigInt::from(17));
+        assert!(lhtlp.solve_signed(lhtlp.generate(18)) == BigInt::from(-17));
+    }
+
+    #[test]
+    fn byte_secrets() {
+        let lhtlp = LHTLP::setup_fast_insecure(384, 1000u64);
+        let mut key = [0u8; 32];
+        rand::Rng::fill(&mut rand::thread_rng(), &mut key);
+        key[0] = 0;
+        let puzzle = lhtlp.generate_bytes(&key).unwrap();
+        assert!(lhtlp.solve_as_bytes(puzzle.clone(), 32) == Ok(key.to_vec()));
+        assert!(lhtlp.solve_as_bytes(puzzle, 16) == Err(LhtlpError::PrecisionLoss));
+
+        assert!(lhtlp.solve_as_bytes(lhtlp.generate_bytes(&[]).unwrap(), 4) == Ok(vec![0; 4]));
+        assert!(lhtlp.generate_bytes(&[0xff; 64]) == Err(LhtlpError::InvalidSecret));
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.solve_as_bytes(malformed, 4) == Err(LhtlpError::InvalidPuzzle));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{Difficulty, LhtlpError, PartialSolution, Puzzle, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+use std::time::{Duration, Instant};
+
+/// Number of squarings between two checks of the wall clock.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Default practical ceiling on the difficulty, `2^36` squarings.
+///
+/// That is about an hour of squaring with a 128-bit modulus and days with a 2048-bit one. A
+/// larger difficulty is far more likely a mistyped value than an intended timelock.
+pub const DEFAULT_DIFFICULTY_CEILING: u64 = 1 << 36;
+
+/// Limits on the work spent solving puzzles, to protect solvers from untrusted parameters.
+///
+/// Difficulty and modulus size are checked before any squaring is performed. The wall-clock
+/// limit aborts a running solve and hands back a resumable `PartialSolution`. A limit set to
+/// `None` is not enforced. By default only the difficulty is limited, to
+/// `DEFAULT_DIFFICULTY_CEILING`; `SolvePolicy::unlimited` lifts it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolvePolicy {
+    pub max_difficulty: Option<BigUint>,
+    pub max_modulus_bits: Option<u64>,
+    pub max_wall_clock: Option<Duration>,
+}
+
+impl Default for SolvePolicy {
+    fn default() -> SolvePolicy {
+        SolvePolicy { max_difficulty: Some(BigUint::from(DEFAULT_DIFFICULTY_CEILING)), ..SolvePolicy::unlimited() }
+    }
+}
+
+impl SolvePolicy {
+    /// A policy enforcing no limit at all, for users who really mean a huge difficulty.
+    pub fn unlimited() -> SolvePolicy {
+        SolvePolicy { max_difficulty: None, max_modulus_bits: None, max_wall_clock: None }
+    }
+
+    /// Limit the difficulty of the puzzles to solve.
+    pub fn with_max_difficulty(mut self, difficulty: impl Into<Difficulty>) -> SolvePolicy {
+        self.max_difficulty = Some(difficulty.into().into());
+        self
+    }
+
+    /// Check the parameters of `lhtlp` against the difficulty and modulus limits.
+    pub fn check(&self, lhtlp: &LHTLP) -> Result<(), LhtlpError> {
+        if let Some(limit) = &self.max_difficulty {
+            if &lhtlp.difficulty > limit {
+                return Err(LhtlpError::DifficultyTooLarge { difficulty: lhtlp.difficulty.clone(), limit: limit.clone() });
+            }
+        }
+        if let Some(limit) = self.max_modulus_bits {
+            if lhtlp.n.bits() > limit {
+                return Err(LhtlpError::ModulusTooLarge { bits: lhtlp.n.bits(), limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LHTLP {
+    /// Setup an instance like `setup`, failing with `LhtlpError::DifficultyTooLarge` if
+    /// `difficulty` exceeds `DEFAULT_DIFFICULTY_CEILING`.
+    ///
+    /// The check happens before the primes are generated. `setup` itself does not check.
+    pub fn try_setup(lambda: u64, difficulty: impl Into<Difficulty>) -> Result<LHTLP, LhtlpError> {
+        LHTLP::try_setup_with_ceiling(lambda, difficulty, DEFAULT_DIFFICULTY_CEILING)
+    }
+
+    /// Setup an instance like `try_setup` with a custom difficulty `ceiling`.
+    pub fn try_setup_with_ceiling(lambda: u64, difficulty: impl Into<Difficulty>, ceiling: impl Into<Difficulty>) -> Result<LHTLP, LhtlpError> {
+        let difficulty: BigUint = difficulty.into().into();
+        let limit: BigUint = ceiling.into().into();
+        if difficulty > limit {
+            return Err(LhtlpError::DifficultyTooLarge { difficulty, limit });
+        }
+        Ok(LHTLP::setup(lambda, difficulty))
+    }
+
+    /// Attach a solve `policy` to the instance, enforced by `solve_checked`.
+    pub fn with_policy(mut self, policy: SolvePolicy) -> LHTLP {
+        self.policy = policy;
+        self
+    }
+
+    /// The solve policy attached to the instance.
+    pub fn policy(&self) -> &SolvePolicy {
+        &self.policy
+    }
+
+    /// Open a puzzle under the policy attached to the instance.
+    ///
+    /// Malformed puzzles are rejected with `LhtlpError::InvalidPuzzle` before solving.
+    pub fn solve_checked(&self, puzzle: Puzzle) -> Result<BigUint, LhtlpError> {
+        self.verify_puzzle(&puzzle)?;
+        self.solve_with_policy(PartialSolution::new(puzzle), &self.policy)
+    }
+
+    /// Open a puzzle, giving up with `None` once `max` of wall-clock time has elapsed.
+    ///
+    /// The clock is checked every 1024 squarings, so the overrun is at most that many squarings.
+    /// The other limits of the instance policy still apply, and an instance exceeding them or a
+    /// puzzle that cannot be decoded also gives `None`. Use `solve_with_policy` to keep the
+    /// partial solution of an aborted solve.
+    pub fn solve_timeout(&self, puzzle: Puzzle, max: Duration) -> Option<BigUint> {
+        let policy = SolvePolicy { max_wall_clock: Some(max), ..self.policy.clone() };
+        self.solve_with_policy(PartialSolution::new(puzzle), &policy).ok()
+    }
+
+    /// Open a puzzle, or resume a previous solve, under `policy`.
+    ///
+    /// Fails before any squaring if the instance exceeds the difficulty or modulus limits. If the
+    /// wall-clock limit is hit, fails with `LhtlpError::DeadlineExceeded` holding the state
+    /// reached so far, which can be passed back to resume the solve.
+    pub fn solve_with_policy(&self, partial: PartialSolution, policy: &SolvePolicy) -> Result<BigUint, LhtlpError> {
+        self.solve_with_policy_counted(partial, policy, &mut 0)
+    }
+
+    /// Solve like `solve_with_policy`, adding the number of squarings performed to `squarings`.
+    pub(crate) fn solve_with_policy_counted(&self, partial: PartialSolution, policy: &SolvePolicy, squarings: &mut u64) -> Result<BigUint, LhtlpError> {
+        let state = self.advance_with_policy(partial, policy, squarings)?;
+        self.try_decode(&state.puzzle.v, &state.w)
+    }
+
+    /// Square `partial` up to the difficulty under `policy` like `solve_with_policy_counted`,
+    /// returning the final state instead of decoding it.
+    pub(crate) fn advance_with_policy(&self, partial: PartialSolution, policy: &SolvePolicy, squarings: &mut u64) -> Result<PartialSolution, LhtlpError> {
+        policy.check(self)?;
+        let deadline = policy.max_wall_clock.map(|max| Instant::now() + max);
+        let total = self.try_squarings()?;
+        let mut state = partial;
+        while state.squarings < total {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(LhtlpError::DeadlineExceeded(Box::new(state)));
+            }
+            let steps = DEADLINE_CHECK_INTERVAL.min(total - state.squarings);
+            state.w = LHTLP::square_in_chunks(&state.w, &BigUint::from(steps), &self.n, SQUARING_CHUNK, squarings);
+            state.squarings += steps;
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn compliant_puzzle() {
+        let policy = SolvePolicy {
+            max_difficulty: Some(BigUint::from(DIFFICULTY)),
+            max_modulus_bits: Some(2 * LAMBDA),
+            max_wall_clock: Some(Duration::from_secs(60)),
+        };
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY)).with_policy(policy);
+        assert!(lhtlp.solve_checked(lhtlp.generate(42)) == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn difficulty_over_limit() {
+        let policy = SolvePolicy::default().with_max_difficulty(1000u64);
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(1u64 << 60)).with_policy(policy);
+        let start = Instant::now();
+
+        assert!(matches!(lhtlp.solve_checked(lhtlp.generate(42)), Err(LhtlpError::DifficultyTooLarge { .. })));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn difficulty_over_u64() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY)).with_policy(SolvePolicy::unlimited());
+        let lhtlp = LHTLP { difficulty: BigUint::from(u64::MAX) + 1u32, ..lhtlp };
+        assert!(matches!(lhtlp.solve_checked(lhtlp.generate(42)), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+
+    #[test]
+    fn modulus_over_limit() {
+        let policy = SolvePolicy { max_modulus_bits: Some(LAMBDA), ..SolvePolicy::default() };
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY)).with_policy(policy);
+        assert!(matches!(lhtlp.solve_checked(lhtlp.generate(42)), Err(LhtlpError::ModulusTooLarge { .. })));
+    }
+
+    #[test]
+    fn deadline_is_resumable() {
+        let difficulty = 1u64 << 40;
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(difficulty));
+        let policy = SolvePolicy { max_wall_clock: Some(Duration::from_millis(50)), ..SolvePolicy::unlimited() };
+        let puzzle = lhtlp.generate(42);
+
+        let partial = match lhtlp.solve_with_policy(PartialSolution::new(puzzle.clone()), &policy) {
+            Err(LhtlpError::DeadlineExceeded(partial)) => *partial,
+            _ => panic!("expected the deadline to be exceeded"),
+        };
+        assert!(partial.squarings > 0 && partial.squarings < difficulty);
+        assert!(partial.puzzle == puzzle);
+        assert!(partial.w == lhtlp.square(&puzzle.u, &BigUint::from(partial.squarings)));
+    }
+
+    #[test]
+    fn solve_timeout() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(lhtlp.solve_timeout(lhtlp.generate(42), Duration::from_secs(60)) == Some(BigUint::from(42u32)));
+
+        let slow = LHTLP::setup(LAMBDA, BigUint::from(1u64 << 40)).with_policy(SolvePolicy::unlimited());
+        let start = Instant::now();
+        assert!(slow.solve_timeout(slow.generate(42), Duration::from_millis(50)).is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let limited = lhtlp.clone().with_policy(SolvePolicy::default().with_max_difficulty(DIFFICULTY - 1));
+        assert!(limited.solve_timeout(limited.generate(42), Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn difficulty_ceiling() {
+        let huge = BigUint::from(1u64 << 40);
+        assert!(matches!(LHTLP::try_setup(LAMBDA, huge.clone()), Err(LhtlpError::DifficultyTooLarge { .. })));
+        assert!(LHTLP::try_setup(LAMBDA, DIFFICULTY).is_ok());
+        assert!(LHTLP::try_setup_with_ceiling(LAMBDA, huge.clone(), huge.clone()).is_ok());
+        assert!(matches!(LHTLP::try_setup_with_ceiling(LAMBDA, DIFFICULTY, DIFFICULTY - 1), Err(LhtlpError::DifficultyTooLarge { .. })));
+
+        let lhtlp = LHTLP::setup(LAMBDA, huge);
+        assert!(matches!(lhtlp.solve_checked(lhtlp.generate(42)), Err(LhtlpError::DifficultyTooLarge { .. })));
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY).with_policy(SolvePolicy::unlimited());
+        assert!(lhtlp.solve_checked(lhtlp.generate(42)) == Ok(BigUint::from(42u32)));
+    }
+}
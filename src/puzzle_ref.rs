@@ -0,0 +1,172 @@
+use crate::{read_magnitude, LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+
+/// A puzzle borrowed from its canonical byte encoding, as written by `Puzzle::to_bytes`.
+///
+/// The components stay big-endian byte slices into the encoding until `to_owned` is called, so
+/// that validating a puzzle does not allocate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PuzzleRef<'a> {
+    encoding: &'a [u8],
+    u: &'a [u8],
+    v: &'a [u8],
+}
+
+impl<'a> PuzzleRef<'a> {
+    /// Split a canonical encoding into its components, without checking them against an instance.
+    ///
+    /// Fails with `LhtlpError::InvalidEncoding` on truncated or trailing data, and on magnitudes
+    /// with leading zero bytes, which `Puzzle::to_bytes` never writes.
+    pub fn parse(bytes: &'a [u8]) -> Result<PuzzleRef<'a>, LhtlpError> {
+        let mut rest = bytes;
+        let mut next = || {
+            let magnitude = read_magnitude(&mut rest)?;
+            if magnitude.len() > 1 && magnitude[0] == 0 {
+                return Err(LhtlpError::InvalidEncoding);
+            }
+            Ok(magnitude)
+        };
+        let u = next()?;
+        let v = next()?;
+        if !rest.is_empty() {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        Ok(PuzzleRef { encoding: bytes, u, v })
+    }
+
+    /// Big-endian magnitude of `u`.
+    pub fn u_bytes(&self) -> &'a [u8] {
+        self.u
+    }
+
+    /// Big-endian magnitude of `v`.
+    pub fn v_bytes(&self) -> &'a [u8] {
+        self.v
+    }
+
+    /// SHA-256 digest of the encoding, equal to `Puzzle::fingerprint` of the owned puzzle.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        Sha256::digest(self.encoding).into()
+    }
+
+    /// Materialize the owned puzzle.
+    pub fn to_owned(self) -> Puzzle {
+        Puzzle { u: BigUint::from_bytes_be(self.u), v: BigUint::from_bytes_be(self.v) }
+    }
+}
+
+/// Compare a canonical big-endian magnitude with `value` through its limbs, without allocating.
+fn cmp_magnitude(bytes: &[u8], value: &BigUint) -> Ordering {
+    let value_len = value.bits().div_ceil(8) as usize;
+    let bytes = if bytes == [0] { &bytes[1..] } else { bytes };
+    if bytes.len() != value_len {
+        return bytes.len().cmp(&value_len);
+    }
+    // Walk both from the least significant limb, keeping the outcome of the most significant difference.
+    let mut ordering = Ordering::Equal;
+    for (chunk, digit) in bytes.rchunks(8).zip(value.iter_u64_digits()) {
+        let limb = chunk.iter().fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte));
+        if limb != digit {
+            ordering = limb.cmp(&digit);
+        }
+    }
+    ordering
+}
+
+impl LHTLP {
+    /// Parse and check a puzzle encoding without materializing its components.
+    ///
+    /// Requires `0 < u < n` and `0 < v < n^2` as `verify_puzzle` does, comparing the magnitudes
+    /// with the limbs of the moduli. Coprimality with `n` needs a gcd and is left to
+    /// `verify_puzzle`: a component sharing a factor with `n` can only be built by someone who
+    /// already knows the factorization.
+    pub fn verify_puzzle_bytes<'a>(&self, bytes: &'a [u8]) -> Result<PuzzleRef<'a>, LhtlpError> {
+        let puzzle = PuzzleRef::parse(bytes)?;
+        let is_zero = |magnitude: &[u8]| magnitude.iter().all(|&byte| byte == 0);
+        if is_zero(puzzle.u)
+            || is_zero(puzzle.v)
+            || cmp_magnitude(puzzle.u, &self.n) != Ordering::Less
+            || cmp_magnitude(puzzle.v, &self.n2) != Ordering::Less
+        {
+            return Err(LhtlpError::InvalidPuzzle);
+        }
+        Ok(puzzle)
+    }
+
+    /// Evaluate borrowed puzzles like `evaluate`.
+    #[must_use]
+    pub fn evaluate_refs<'a>(&self, puzzles: impl IntoIterator<Item = PuzzleRef<'a>>) -> Puzzle {
+        self.evaluate(puzzles.into_iter().map(PuzzleRef::to_owned).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn magnitude_comparison() {
+        let values = [0u128, 1, 255, 256, u64::MAX as u128, 1 << 64, u128::MAX];
+        for a in values {
+            for b in values {
+                let bytes = BigUint::from(a).to_bytes_be();
+                assert!(cmp_magnitude(&bytes, &BigUint::from(b)) == a.cmp(&b), "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn parity_with_owned_path() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate(42);
+        let n2 = &lhtlp.n2;
+        let candidates = [
+            puzzle.clone(),
+            Puzzle { u: BigUint::from(0u32), v: puzzle.v.clone() },
+            Puzzle { u: puzzle.u.clone(), v: BigUint::from(0u32) },
+            Puzzle { u: lhtlp.n.clone(), v: puzzle.v.clone() },
+            Puzzle { u: &lhtlp.n - 1u32, v: puzzle.v.clone() },
+            Puzzle { u: puzzle.u.clone(), v: n2.clone() },
+            Puzzle { u: puzzle.u.clone(), v: n2 - 1u32 },
+            Puzzle { u: puzzle.u.clone(), v: n2 + 1u32 },
+        ];
+        for candidate in candidates {
+            let bytes = candidate.to_bytes();
+            let borrowed = lhtlp.verify_puzzle_bytes(&bytes);
+            assert!(borrowed.is_ok() == lhtlp.verify_puzzle(&candidate).is_ok());
+            if let Ok(borrowed) = borrowed {
+                assert!(borrowed.fingerprint() == candidate.fingerprint());
+                assert!(borrowed.to_owned() == candidate);
+            }
+        }
+    }
+
+    #[test]
+    fn non_canonical_encoding() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate(42);
+        let u = puzzle.u.to_bytes_be();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(u.len() as u32 + 1).to_be_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&u);
+        bytes.extend_from_slice(&puzzle.to_bytes()[4 + u.len()..]);
+
+        assert!(Puzzle::from_bytes(&bytes) == Ok(puzzle.clone()));
+        assert!(lhtlp.verify_puzzle_bytes(&bytes) == Err(LhtlpError::InvalidEncoding));
+        assert!(lhtlp.verify_puzzle_bytes(&puzzle.to_bytes()[1..]) == Err(LhtlpError::InvalidEncoding));
+    }
+
+    #[test]
+    fn evaluate_refs() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let encodings: Vec<Vec<u8>> = (1..=4).map(|s| lhtlp.generate(s).to_bytes()).collect();
+        let refs = encodings.iter().map(|bytes| lhtlp.verify_puzzle_bytes(bytes).unwrap());
+        assert!(lhtlp.solve(lhtlp.evaluate_refs(refs)) == BigUint::from(10u32));
+    }
+}
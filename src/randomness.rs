@@ -0,0 +1,133 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// The randomness `r` a puzzle was generated with, which opens the puzzle without solving it.
+///
+/// The value is held as big-endian bytes that are wiped when dropped. `BigUint` values computed
+/// from it, including the one returned by `to_biguint`, are not wiped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Randomness {
+    bytes: Vec<u8>,
+}
+
+impl Randomness {
+    pub(crate) fn new(r: &BigUint) -> Randomness {
+        Randomness { bytes: r.to_bytes_be() }
+    }
+
+    pub fn to_biguint(&self) -> BigUint {
+        BigUint::from_bytes_be(&self.bytes)
+    }
+}
+
+impl Zeroize for Randomness {
+    fn zeroize(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl Drop for Randomness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Randomness {}
+
+impl fmt::Debug for Randomness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Randomness(..)")
+    }
+}
+
+/// A puzzle together with the randomness it was generated with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateOutput {
+    pub puzzle: Puzzle,
+    pub randomness: Randomness,
+}
+
+impl LHTLP {
+    /// Generate a puzzle like `generate`, also returning its randomness.
+    ///
+    /// The randomness opens the puzzle with `open_with_randomness`, so it must be kept as secret as
+    /// the puzzle content. `generate` discards it.
+    #[must_use]
+    pub fn generate_revealing_randomness(&self, secret: u64) -> GenerateOutput {
+        let r = self.sample_randomness(&mut rand::thread_rng());
+        let puzzle = self.generate_with_randomness(&BigUint::from(secret), &r);
+        GenerateOutput { puzzle, randomness: Randomness::new(&r) }
+    }
+
+    /// Generate one puzzle per secret, each with its randomness, for provers needing the witnesses.
+    ///
+    /// All randomness is sampled from `rng` before any puzzle is computed. Fails with
+    /// `LhtlpError::InvalidSecret` if a secret does not fit the plaintext space.
+    pub fn generate_batch_with_witnesses<R: rand::CryptoRng + rand::RngCore + ?Sized>(
+        &self,
+        secrets: &[BigUint],
+        rng: &mut R,
+    ) -> Result<Vec<(Puzzle, Randomness)>, LhtlpError> {
+        let space = self.plaintext_space();
+        if !secrets.iter().all(|secret| space.fits(secret)) {
+            return Err(LhtlpError::InvalidSecret);
+        }
+        let witnesses: Vec<BigUint> = secrets.iter().map(|_| self.sample_randomness(rng)).collect();
+        Ok(secrets
+            .iter()
+            .zip(&witnesses)
+            .map(|(secret, r)| (self.generate_with_randomness(secret, r), Randomness::new(r)))
+            .collect())
+    }
+
+    /// Open a puzzle without solving it, given its `randomness`, like `open_with_witness`.
+    pub fn open_with_randomness(&self, puzzle: &Puzzle, randomness: &Randomness) -> Result<BigUint, LhtlpError> {
+        self.open_with_witness(puzzle, &randomness.to_biguint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn revealed_randomness_opens_puzzle() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let output = lhtlp.generate_revealing_randomness(42);
+        assert!(lhtlp.open_with_randomness(&output.puzzle, &output.randomness) == Ok(BigUint::from(42u32)));
+        assert!(lhtlp.solve(output.puzzle.clone()) == BigUint::from(42u32));
+
+        let other = lhtlp.generate_revealing_randomness(42);
+        assert!(lhtlp.open_with_randomness(&output.puzzle, &other.randomness) == Err(LhtlpError::InvalidPuzzle));
+    }
+
+    #[test]
+    fn randomness_is_zeroized() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut randomness = lhtlp.generate_revealing_randomness(42).randomness;
+        assert!(format!("{:?}", randomness) == "Randomness(..)");
+        randomness.zeroize();
+        assert!(randomness.bytes.is_empty());
+    }
+
+    #[test]
+    fn batch_with_witnesses() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let secrets: Vec<BigUint> = [0u64, 1, 42, u64::MAX].map(BigUint::from).to_vec();
+        let batch = lhtlp.generate_batch_with_witnesses(&secrets, &mut rand::thread_rng()).unwrap();
+        assert!(batch.len() == secrets.len());
+        for (i, (puzzle, randomness)) in batch.iter().enumerate() {
+            assert!(lhtlp.solve(puzzle.clone()) == secrets[i]);
+            assert!(lhtlp.open_with_randomness(puzzle, randomness).as_ref() == Ok(&secrets[i]));
+            assert!(batch[..i].iter().all(|(_, other)| other != randomness));
+        }
+
+        let too_large = [lhtlp.n.clone()];
+        assert!(lhtlp.generate_batch_with_witnesses(&too_large, &mut rand::thread_rng()) == Err(LhtlpError::InvalidSecret));
+    }
+}
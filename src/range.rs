@@ -0,0 +1,285 @@
+use crate::num_primes::RandBigInt;
+use crate::{LhtlpError, PrivateKey, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use rand::Rng;
+use std::cmp::Ordering;
+
+/// Position of the secret of a puzzle relative to a public bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundCheckResult {
+    Above,
+    Below,
+    Equal,
+}
+
+/// Fiat-Shamir OR-proof that `c` is `(1+n)^b * rho^n mod n^2` for a bit `b`.
+///
+/// Branch `j` proves that `c / (1+n)^j` is an `n`-th residue: `z_j^n = a_j * (c / (1+n)^j)^e_j`.
+/// The branch of the other bit is simulated, and `e_0 + e_1` is the global challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitProof {
+    pub c: BigUint,
+    pub a: [BigUint; 2],
+    pub e: [BigUint; 2],
+    pub z: [BigUint; 2],
+}
+
+/// Proof that the secret of a puzzle is above, below or equal to a public bound.
+///
+/// The puzzle `v` is a Paillier ciphertext `(1+n)^s * w^n mod n^2` with `w = u^(2^T) mod n`. For
+/// `Above` the proof shows that `d = s - bound - 1` is in `[0, 2^k)`, for `Below` that
+/// `d = bound - 1 - s` is: `d` is committed bit by bit in `bits`, and `root` is an `n`-th root
+/// of `v^(+-1) * (1+n)^offset / prod(c_i^(2^i))`. For `Equal` there are no bits and `root` opens
+/// `v / (1+n)^bound` directly. With `k = bits(n) - 2` and both the secret and the bound below
+/// `2^k`, the difference cannot wrap around `n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundProof {
+    pub bits: Vec<BitProof>,
+    pub root: BigUint,
+}
+
+impl LHTLP {
+    /// Number of bits of the differences in a bound proof.
+    fn range_bits(&self) -> u64 {
+        self.n.bits() - 2
+    }
+
+    /// Size in bits of the bound proof challenge, below the size of the factors of `n`.
+    fn range_challenge_bits(&self) -> u64 {
+        (self.n.bits() / 2 - 2).min(128)
+    }
+
+    fn range_challenge(&self, puzzle: &Puzzle, bound: &BigUint, result: BoundCheckResult, bits: &[BitProof]) -> BigUint {
+        let mut data = self.fingerprint();
+        data.push(result as u8);
+        let values = [&puzzle.u, &puzzle.v, bound]
+            .into_iter()
+            .chain(bits.iter().flat_map(|bit| [&bit.c, &bit.a[0], &bit.a[1]]));
+        for value in values {
+            let bytes = value.to_bytes_be();
+            data.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+            data.extend_from_slice(&bytes);
+        }
+        BigUint::from_bytes_be(&self.hash_suite.digest(&data)) % (BigUint::from(1u32) << self.range_challenge_bits())
+    }
+
+    /// `v^(+-1) * (1+n)^offset mod n^2`, the value whose bits are committed for `result`.
+    fn range_target(&self, puzzle: &Puzzle, bound: &BigUint, result: BoundCheckResult) -> Result<BigUint, LhtlpError> {
+        let n2 = &self.n2;
+        match result {
+            BoundCheckResult::Above => {
+                let offset = &self.n - (bound + 1u32) % &self.n;
+                Ok(&puzzle.v * self.plaintext_power(&offset, n2) % n2)
+            }
+            BoundCheckResult::Below => {
+                let offset = (bound + &self.n - 1u32) % &self.n;
+                Ok(self.invert(&puzzle.v, n2)? * self.plaintext_power(&offset, n2) % n2)
+            }
+            BoundCheckResult::Equal => {
+                let offset = &self.n - bound % &self.n;
+                Ok(&puzzle.v * self.plaintext_power(&offset, n2) % n2)
+            }
+        }
+    }
+
+    /// Open a puzzle with the trapdoor, compare its secret to `lower_bound` and prove the outcome.
+    ///
+    /// The proof convinces anyone holding the public parameters of the comparison without
+    /// revealing the secret, except that `Equal` reveals it to be `lower_bound`. Fails with
+    /// `LhtlpError::ParameterMismatch` if `key` is not the factorization of `n`, and with
+    /// `LhtlpError::BoundCheckRange` if the secret or the bound is not below `2^(bits(n) - 2)`.
+    pub fn solve_and_check_lower_bound(&self, key: &PrivateKey, puzzle: &Puzzle, lower_bound: &BigUint) -> Result<(BoundCheckResult, BoundProof), LhtlpError> {
+        self.verify_puzzle(puzzle)?;
+        let exponent = self.precompute_solve_exponent(key).ok_or(LhtlpError::ParameterMismatch)?;
+        let w = puzzle.u.modpow(&exponent, &self.n);
+        let secret = self.try_decode(&puzzle.v, &w)?;
+        let k = self.range_bits();
+        let bits = secret.bits().max(lower_bound.bits());
+        if bits > k {
+            return Err(LhtlpError::BoundCheckRange { bits, limit: k });
+        }
+
+        let (result, difference, k) = match secret.cmp(lower_bound) {
+            Ordering::Greater => (BoundCheckResult::Above, &secret - lower_bound - 1u32, k),
+            Ordering::Less => (BoundCheckResult::Below, lower_bound - &secret - 1u32, k),
+            Ordering::Equal => (BoundCheckResult::Equal, BigUint::from(0u32), 0),
+        };
+        let n2 = &self.n2;
+        let mut rng = rand::thread_rng();
+        let challenge_modulus = BigUint::from(1u32) << self.range_challenge_bits();
+
+        // Commit to each bit and run the real branch up to its commitment, simulating the other.
+        let mut bits = Vec::with_capacity(k as usize);
+        let mut secrets = Vec::with_capacity(k as usize);
+        let mut product = BigUint::from(1u32);
+        for i in (0..k).rev() {
+            let bit = difference.bit(i) as usize;
+            let rho = self.sample_unit(&mut rng);
+            let c = self.plaintext_power(&BigUint::from(bit), n2) * rho.modpow(&self.n, n2) % n2;
+            product = &product * &product % &self.n * &rho % &self.n;
+
+            let t = self.sample_unit(&mut rng);
+            let mut a = [BigUint::from(0u32), BigUint::from(0u32)];
+            let mut e = a.clone();
+            let mut z = a.clone();
+            a[bit] = t.modpow(&self.n, n2);
+            let other = 1 - bit;
+            e[other] = rng.gen_biguint_below(&challenge_modulus);
+            z[other] = self.sample_unit(&mut rng);
+            let residue = self.bit_residue(&c, other)?;
+            a[other] = z[other].modpow(&self.n, n2) * self.invert(&residue.modpow(&e[other], n2), n2)? % n2;
+            bits.push(BitProof { c, a, e, z });
+            secrets.push((bit, rho, t));
+        }
+        bits.reverse();
+        secrets.reverse();
+
+        let challenge = self.range_challenge(puzzle, lower_bound, result, &bits);
+        for (proof, (bit, rho, t)) in bits.iter_mut().zip(secrets) {
+            let other = 1 - bit;
+            proof.e[bit] = (&challenge + &challenge_modulus - &proof.e[other]) % &challenge_modulus;
+            proof.z[bit] = t * rho.modpow(&proof.e[bit], &self.n) % &self.n;
+        }
+
+        let w = match result {
+            BoundCheckResult::Below => self.invert(&w, &self.n)?,
+            _ => w,
+        };
+        let root = w * self.invert(&product, &self.n)? % &self.n;
+        Ok((result, BoundProof { bits, root }))
+    }
+
+    /// Check a proof from `solve_and_check_lower_bound` that the secret of `puzzle` compares to
+    /// `lower_bound` as stated by `result`.
+    ///
+    /// The proof is about the Paillier plaintext `s mod n` of `v`, which is the solution of every
+    /// well-formed puzzle, and shows that the difference with the bound, taken modulo `n`, lies in
+    /// `[0, 2^k)` with `k = bits(n) - 2`. It does not show that `s < 2^k`: a plaintext close to
+    /// `n` passes as `Below` any bound, since `bound - 1 - s` wraps around to a small value. The
+    /// comparison therefore only holds for secrets known to be below `2^k`, for instance from a
+    /// range proof or because the verifier trusts the generator to respect the plaintext space.
+    pub fn verify_lower_bound(&self, puzzle: &Puzzle, lower_bound: &BigUint, result: BoundCheckResult, proof: &BoundProof) -> bool {
+        let k = match result {
+            BoundCheckResult::Equal => 0,
+            _ => self.range_bits(),
+        };
+        let one = BigUint::from(1u32);
+        let unit = |value: &BigUint, modulus: &BigUint| value < modulus && value.gcd(&self.n) == one;
+        if self.verify_puzzle(puzzle).is_err()
+            || lower_bound.bits() > self.range_bits()
+            || proof.bits.len() as u64 != k
+            || !unit(&proof.root, &self.n)
+        {
+            return false;
+        }
+        let n2 = &self.n2;
+        let challenge_modulus = &one << self.range_challenge_bits();
+        let challenge = self.range_challenge(puzzle, lower_bound, result, &proof.bits);
+
+        let mut product = BigUint::from(1u32);
+        for bit in proof.bits.iter().rev() {
+            if !unit(&bit.c, n2) || (&bit.e[0] + &bit.e[1]) % &challenge_modulus != challenge {
+                return false;
+            }
+            for j in 0..2 {
+                let Ok(residue) = self.bit_residue(&bit.c, j) else { return false };
+                if !unit(&bit.a[j], n2) || bit.e[j] >= challenge_modulus || !unit(&bit.z[j], &self.n) {
+                    return false;
+                }
+                if bit.z[j].modpow(&self.n, n2) != &bit.a[j] * residue.modpow(&bit.e[j], n2) % n2 {
+                    return false;
+                }
+            }
+            product = &product * &product % n2 * &bit.c % n2;
+        }
+
+        match self.range_target(puzzle, lower_bound, result) {
+            Ok(target) => target == product * proof.root.modpow(&self.n, n2) % n2,
+            Err(_) => false,
+        }
+    }
+
+    /// `c / (1+n)^bit mod n^2`, an `n`-th residue when `c` commits to `bit`.
+    fn bit_residue(&self, c: &BigUint, bit: usize) -> Result<BigUint, LhtlpError> {
+        match bit {
+            0 => Ok(c.clone()),
+            _ => Ok(c * self.invert(&(&self.n + 1u32), &self.n2)? % &self.n2),
+        }
+    }
+
+    /// A uniformly random unit modulo `n`.
+    fn sample_unit<R: Rng + ?Sized>(&self, rng: &mut R) -> BigUint {
+        loop {
+            let value = rng.gen_biguint_below(&self.n);
+            if value.gcd(&self.n) == BigUint::from(1u32) {
+                return value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::fixed_instance;
+
+    const DIFFICULTY: u64 = 1000;
+
+    fn instance() -> (LHTLP, PrivateKey) {
+        fixed_instance(DIFFICULTY)
+    }
+
+    #[test]
+    fn bound_checks() {
+        let (lhtlp, key) = instance();
+        let reserve = BigUint::from(1000u32);
+        for (bid, expected) in [(1500u64, BoundCheckResult::Above), (999, BoundCheckResult::Below), (1000, BoundCheckResult::Equal), (0, BoundCheckResult::Below)] {
+            let puzzle = lhtlp.generate(bid);
+            let (result, proof) = lhtlp.solve_and_check_lower_bound(&key, &puzzle, &reserve).unwrap();
+            assert!(result == expected);
+            assert!(lhtlp.verify_lower_bound(&puzzle, &reserve, result, &proof));
+            for wrong in [BoundCheckResult::Above, BoundCheckResult::Below, BoundCheckResult::Equal] {
+                if wrong != result {
+                    assert!(!lhtlp.verify_lower_bound(&puzzle, &reserve, wrong, &proof));
+                }
+            }
+            assert!(!lhtlp.verify_lower_bound(&puzzle, &(&reserve + 1u32), result, &proof));
+        }
+    }
+
+    #[test]
+    fn tampered_proofs_fail() {
+        let (lhtlp, key) = instance();
+        let reserve = BigUint::from(1000u32);
+        let puzzle = lhtlp.generate(999);
+        let (result, proof) = lhtlp.solve_and_check_lower_bound(&key, &puzzle, &reserve).unwrap();
+        assert!(result == BoundCheckResult::Below);
+
+        let other = lhtlp.generate(999);
+        assert!(!lhtlp.verify_lower_bound(&other, &reserve, result, &proof));
+        let mut tampered = proof.clone();
+        tampered.bits[3].z[0] = &tampered.bits[3].z[0] + 1u32;
+        assert!(!lhtlp.verify_lower_bound(&puzzle, &reserve, result, &tampered));
+        let mut tampered = proof.clone();
+        tampered.bits.pop();
+        assert!(!lhtlp.verify_lower_bound(&puzzle, &reserve, result, &tampered));
+        let mut tampered = proof;
+        tampered.root = &tampered.root + 1u32;
+        assert!(!lhtlp.verify_lower_bound(&puzzle, &reserve, result, &tampered));
+    }
+
+    #[test]
+    fn bound_check_errors() {
+        let (lhtlp, key) = instance();
+        let puzzle = lhtlp.generate(42);
+        let huge = BigUint::from(1u32) << lhtlp.range_bits();
+        let range = Err(LhtlpError::BoundCheckRange { bits: lhtlp.range_bits() + 1, limit: lhtlp.range_bits() });
+        assert!(lhtlp.solve_and_check_lower_bound(&key, &puzzle, &huge) == range);
+        let other = PrivateKey::new(BigUint::from(1019u32), key.q().clone());
+        assert!(lhtlp.solve_and_check_lower_bound(&other, &puzzle, &BigUint::from(1u32)) == Err(LhtlpError::ParameterMismatch));
+    }
+}
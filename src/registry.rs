@@ -0,0 +1,154 @@
+use crate::{LhtlpError, Puzzle, PuzzleEnvelope, LHTLP};
+use std::collections::HashMap;
+
+/// Fingerprint of the public parameters of an instance, as returned by `LHTLP::fingerprint`.
+pub type Fingerprint = Vec<u8>;
+
+/// A set of LHTLP instances, indexed by parameter fingerprint.
+///
+/// Lets a collector receiving puzzles from several setups route each envelope to the instance
+/// that generated it and aggregate puzzles per instance without mixing them up.
+#[derive(Debug, Clone, Default)]
+pub struct InstanceRegistry {
+    instances: HashMap<Fingerprint, LHTLP>,
+}
+
+impl InstanceRegistry {
+    pub fn new() -> InstanceRegistry {
+        InstanceRegistry::default()
+    }
+
+    /// Register an instance, returning its fingerprint. Registering the same parameters twice
+    /// replaces the previous instance.
+    pub fn register(&mut self, lhtlp: LHTLP) -> Fingerprint {
+        let fingerprint = lhtlp.fingerprint();
+        self.instances.insert(fingerprint.clone(), lhtlp);
+        fingerprint
+    }
+
+    /// The instance registered under `fingerprint`, if any.
+    pub fn get(&self, fingerprint: &Fingerprint) -> Option<&LHTLP> {
+        self.instances.get(fingerprint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// The instance that generated the puzzle in `envelope`, found by its fingerprint.
+    ///
+    /// An unknown fingerprint is reported as `LhtlpError::UnknownFingerprint` at index 0.
+    pub fn route(&self, envelope: &PuzzleEnvelope) -> Result<&LHTLP, LhtlpError> {
+        self.get(&envelope.fingerprint).ok_or(LhtlpError::UnknownFingerprint { indices: vec![0] })
+    }
+
+    /// Group `envelopes` by fingerprint and evaluate the puzzles of each group under its instance.
+    ///
+    /// Fails with `LhtlpError::UnknownFingerprint`, listing every offending index, if some
+    /// envelopes belong to no registered instance. Each envelope is then validated by its instance
+    /// like in `LHTLP::open_envelope`, and the indices of those failing are listed in
+    /// `LhtlpError::InvalidEnvelopes`, so that no malformed puzzle reaches an aggregate.
+    pub fn aggregate_all(&self, envelopes: Vec<PuzzleEnvelope>) -> Result<HashMap<Fingerprint, Puzzle>, LhtlpError> {
+        let unknown: Vec<usize> = envelopes
+            .iter()
+            .enumerate()
+            .filter(|(_, envelope)| !self.instances.contains_key(&envelope.fingerprint))
+            .map(|(i, _)| i)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(LhtlpError::UnknownFingerprint { indices: unknown });
+        }
+
+        let invalid: Vec<usize> = envelopes
+            .iter()
+            .enumerate()
+            .filter(|(_, envelope)| self.instances[&envelope.fingerprint].open_envelope(envelope).is_err())
+            .map(|(i, _)| i)
+            .collect();
+        if !invalid.is_empty() {
+            return Err(LhtlpError::InvalidEnvelopes { indices: invalid });
+        }
+
+        let mut groups: HashMap<Fingerprint, Vec<Puzzle>> = HashMap::new();
+        for envelope in envelopes {
+            groups.entry(envelope.fingerprint).or_default().push(envelope.puzzle);
+        }
+        Ok(groups
+            .into_iter()
+            .map(|(fingerprint, puzzles)| {
+                let combined = self.instances[&fingerprint].evaluate(puzzles);
+                (fingerprint, combined)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn aggregate_per_instance() {
+        let first = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let second = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut registry = InstanceRegistry::new();
+        let first_id = registry.register(first.clone());
+        let second_id = registry.register(second.clone());
+
+        let envelopes = vec![
+            PuzzleEnvelope::new(&first, first.generate(1)),
+            PuzzleEnvelope::new(&second, second.generate(10)),
+            PuzzleEnvelope::new(&first, first.generate(2)),
+            PuzzleEnvelope::new(&second, second.generate(20)),
+        ];
+        assert!(registry.route(&envelopes[1]).unwrap() == &second);
+
+        let aggregates = registry.aggregate_all(envelopes).unwrap();
+        assert!(aggregates.len() == 2);
+        assert!(first.solve(aggregates[&first_id].clone()) == BigUint::from(3u32));
+        assert!(second.solve(aggregates[&second_id].clone()) == BigUint::from(30u32));
+    }
+
+    #[test]
+    fn unregistered_puzzle() {
+        let known = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let unknown = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut registry = InstanceRegistry::new();
+        registry.register(known.clone());
+
+        let envelopes = vec![
+            PuzzleEnvelope::new(&known, known.generate(1)),
+            PuzzleEnvelope::new(&unknown, unknown.generate(2)),
+            PuzzleEnvelope::new(&known, known.generate(3)),
+            PuzzleEnvelope::new(&unknown, unknown.generate(4)),
+        ];
+        assert!(registry.route(&envelopes[1]).is_err());
+        assert!(registry.aggregate_all(envelopes) == Err(LhtlpError::UnknownFingerprint { indices: vec![1, 3] }));
+    }
+
+    #[test]
+    fn malformed_envelopes() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut registry = InstanceRegistry::new();
+        registry.register(lhtlp.clone());
+
+        let mut envelopes = vec![
+            PuzzleEnvelope::new(&lhtlp, lhtlp.generate(1)),
+            PuzzleEnvelope::new(&lhtlp, Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) }),
+            PuzzleEnvelope::new(&lhtlp, lhtlp.generate(3)),
+        ];
+        assert!(registry.aggregate_all(envelopes.clone()) == Err(LhtlpError::InvalidEnvelopes { indices: vec![1] }));
+
+        envelopes[1] = PuzzleEnvelope::new(&lhtlp, lhtlp.generate(2));
+        envelopes[2].difficulty = (DIFFICULTY + 1).into();
+        assert!(registry.aggregate_all(envelopes) == Err(LhtlpError::InvalidEnvelopes { indices: vec![2] }));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::vdf::expand;
+use crate::{HashSuite, LhtlpError, PrivateKey, SolvePolicy, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+
+/// Derive the generator `g` from the modulus alone, so that every holder of the key agrees on it.
+fn derive_generator(hash_suite: HashSuite, n: &BigUint) -> BigUint {
+    let mut counter = 0u64;
+    loop {
+        let x = expand(hash_suite, &[b"lhtlp-rsa-generator", &n.to_bytes_be(), &counter.to_be_bytes()], 2 * n.bits() as usize) % n;
+        if let Some(g) = (&x * &x % n).modinv(n) {
+            if g > BigUint::from(1u32) {
+                return g;
+            }
+        }
+        counter += 1;
+    }
+}
+
+impl LHTLP {
+    /// Import the modulus of an RSA public key as LHTLP parameters, for participants without the trapdoor.
+    ///
+    /// The generator is derived from `n` by hashing, so it matches the one obtained by the holder
+    /// of the private key with `from_rsa_private_key`. Without the factorization, `h` is computed
+    /// with `difficulty` sequential squarings, which takes as long as solving a puzzle.
+    ///
+    /// RSA primes are not safe primes, so the squaring group may be smaller than with `setup`.
+    pub fn from_rsa_public_key(key: &rsa::RsaPublicKey, difficulty: BigUint) -> Result<LHTLP, LhtlpError> {
+        LHTLP::from_rsa_public_key_with_hash_suite(key, difficulty, HashSuite::default())
+    }
+
+    /// Like `from_rsa_public_key`, deriving the generator under `hash_suite`, which the instance keeps.
+    ///
+    /// Both parties must import the key under the same suite to agree on the generator.
+    pub fn from_rsa_public_key_with_hash_suite(key: &rsa::RsaPublicKey, difficulty: BigUint, hash_suite: HashSuite) -> Result<LHTLP, LhtlpError> {
+        let n = BigUint::from_bytes_be(&key.n().to_bytes_be());
+        if n.is_even() {
+            return Err(LhtlpError::UnsupportedKey);
+        }
+        let g = derive_generator(hash_suite, &n);
+        let mut lhtlp = LHTLP { difficulty, n2: &n * &n, n, g: g.clone(), h: g, policy: SolvePolicy::default(), hash_suite };
+        lhtlp.h = lhtlp.square(&lhtlp.g, &lhtlp.difficulty);
+        Ok(lhtlp)
+    }
+
+    /// Import an RSA private key as LHTLP parameters together with their trapdoor, for the trusted setup party.
+    ///
+    /// Fails with `LhtlpError::UnsupportedKey` for multi-prime keys.
+    pub fn from_rsa_private_key(key: &rsa::RsaPrivateKey, difficulty: BigUint) -> Result<(LHTLP, PrivateKey), LhtlpError> {
+        LHTLP::from_rsa_private_key_with_hash_suite(key, difficulty, HashSuite::default())
+    }
+
+    /// Like `from_rsa_private_key`, deriving the generator under `hash_suite`, which the instance keeps.
+    pub fn from_rsa_private_key_with_hash_suite(
+        key: &rsa::RsaPrivateKey,
+        difficulty: BigUint,
+        hash_suite: HashSuite,
+    ) -> Result<(LHTLP, PrivateKey), LhtlpError> {
+        let [p, q] = key.primes() else {
+            return Err(LhtlpError::UnsupportedKey);
+        };
+        let private = PrivateKey::new(BigUint::from_bytes_be(&p.to_bytes_be()), BigUint::from_bytes_be(&q.to_bytes_be()));
+        let n = private.modulus();
+        let g = derive_generator(hash_suite, &n);
+        let h = g.modpow(&BigUint::from(2u32).modpow(&difficulty, &private.phi_half()), &n);
+        Ok((LHTLP { difficulty, n2: &n * &n, n, g, h, policy: SolvePolicy::default(), hash_suite }, private))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+
+    #[test]
+    fn import_rsa_keys() {
+        let key = rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 512).unwrap();
+        let (private_instance, private) = LHTLP::from_rsa_private_key(&key, BigUint::from(DIFFICULTY)).unwrap();
+        let public_instance = LHTLP::from_rsa_public_key(&key.to_public_key(), BigUint::from(DIFFICULTY)).unwrap();
+
+        assert!(private_instance == public_instance);
+        assert!(private.modulus() == BigUint::from_bytes_be(&key.n().to_bytes_be()));
+
+        let puzzle = public_instance.generate(42);
+        assert!(private_instance.solve(puzzle) == BigUint::from(42u32));
+
+        let (sha384_private, _) =
+            LHTLP::from_rsa_private_key_with_hash_suite(&key, BigUint::from(DIFFICULTY), HashSuite::Sha384).unwrap();
+        let sha384_public =
+            LHTLP::from_rsa_public_key_with_hash_suite(&key.to_public_key(), BigUint::from(DIFFICULTY), HashSuite::Sha384).unwrap();
+        assert!(sha384_private == sha384_public && sha384_public.hash_suite() == HashSuite::Sha384);
+        assert!(sha384_public.g != public_instance.g);
+    }
+}
@@ -0,0 +1,427 @@
+use crate::{LhtlpError, Puzzle, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Number of squarings timed when calibrating the checkpoint stride.
+const CALIBRATION_SQUARINGS: u64 = 1000;
+
+/// The state of a solve in progress, from which solving can be resumed.
+///
+/// `w` holds `u^(2^squarings) mod n`, so a fresh partial solution starts from `w = u`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartialSolution {
+    pub puzzle: Puzzle,
+    pub w: BigUint,
+    pub squarings: u64,
+}
+
+impl PartialSolution {
+    /// Start solving `puzzle` from scratch.
+    pub fn new(puzzle: Puzzle) -> PartialSolution {
+        let w = puzzle.u.clone();
+        PartialSolution { puzzle, w, squarings: 0 }
+    }
+}
+
+/// In-memory store of solves in progress, keyed by `Puzzle::fingerprint`, to resume interrupted
+/// solves within a process.
+///
+/// The keys do not cover the instance, so a cache should only be used with instances sharing one
+/// modulus.
+#[derive(Debug, Clone, Default)]
+pub struct SolveCache {
+    map: HashMap<[u8; 32], PartialSolution>,
+}
+
+impl SolveCache {
+    pub fn new() -> SolveCache {
+        SolveCache::default()
+    }
+
+    /// The furthest state stored for `puzzle`.
+    pub fn check(&self, puzzle: &Puzzle) -> Option<&PartialSolution> {
+        self.map.get(&puzzle.fingerprint()).filter(|partial| &partial.puzzle == puzzle)
+    }
+
+    /// Store `partial` for `puzzle`, unless a state further along is already stored.
+    pub fn store(&mut self, puzzle: &Puzzle, partial: PartialSolution) {
+        let entry = self.map.entry(puzzle.fingerprint()).or_insert_with(|| partial.clone());
+        if entry.squarings < partial.squarings {
+            *entry = partial;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// How many squarings to perform between two checkpoints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckpointStride {
+    /// Pick the stride so that checkpointing takes at most `max_overhead` (a fraction, e.g. `0.01`)
+    /// of the total compute, based on a calibration of squaring against checkpointing cost.
+    Auto { max_overhead: f64 },
+    /// Checkpoint every given number of squarings.
+    Fixed(u64),
+}
+
+impl Default for CheckpointStride {
+    /// Auto-tuned stride keeping checkpointing under 1% of the total compute.
+    fn default() -> Self {
+        CheckpointStride::Auto { max_overhead: 0.01 }
+    }
+}
+
+/// Smallest stride such that `checkpoint` takes at most `max_overhead` of the time spent squaring.
+fn stride_for(squaring: Duration, checkpoint: Duration, max_overhead: f64) -> u64 {
+    if squaring.is_zero() || max_overhead <= 0.0 {
+        return u64::MAX;
+    }
+    let ratio = checkpoint.as_nanos() as f64 / squaring.as_nanos() as f64;
+    (ratio / max_overhead).ceil().clamp(1.0, u64::MAX as f64) as u64
+}
+
+impl LHTLP {
+    /// The difficulty as a count of squarings, failing with `LhtlpError::DifficultyTooLarge` if it
+    /// does not fit in `u64`, the most a solver can perform.
+    pub(crate) fn try_squarings(&self) -> Result<u64, LhtlpError> {
+        self.difficulty.to_u64().ok_or_else(|| LhtlpError::DifficultyTooLarge {
+            difficulty: self.difficulty.clone(),
+            limit: BigUint::from(u64::MAX),
+        })
+    }
+
+    /// Measure the time of a single squaring modulo `n`.
+    pub(crate) fn time_squaring(&self, base: &BigUint) -> Duration {
+        let mut w = base.clone();
+        let start = Instant::now();
+        for _ in 0..CALIBRATION_SQUARINGS {
+            w = &w * &w % &self.n;
+        }
+        start.elapsed() / CALIBRATION_SQUARINGS as u32
+    }
+
+    /// Compute the checkpoint stride keeping checkpoint serialization under `max_overhead` of the compute.
+    ///
+    /// The calibration times a batch of squarings modulo `n` against the serialization of a
+    /// checkpoint value. It takes a few milliseconds.
+    pub fn calibrate_checkpoint_stride(&self, max_overhead: f64) -> u64 {
+        let squaring = self.time_squaring(&self.g);
+        stride_for(squaring, self.time_serialization(&self.g), max_overhead)
+    }
+
+    /// Measure the time of serializing a value modulo `n`.
+    fn time_serialization(&self, w: &BigUint) -> Duration {
+        let start = Instant::now();
+        std::hint::black_box(w.to_bytes_be());
+        start.elapsed()
+    }
+
+    /// Open a puzzle by sequential squaring, calling `on_checkpoint` every `stride` squarings.
+    ///
+    /// Solving starts from `partial`, so a solve can be resumed from the last checkpoint handed to
+    /// `on_checkpoint`. With `CheckpointStride::Auto` the stride is first calibrated and then
+    /// re-tuned using the time actually spent in `on_checkpoint`, which usually dominates when
+    /// checkpoints are persisted. Fails with `LhtlpError::DifficultyTooLarge` if the difficulty
+    /// does not fit in `u64`.
+    pub fn solve_with_checkpointing<F>(&self, partial: PartialSolution, stride: CheckpointStride, on_checkpoint: F) -> Result<BigUint, LhtlpError>
+    where
+        F: FnMut(&PartialSolution),
+    {
+        let state = self.advance_with_checkpointing(partial, stride, on_checkpoint)?;
+        self.try_decode(&state.puzzle.v, &state.w)
+    }
+
+    /// Square `partial` up to the difficulty, returning the final state.
+    fn advance_with_checkpointing<F>(
+        &self,
+        partial: PartialSolution,
+        stride: CheckpointStride,
+        mut on_checkpoint: F,
+    ) -> Result<PartialSolution, LhtlpError>
+    where
+        F: FnMut(&PartialSolution),
+    {
+        let total = self.try_squarings()?;
+        let mut state = partial;
+        let (mut stride, squaring) = match stride {
+            CheckpointStride::Fixed(stride) => (stride.max(1), None),
+            CheckpointStride::Auto { max_overhead } => {
+                let squaring = self.time_squaring(&state.w);
+                let stride = stride_for(squaring, self.time_serialization(&state.w), max_overhead);
+                (stride, Some((squaring, max_overhead)))
+            }
+        };
+
+        while state.squarings < total {
+            let steps = stride.min(total - state.squarings);
+            state.w = self.square(&state.w, &BigUint::from(steps));
+            state.squarings += steps;
+            if state.squarings < total {
+                let start = Instant::now();
+                on_checkpoint(&state);
+                if let Some((squaring, max_overhead)) = squaring {
+                    stride = stride_for(squaring, start.elapsed(), max_overhead);
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    /// Open a puzzle by raising `u` to `2^batch` at a time, in `ceil(difficulty / batch)` modpows.
+    ///
+    /// The result is the same as `solve`. Each modpow performs its `batch` squarings in Montgomery
+    /// form, which can beat the plain multiply-and-reduce loop on large moduli. The puzzle is
+    /// checked and the policy of the instance enforced like in `solve_checked`, with the clock
+    /// read between batches. Panics if `batch` is zero.
+    pub fn solve_batched(&self, puzzle: Puzzle, batch: u32) -> Result<BigUint, LhtlpError> {
+        assert!(batch > 0, "squaring batch must be positive");
+        self.verify_puzzle(&puzzle)?;
+        self.policy.check(self)?;
+        let deadline = self.policy.max_wall_clock.map(|max| Instant::now() + max);
+        let total = self.try_squarings()?;
+        let mut state = PartialSolution::new(puzzle);
+        while state.squarings < total {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(LhtlpError::DeadlineExceeded(Box::new(state)));
+            }
+            let steps = u64::from(batch).min(total - state.squarings);
+            state.w = LHTLP::square_in_chunks(&state.w, &BigUint::from(steps), &self.n, steps, &mut 0);
+            state.squarings += steps;
+        }
+        self.try_decode(&state.puzzle.v, &state.w)
+    }
+
+    /// Open a puzzle like `solve`, returning the solution with the number of squarings performed.
+    ///
+    /// A testing aid catching optimizations that skip iterations: the squarings are counted chunk
+    /// by chunk inside the squaring routine of `solve`, and the count must equal the difficulty,
+    /// which is asserted in debug builds. The only exception is a `u` reaching 1 early, such as
+    /// `u = 1`, after which the squarings are skipped. Fails with `LhtlpError::DifficultyTooLarge`
+    /// if the difficulty does not fit in `u64`.
+    pub fn solve_counted(&self, puzzle: Puzzle) -> Result<(BigUint, u64), LhtlpError> {
+        let total = self.try_squarings()?;
+        let mut count = 0u64;
+        let w = LHTLP::square_in_chunks(&puzzle.u, &self.difficulty, &self.n, SQUARING_CHUNK, &mut count);
+        debug_assert!(count == total || w == BigUint::from(1u32), "skipped {} of {} squarings", total - count, total);
+        Ok((self.try_decode(&puzzle.v, &w)?, count))
+    }
+
+    /// Open a puzzle with an external implementation of the squaring, such as a hardware accelerator.
+    ///
+    /// `oracle` must map `w` to `w^2 mod n`; the crate drives the `T` squarings and decodes the
+    /// result. The first step is checked against the built-in squaring, failing with
+    /// `LhtlpError::InvalidOracle` on a misconfigured oracle before the long loop starts, and with
+    /// `LhtlpError::DifficultyTooLarge` if the difficulty does not fit in `u64`.
+    pub fn solve_with_oracle(&self, puzzle: Puzzle, oracle: impl Fn(&BigUint) -> BigUint) -> Result<BigUint, LhtlpError> {
+        let total = self.try_squarings()?;
+        let mut w = puzzle.u;
+        if total > 0 {
+            let first = oracle(&w);
+            if first != &w * &w % &self.n {
+                return Err(LhtlpError::InvalidOracle);
+            }
+            w = first;
+        }
+        for _ in 1..total {
+            w = oracle(&w);
+        }
+        self.try_decode(&puzzle.v, &w)
+    }
+
+    /// Open a puzzle like `solve_with_checkpointing`, resuming from the state stored in `cache`
+    /// and storing every checkpoint into it.
+    ///
+    /// The final state is stored as well, so solving the same puzzle again performs no squaring.
+    /// Fails with `LhtlpError::DifficultyTooLarge` if the difficulty does not fit in `u64`.
+    pub fn solve_with_cache(&self, puzzle: Puzzle, stride: CheckpointStride, cache: &mut SolveCache) -> Result<BigUint, LhtlpError> {
+        let total = self.try_squarings()?;
+        let partial = match cache.check(&puzzle) {
+            Some(partial) if partial.squarings <= total => partial.clone(),
+            _ => PartialSolution::new(puzzle.clone()),
+        };
+        let state = self.advance_with_checkpointing(partial, stride, |checkpoint| cache.store(&puzzle, checkpoint.clone()))?;
+        let solution = self.try_decode(&state.puzzle.v, &state.w)?;
+        cache.store(&puzzle, state);
+        Ok(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn stride_for_overhead() {
+        let squaring = Duration::from_micros(1);
+        assert!(stride_for(squaring, Duration::from_micros(10), 0.01) == 1000);
+        assert!(stride_for(squaring, Duration::ZERO, 0.01) == 1);
+        assert!(stride_for(Duration::ZERO, Duration::from_micros(10), 0.01) == u64::MAX);
+    }
+
+    #[test]
+    fn solve_with_fixed_stride() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut checkpoints = Vec::new();
+        let partial = PartialSolution::new(lhtlp.generate(42));
+        let solution = lhtlp.solve_with_checkpointing(partial, CheckpointStride::Fixed(300), |p| checkpoints.push(p.clone()));
+
+        assert!(solution == Ok(BigUint::from(42u32)));
+        assert!(checkpoints.iter().map(|p| p.squarings).collect::<Vec<_>>() == vec![300, 600, 900]);
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let mut checkpoints = Vec::new();
+        let partial = PartialSolution::new(lhtlp.generate(42));
+        let solution = lhtlp.solve_with_checkpointing(partial, CheckpointStride::Fixed(400), |p| checkpoints.push(p.clone()));
+        assert!(solution == Ok(BigUint::from(42u32)));
+
+        let resumed = lhtlp.solve_with_checkpointing(checkpoints[1].clone(), CheckpointStride::Fixed(400), |_| {});
+        assert!(resumed == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn difficulty_beyond_u64() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let lhtlp = LHTLP { difficulty: BigUint::from(u64::MAX) + 1u32, ..lhtlp };
+        let result = lhtlp.solve_with_checkpointing(PartialSolution::new(puzzle.clone()), CheckpointStride::Fixed(300), |_| {});
+        assert!(matches!(result, Err(LhtlpError::DifficultyTooLarge { .. })));
+        let result = lhtlp.solve_with_cache(puzzle.clone(), CheckpointStride::Fixed(300), &mut SolveCache::new());
+        assert!(matches!(result, Err(LhtlpError::DifficultyTooLarge { .. })));
+        assert!(matches!(lhtlp.solve_with_observer(puzzle.clone(), 100, |_, _| {}), Err(LhtlpError::DifficultyTooLarge { .. })));
+        assert!(matches!(lhtlp.solve_with_progress(puzzle.clone(), |_, _| {}), Err(LhtlpError::DifficultyTooLarge { .. })));
+        assert!(matches!(lhtlp.solve_counted(puzzle), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+
+    #[test]
+    fn solve_with_auto_stride() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(lhtlp.calibrate_checkpoint_stride(0.01) >= 1);
+
+        let partial = PartialSolution::new(lhtlp.generate(42));
+        let solution = lhtlp.solve_with_checkpointing(partial, CheckpointStride::default(), |_| {});
+        assert!(solution == Ok(BigUint::from(42u32)));
+    }
+
+    #[test]
+    fn solve_cache_resumes() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let mut checkpoints = Vec::new();
+        let _ = lhtlp.solve_with_checkpointing(PartialSolution::new(puzzle.clone()), CheckpointStride::Fixed(300), |p| {
+            checkpoints.push(p.clone())
+        });
+
+        let mut cache = SolveCache::new();
+        assert!(cache.check(&puzzle).is_none());
+        cache.store(&puzzle, checkpoints[1].clone());
+        cache.store(&puzzle, checkpoints[0].clone());
+        assert!(cache.check(&puzzle).unwrap().squarings == 600);
+
+        let solution = lhtlp.solve_with_cache(puzzle.clone(), CheckpointStride::Fixed(300), &mut cache);
+        assert!(solution == Ok(BigUint::from(42u32)));
+        assert!(cache.check(&puzzle).unwrap().squarings == DIFFICULTY);
+        assert!(cache.len() == 1);
+        assert!(cache.check(&lhtlp.generate(42)).is_none());
+    }
+
+    #[test]
+    fn solve_cache_skips_repeated_work() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(10 * DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        let mut cache = SolveCache::new();
+
+        assert!(lhtlp.solve_with_cache(puzzle.clone(), CheckpointStride::Fixed(1000), &mut cache) == Ok(BigUint::from(42u32)));
+        let stored = cache.check(&puzzle).unwrap().clone();
+        assert!(stored.squarings == 10 * DIFFICULTY);
+
+        // Resuming from the stored state has no squaring left, so no checkpoint is reached.
+        let mut checkpoints = 0;
+        let state = lhtlp.advance_with_checkpointing(stored.clone(), CheckpointStride::Fixed(1000), |_| checkpoints += 1).unwrap();
+        assert!(checkpoints == 0 && state == stored);
+        assert!(lhtlp.solve_with_cache(puzzle.clone(), CheckpointStride::Fixed(1000), &mut cache) == Ok(BigUint::from(42u32)));
+        assert!(cache.check(&puzzle) == Some(&stored));
+    }
+
+    #[test]
+    fn solve_with_oracle() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate(42);
+        let calls = std::cell::Cell::new(0u64);
+        let oracle = |w: &BigUint| {
+            calls.set(calls.get() + 1);
+            w.modpow(&BigUint::from(2u32), &lhtlp.n)
+        };
+        assert!(lhtlp.solve_with_oracle(puzzle.clone(), oracle) == Ok(BigUint::from(42u32)));
+        assert!(calls.get() == DIFFICULTY);
+
+        let unreduced = |w: &BigUint| w * w;
+        assert!(lhtlp.solve_with_oracle(puzzle.clone(), unreduced) == Err(LhtlpError::InvalidOracle));
+
+        let lhtlp = LHTLP { difficulty: BigUint::from(u64::MAX) + 1u32, ..lhtlp };
+        let result = lhtlp.solve_with_oracle(puzzle, |w: &BigUint| w * w % &lhtlp.n);
+        assert!(matches!(result, Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+
+    #[test]
+    fn solve_batched_matches_solve() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate(42);
+        for batch in [1, 7, 64, 1000, 4096] {
+            assert!(lhtlp.solve_batched(puzzle.clone(), batch) == Ok(BigUint::from(42u32)));
+        }
+
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.solve_batched(malformed, 64) == Err(LhtlpError::InvalidPuzzle));
+        let limited = lhtlp.with_policy(crate::SolvePolicy::default().with_max_difficulty(DIFFICULTY - 1));
+        assert!(matches!(limited.solve_batched(puzzle, 64), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with --release --ignored --nocapture"]
+    fn solve_batched_timing() {
+        let lhtlp = LHTLP::setup(256u64, 10_000_000u64);
+        let puzzle = lhtlp.generate(42);
+
+        let start = Instant::now();
+        let mut w = puzzle.u.clone();
+        for _ in 0..lhtlp.try_squarings().unwrap() {
+            w = &w * &w % &lhtlp.n;
+        }
+        println!("incremental loop: {:?}", start.elapsed());
+        assert!(lhtlp.decode(&puzzle.v, &w) == BigUint::from(42u32));
+
+        for batch in [64, 1024, 65536] {
+            let start = Instant::now();
+            assert!(lhtlp.solve_batched(puzzle.clone(), batch) == Ok(BigUint::from(42u32)));
+            println!("batch {}: {:?}", batch, start.elapsed());
+        }
+    }
+
+    #[test]
+    fn solve_counted() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+        assert!(lhtlp.solve_counted(puzzle.clone()) == Ok((BigUint::from(42u32), DIFFICULTY)));
+        assert!(lhtlp.solve(puzzle) == BigUint::from(42u32));
+
+        let chunked = LHTLP::setup(LAMBDA, BigUint::from(3 * SQUARING_CHUNK + 7));
+        assert!(chunked.solve_counted(chunked.generate(42)) == Ok((BigUint::from(42u32), 3 * SQUARING_CHUNK + 7)));
+        let trivial = Puzzle { u: BigUint::from(1u32), v: BigUint::from(1u32) };
+        assert!(lhtlp.solve_counted(trivial) == Ok((BigUint::from(0u32), 0)));
+    }
+}
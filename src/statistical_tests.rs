@@ -0,0 +1,56 @@
+//! Statistical checks of the randomness used by `generate`.
+//!
+//! These are slow and probabilistic (each fails with probability below 0.1% on a correct
+//! implementation), so they only run with `cargo test --release --features statistical_tests`.
+use crate::LHTLP;
+use num_bigint::BigUint;
+
+const DIFFICULTY: u64 = 1000;
+const LAMBDA: u64 = 64;
+
+/// Critical value of the chi-squared distribution with 255 degrees of freedom at p = 0.001.
+const CHI_SQUARED_255_CRITICAL: f64 = 330.52;
+
+#[test]
+fn randomness_low_byte_is_uniform() {
+    let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+    let mut rng = rand::thread_rng();
+    let samples = 256 * 100;
+    let mut counts = [0u64; 256];
+    for _ in 0..samples {
+        let r = lhtlp.sample_randomness(&mut rng);
+        counts[(r.to_bytes_le()[0]) as usize] += 1;
+    }
+
+    let expected = samples as f64 / 256.0;
+    let chi_squared: f64 = counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum();
+    assert!(chi_squared < CHI_SQUARED_255_CRITICAL, "chi-squared statistic {} too large", chi_squared);
+}
+
+#[test]
+fn same_secret_gives_distinct_puzzles() {
+    let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+    let mut us: Vec<BigUint> = (0..1000).map(|_| lhtlp.generate(42).u).collect();
+    us.sort();
+    us.dedup();
+    assert!(us.len() == 1000);
+}
+
+#[test]
+fn u_passes_monobit() {
+    let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+    // The top bits of a value below `n` are biased towards zero, so only the low bits are counted.
+    let bits = lhtlp.n.bits() - 8;
+    let samples = 2000;
+    let ones: u64 = (0..samples)
+        .map(|_| {
+            let u = lhtlp.generate(42).u;
+            (0..bits).filter(|&i| u.bit(i)).count() as u64
+        })
+        .sum();
+
+    let total = (samples * bits) as f64;
+    // Reject beyond 3.3 standard deviations of a fair coin, that is p < 0.001.
+    let deviation = (ones as f64 - total / 2.0).abs() / (total / 4.0).sqrt();
+    assert!(deviation < 3.3, "monobit deviation of {} standard deviations", deviation);
+}
@@ -0,0 +1,224 @@
+//! File-backed store of puzzles, solved lazily by index.
+//!
+//! # File format
+//!
+//! The file starts with a header:
+//!
+//! - the 8-byte magic `LHTLPPZS`,
+//! - a 1-byte format version, currently `1`,
+//! - the 1-byte identifier of the hash suite of the fingerprint (`HashSuite::id`),
+//! - the parameter fingerprint (`LHTLP::fingerprint`) of the instance the puzzles belong to,
+//!   whose length is the digest size of the suite (`HashSuite::output_len`), 32 bytes for SHA-256.
+//!
+//! It is followed by one record per puzzle, in insertion order: a 4-byte big-endian length and
+//! the canonical encoding of the puzzle (`Puzzle::to_bytes`) of that length.
+//!
+//! Any change to the header or the record layout bumps the version byte. Files with an unknown
+//! version are rejected rather than misread.
+use crate::{HashSuite, LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+const MAGIC: &[u8; 8] = b"LHTLPPZS";
+const VERSION: u8 = 1;
+
+/// An append-only file of puzzles generated by one instance.
+///
+/// Only the offset of each record is kept in memory, so millions of puzzles can be stored while
+/// the working set stays small; a puzzle is read back from disk when it is solved. Reads seek the
+/// shared file handle, so they hold a lock for the seek and the read.
+#[derive(Debug)]
+pub struct PuzzleStore {
+    lhtlp: LHTLP,
+    file: Mutex<File>,
+    offsets: Vec<u64>,
+    end: u64,
+}
+
+impl PuzzleStore {
+    /// Open the store at `path` for puzzles of `lhtlp`, creating it if the file does not exist.
+    ///
+    /// Fails with `LhtlpError::ParameterMismatch` if the store was written for another instance,
+    /// with `LhtlpError::UnsupportedHashSuite` if it was fingerprinted under another hash suite,
+    /// and with `LhtlpError::InvalidEncoding` if the file is corrupt or has an unknown version.
+    pub fn open(path: impl AsRef<Path>, lhtlp: LHTLP) -> Result<PuzzleStore, LhtlpError> {
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let end = file.metadata()?.len();
+        if end == 0 {
+            let mut header = MAGIC.to_vec();
+            header.push(VERSION);
+            header.push(lhtlp.hash_suite().id());
+            header.extend_from_slice(&lhtlp.fingerprint());
+            file.write_all(&header)?;
+            return Ok(PuzzleStore { lhtlp, file: Mutex::new(file), offsets: Vec::new(), end: header.len() as u64 });
+        }
+
+        let mut header = [0u8; 10];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header).map_err(|_| LhtlpError::InvalidEncoding)?;
+        if &header[..8] != MAGIC || header[8] != VERSION {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        lhtlp.hash_suite().check(HashSuite::from_id(header[9])?)?;
+        let mut fingerprint = vec![0u8; lhtlp.hash_suite().output_len()];
+        file.read_exact(&mut fingerprint).map_err(|_| LhtlpError::InvalidEncoding)?;
+        if fingerprint != lhtlp.fingerprint() {
+            return Err(LhtlpError::ParameterMismatch);
+        }
+
+        let mut offsets = Vec::new();
+        let mut offset = file.stream_position()?;
+        while offset < end {
+            let mut len = [0u8; 4];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut len).map_err(|_| LhtlpError::InvalidEncoding)?;
+            offsets.push(offset);
+            offset += 4 + u32::from_be_bytes(len) as u64;
+        }
+        if offset != end {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        Ok(PuzzleStore { lhtlp, file: Mutex::new(file), offsets, end })
+    }
+
+    /// Append `puzzle` to the store, returning its index.
+    pub fn append(&mut self, puzzle: &Puzzle) -> Result<u64, LhtlpError> {
+        let bytes = puzzle.to_bytes();
+        let mut record = Vec::with_capacity(4 + self.lhtlp.puzzle_size_bytes());
+        record.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        record.extend_from_slice(&bytes);
+        self.file.get_mut().unwrap_or_else(PoisonError::into_inner).write_all(&record)?;
+        self.offsets.push(self.end);
+        self.end += record.len() as u64;
+        Ok(self.offsets.len() as u64 - 1)
+    }
+
+    /// Read the puzzle stored at `index`.
+    ///
+    /// Fails with `LhtlpError::InvalidEncoding` if the length of the record no longer spans the
+    /// bytes up to the next record, as when the file was modified since it was opened.
+    pub fn get(&self, index: u64) -> Result<Puzzle, LhtlpError> {
+        let offset = *self
+            .offsets
+            .get(index as usize)
+            .ok_or(LhtlpError::IndexOutOfRange { index, len: self.len() })?;
+        let next = self.offsets.get(index as usize + 1).copied().unwrap_or(self.end);
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut len = [0u8; 4];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut len)?;
+        let len = u64::from(u32::from_be_bytes(len));
+        if offset + 4 + len != next {
+            return Err(LhtlpError::InvalidEncoding);
+        }
+        let mut bytes = vec![0u8; len as usize];
+        file.read_exact(&mut bytes)?;
+        Puzzle::from_bytes(&bytes)
+    }
+
+    /// Read the puzzle stored at `index` and solve it like `solve_checked`.
+    ///
+    /// A record that decodes to a malformed puzzle fails with `LhtlpError::InvalidPuzzle`.
+    pub fn solve_at(&self, index: u64) -> Result<BigUint, LhtlpError> {
+        self.lhtlp.solve_checked(self.get(index)?)
+    }
+
+    /// The number of puzzles in the store.
+    pub fn len(&self) -> u64 {
+        self.offsets.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn append_and_solve_lazily() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let path = std::env::temp_dir().join(format!("lhtlp-store-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = PuzzleStore::open(&path, lhtlp.clone()).unwrap();
+        for secret in 0..10 {
+            assert!(store.append(&lhtlp.generate(secret)) == Ok(secret));
+        }
+        let malformed = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(store.append(&malformed) == Ok(10));
+        drop(store);
+
+        let store = PuzzleStore::open(&path, lhtlp.clone()).unwrap();
+        assert!(store.len() == 11);
+        assert!(store.solve_at(7) == Ok(BigUint::from(7u32)));
+        assert!(store.solve_at(0) == Ok(BigUint::from(0u32)));
+        assert!(store.solve_at(10) == Err(LhtlpError::InvalidPuzzle));
+        assert!(store.solve_at(11) == Err(LhtlpError::IndexOutOfRange { index: 11, len: 11 }));
+
+        let other = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        assert!(matches!(PuzzleStore::open(&path, other), Err(LhtlpError::ParameterMismatch)));
+        let sha384 = lhtlp.with_hash_suite(HashSuite::Sha384);
+        assert!(matches!(PuzzleStore::open(&path, sha384), Err(LhtlpError::UnsupportedHashSuite)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn hash_suites() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY)).with_hash_suite(HashSuite::Sha384);
+        let path = std::env::temp_dir().join(format!("lhtlp-store-sha384-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = PuzzleStore::open(&path, lhtlp.clone()).unwrap();
+        store.append(&lhtlp.generate(42)).unwrap();
+        drop(store);
+
+        let store = PuzzleStore::open(&path, lhtlp.clone()).unwrap();
+        assert!(store.solve_at(0) == Ok(BigUint::from(42u32)));
+        let sha256 = lhtlp.clone().with_hash_suite(HashSuite::Sha256);
+        assert!(matches!(PuzzleStore::open(&path, sha256), Err(LhtlpError::UnsupportedHashSuite)));
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8] = VERSION + 1;
+        std::fs::write(&path, bytes).unwrap();
+        assert!(matches!(PuzzleStore::open(&path, lhtlp), Err(LhtlpError::InvalidEncoding)));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn shared_reads_and_corrupt_lengths() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let path = std::env::temp_dir().join(format!("lhtlp-store-shared-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = PuzzleStore::open(&path, lhtlp.clone()).unwrap();
+        let puzzles: Vec<Puzzle> = (0..8).map(|secret| lhtlp.generate(secret)).collect();
+        for puzzle in &puzzles {
+            store.append(puzzle).unwrap();
+        }
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for (index, puzzle) in puzzles.iter().enumerate() {
+                        assert!(store.get(index as u64).as_ref() == Ok(puzzle));
+                    }
+                });
+            }
+        });
+
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(store.offsets[3])).unwrap();
+        file.write_all(&u32::MAX.to_be_bytes()).unwrap();
+        assert!(store.get(3) == Err(LhtlpError::InvalidEncoding));
+        assert!(store.get(2) == Ok(puzzles[2].clone()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+//! Encoding of parameters and puzzles to and from `std::io` streams.
+//!
+//! Values use the encoding of `Puzzle::to_bytes`: a 4-byte big-endian length followed by the
+//! big-endian magnitude. Parameters are written as `n`, `g`, `h`, then the difficulty.
+use crate::{write_value, LhtlpError, PublicParams, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use std::io::{ErrorKind, Read, Write};
+
+/// Read a length-prefixed value, growing the buffer only as bytes arrive so that a forged length
+/// cannot force a large allocation. A stream ending early fails with `LhtlpError::InvalidEncoding`.
+fn read_value(reader: &mut impl Read) -> Result<BigUint, LhtlpError> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).map_err(|err| match err.kind() {
+        ErrorKind::UnexpectedEof => LhtlpError::InvalidEncoding,
+        kind => LhtlpError::Io(kind),
+    })?;
+    let len = u32::from_be_bytes(len) as u64;
+    let mut magnitude = Vec::new();
+    reader.take(len).read_to_end(&mut magnitude)?;
+    if magnitude.len() as u64 != len {
+        return Err(LhtlpError::InvalidEncoding);
+    }
+    Ok(BigUint::from_bytes_be(&magnitude))
+}
+
+impl Puzzle {
+    /// Write the canonical encoding of the puzzle (`Puzzle::to_bytes`) to `writer`.
+    pub fn to_writer(&self, mut writer: impl Write) -> Result<(), LhtlpError> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Read one puzzle in its canonical encoding from `reader`, consuming exactly its bytes.
+    pub fn from_reader(mut reader: impl Read) -> Result<Puzzle, LhtlpError> {
+        let u = read_value(&mut reader)?;
+        let v = read_value(&mut reader)?;
+        Ok(Puzzle { u, v })
+    }
+}
+
+impl LHTLP {
+    /// Write the public parameters and the difficulty of the instance to `writer`.
+    ///
+    /// The solve policy and the hash suite are local settings and are not written.
+    pub fn params_to_writer(&self, mut writer: impl Write) -> Result<(), LhtlpError> {
+        let mut bytes = Vec::new();
+        for value in [&self.n, &self.g, &self.h, &self.difficulty] {
+            write_value(&mut bytes, value);
+        }
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Read an instance written by `params_to_writer` from `reader`.
+    ///
+    /// The parameters are checked like in `from_public_params`.
+    pub fn params_from_reader(mut reader: impl Read) -> Result<LHTLP, LhtlpError> {
+        let n = read_value(&mut reader)?;
+        let g = read_value(&mut reader)?;
+        let h = read_value(&mut reader)?;
+        let difficulty = read_value(&mut reader)?;
+        LHTLP::from_public_params(PublicParams { n, g, h }, difficulty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    /// A reader returning at most one byte per call.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn puzzle_stream_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzles = [lhtlp.generate(1), lhtlp.generate(2)];
+        let mut bytes = Vec::new();
+        for puzzle in &puzzles {
+            puzzle.to_writer(&mut bytes).unwrap();
+        }
+        assert!(bytes[..puzzles[0].to_bytes().len()] == puzzles[0].to_bytes());
+
+        let mut reader = Trickle(&bytes);
+        assert!(Puzzle::from_reader(&mut reader) == Ok(puzzles[0].clone()));
+        assert!(Puzzle::from_reader(&mut reader) == Ok(puzzles[1].clone()));
+        assert!(Puzzle::from_reader(&mut reader) == Err(LhtlpError::InvalidEncoding));
+        assert!(Puzzle::from_reader(&bytes[..bytes.len() / 2 - 1]) == Err(LhtlpError::InvalidEncoding));
+        assert!(Puzzle::from_reader(&[0xffu8, 0xff, 0xff, 0xff, 1][..]) == Err(LhtlpError::InvalidEncoding));
+    }
+
+    #[test]
+    fn params_stream_roundtrip() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut bytes = Vec::new();
+        lhtlp.params_to_writer(&mut bytes).unwrap();
+        assert!(LHTLP::params_from_reader(Trickle(&bytes)) == Ok(lhtlp));
+        assert!(LHTLP::params_from_reader(&bytes[..bytes.len() - 1]) == Err(LhtlpError::InvalidEncoding));
+    }
+}
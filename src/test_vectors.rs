@@ -0,0 +1,224 @@
+//! Canonical test vectors for other LHTLP implementations.
+//!
+//! The vectors in `test-vectors/lhtlp-v1.json` fix the primes, the seed of the generator, the
+//! difficulty and, for each secret, the randomness `r`, the puzzle `(u, v)`, the value
+//! `w = u^(2^T) mod n` and the solution. Big integers are lowercase hexadecimal strings. The
+//! seeded generator is `rand::rngs::StdRng` from rand 0.8; other implementations should take `g`
+//! and `r` from the file rather than reproduce the generator.
+//!
+//! `committed_vectors_match` fails whenever the math changes without the vectors being updated.
+//! Regenerate them with `cargo test --features gen-vectors -- --ignored generate_vectors`, bumping
+//! `VERSION` if the format changes.
+//!
+//! `test-vectors/lhtlp-interop-v1.json` follows the proposed interoperability format instead: an
+//! array of vectors `{lambda, difficulty, n, g, h, test_cases}`, each set up from a fixed seed
+//! with `setup_with_transcript` and holding `INTEROP_CASES` cases `{secret, r, u, v, solution}`.
+//! Implementations in other languages can check themselves against it the way
+//! `verify_test_vectors` does.
+use crate::{Difficulty, PrivateKey, Puzzle, PublicParams, LHTLP};
+use num_bigint::{BigUint, RandBigInt};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{json, Value};
+
+const VERSION: u64 = 1;
+const P: &str = "a82ee0bc09437bcb";
+const Q: &str = "981437229b84654f";
+const DIFFICULTY: u64 = 16;
+const SEED: u64 = 42;
+const SECRETS: [u64; 5] = [0, 1, 42, 1000, u64::MAX];
+const COMMITTED: &str = include_str!("../test-vectors/lhtlp-v1.json");
+const INTEROP_SEEDS: [(u8, u64); 2] = [(1, 32), (2, 64)];
+const INTEROP_DIFFICULTY: u64 = 1000;
+const INTEROP_CASES: usize = 10;
+const INTEROP: &str = include_str!("../test-vectors/lhtlp-interop-v1.json");
+
+/// The instance over the fixed primes `P` and `Q`, with its trapdoor, for tests that need a known
+/// factorization.
+pub(crate) fn fixed_instance(difficulty: impl Into<Difficulty>) -> (LHTLP, PrivateKey) {
+    let p = BigUint::parse_bytes(P.as_bytes(), 16).unwrap();
+    let q = BigUint::parse_bytes(Q.as_bytes(), 16).unwrap();
+    (LHTLP::from_primes(&p, &q, difficulty.into().into()), PrivateKey::new(p, q))
+}
+
+fn hex(value: &BigUint) -> Value {
+    Value::String(value.to_str_radix(16))
+}
+
+fn compute_vectors() -> Value {
+    let p = BigUint::parse_bytes(P.as_bytes(), 16).unwrap();
+    let q = BigUint::parse_bytes(Q.as_bytes(), 16).unwrap();
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let lhtlp = LHTLP::from_primes_with_rng(&p, &q, BigUint::from(DIFFICULTY), &mut rng);
+
+    let puzzles: Vec<Value> = SECRETS
+        .iter()
+        .map(|&secret| {
+            let r = lhtlp.sample_randomness(&mut rng);
+            let puzzle = lhtlp.generate_with_randomness(&BigUint::from(secret), &r);
+            let w = lhtlp.square(&puzzle.u, &lhtlp.difficulty);
+            let solution = lhtlp.decode(&puzzle.v, &w);
+            json!({
+                "secret": hex(&BigUint::from(secret)),
+                "r": hex(&r),
+                "u": hex(&puzzle.u),
+                "v": hex(&puzzle.v),
+                "w": hex(&w),
+                "solution": hex(&solution),
+            })
+        })
+        .collect();
+
+    json!({
+        "version": VERSION,
+        "p": P,
+        "q": Q,
+        "seed": SEED,
+        "difficulty": DIFFICULTY,
+        "n": hex(&lhtlp.n),
+        "g": hex(&lhtlp.g),
+        "h": hex(&lhtlp.h),
+        "puzzles": puzzles,
+    })
+}
+
+#[test]
+fn committed_vectors_match() {
+    let committed: Value = serde_json::from_str(COMMITTED).unwrap();
+    let fresh = compute_vectors();
+    for field in ["version", "p", "q", "seed", "difficulty", "n", "g", "h"] {
+        assert!(committed[field] == fresh[field], "test vector field `{}` changed", field);
+    }
+
+    let committed = committed["puzzles"].as_array().unwrap();
+    let fresh = fresh["puzzles"].as_array().unwrap();
+    assert!(committed.len() == fresh.len());
+    for (i, (committed, fresh)) in committed.iter().zip(fresh).enumerate() {
+        for field in ["secret", "r", "u", "v", "w", "solution"] {
+            assert!(committed[field] == fresh[field], "test vector field `{}` of puzzle {} changed", field, i);
+        }
+        assert!(committed["solution"] == committed["secret"]);
+    }
+}
+
+#[cfg(feature = "gen-vectors")]
+#[test]
+#[ignore]
+fn generate_vectors() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test-vectors/lhtlp-v1.json");
+    let json = serde_json::to_string_pretty(&compute_vectors()).unwrap();
+    std::fs::write(path, json + "\n").unwrap();
+}
+
+/// The interoperability vector of the instance set up from `[seed; 32]` with `lambda`.
+///
+/// The secrets are 0, 1, `n - 1` and random values below `n`, drawn with the randomness `r` from
+/// `StdRng` seeded with the same seed.
+fn interop_vector(seed: u8, lambda: u64) -> Value {
+    let (lhtlp, _) = LHTLP::setup_with_transcript([seed; 32], lambda, INTEROP_DIFFICULTY);
+    let mut rng = StdRng::from_seed([seed; 32]);
+    let mut secrets = vec![BigUint::from(0u32), BigUint::from(1u32), &lhtlp.n - 1u32];
+    while secrets.len() < INTEROP_CASES {
+        secrets.push(rng.gen_biguint_below(&lhtlp.n));
+    }
+
+    let test_cases: Vec<Value> = secrets
+        .iter()
+        .map(|secret| {
+            let r = lhtlp.sample_randomness(&mut rng);
+            let puzzle = lhtlp.generate_with_randomness(secret, &r);
+            let solution = lhtlp.solve(puzzle.clone());
+            json!({
+                "secret": hex(secret),
+                "r": hex(&r),
+                "u": hex(&puzzle.u),
+                "v": hex(&puzzle.v),
+                "solution": hex(&solution),
+            })
+        })
+        .collect();
+
+    json!({
+        "lambda": lambda,
+        "difficulty": INTEROP_DIFFICULTY,
+        "n": hex(&lhtlp.n),
+        "g": hex(&lhtlp.g),
+        "h": hex(&lhtlp.h),
+        "test_cases": test_cases,
+    })
+}
+
+fn interop_vectors() -> Value {
+    Value::Array(INTEROP_SEEDS.iter().map(|&(seed, lambda)| interop_vector(seed, lambda)).collect())
+}
+
+fn parse_hex(value: &Value) -> Option<BigUint> {
+    BigUint::parse_bytes(value.as_str()?.as_bytes(), 16)
+}
+
+/// Check every case of an array of interoperability vectors.
+///
+/// The parameters must pass `from_public_params` and `verify_setup_consistency`, each puzzle must
+/// be the one generated from its secret and `r`, and solving it must give back the secret.
+fn verify_test_vectors(json: &str) -> bool {
+    let verify_vector = |vector: &Value| -> Option<bool> {
+        let params = PublicParams { n: parse_hex(&vector["n"])?, g: parse_hex(&vector["g"])?, h: parse_hex(&vector["h"])? };
+        let lhtlp = LHTLP::from_public_params(params, vector["difficulty"].as_u64()?).ok()?;
+        if lhtlp.verify_setup_consistency().is_err() || lhtlp.n.bits() > 2 * vector["lambda"].as_u64()? {
+            return Some(false);
+        }
+        let cases = vector["test_cases"].as_array()?;
+        for case in cases {
+            let [secret, r, u, v, solution] = ["secret", "r", "u", "v", "solution"].map(|field| parse_hex(&case[field]));
+            let (secret, r, solution) = (secret?, r?, solution?);
+            let puzzle = Puzzle { u: u?, v: v? };
+            if lhtlp.verify_puzzle(&puzzle).is_err() || lhtlp.generate_with_randomness(&secret, &r) != puzzle {
+                return Some(false);
+            }
+            if solution != secret || lhtlp.solve_checked(puzzle) != Ok(solution) {
+                return Some(false);
+            }
+        }
+        Some(!cases.is_empty())
+    };
+    match serde_json::from_str::<Value>(json) {
+        Ok(Value::Array(vectors)) => !vectors.is_empty() && vectors.iter().all(|vector| verify_vector(vector) == Some(true)),
+        _ => false,
+    }
+}
+
+#[test]
+fn interop_vectors_verify() {
+    assert!(verify_test_vectors(INTEROP));
+    let committed: Value = serde_json::from_str(INTEROP).unwrap();
+    assert!(committed == interop_vectors(), "interop test vectors changed");
+    for vector in committed.as_array().unwrap() {
+        assert!(vector["test_cases"].as_array().unwrap().len() == INTEROP_CASES);
+    }
+}
+
+#[test]
+fn tampered_interop_vectors_fail() {
+    let committed: Value = serde_json::from_str(INTEROP).unwrap();
+    let tamper = |edit: &dyn Fn(&mut Value)| {
+        let mut vectors = committed.clone();
+        edit(&mut vectors);
+        verify_test_vectors(&vectors.to_string())
+    };
+    assert!(!tamper(&|vectors| vectors[0]["test_cases"][3]["solution"] = hex(&BigUint::from(7u32))));
+    assert!(!tamper(&|vectors| vectors[0]["test_cases"][3]["r"] = hex(&BigUint::from(7u32))));
+    assert!(!tamper(&|vectors| vectors[1]["h"] = vectors[1]["g"].clone()));
+    assert!(!tamper(&|vectors| vectors[1]["difficulty"] = json!(999)));
+    assert!(!tamper(&|vectors| vectors[0]["test_cases"][0]["u"] = json!("not hex")));
+    assert!(!verify_test_vectors("[]"));
+    assert!(!verify_test_vectors("{}"));
+}
+
+#[cfg(feature = "gen-vectors")]
+#[test]
+#[ignore]
+fn generate_interop_vectors() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test-vectors/lhtlp-interop-v1.json");
+    let json = serde_json::to_string_pretty(&interop_vectors()).unwrap();
+    std::fs::write(path, json + "\n").unwrap();
+}
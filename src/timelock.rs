@@ -0,0 +1,112 @@
+use crate::{LhtlpError, PublicParams, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// The core operations of a linearly homomorphic timelock puzzle, for code generic over the backend.
+///
+/// The trait is implemented by `LHTLP`, whose inherent methods stay the primary API. Other
+/// implementations, such as an instant mock in tests, can stand in for it where only these
+/// operations are needed.
+///
+/// ```rust
+/// # use lhtlp::{TimelockPuzzle, LHTLP};
+/// # use num_bigint::BigUint;
+/// fn sum<T: TimelockPuzzle>(timelock: &T, secrets: &[u64]) -> Result<BigUint, T::Error> {
+///     let puzzles = secrets.iter().map(|&s| timelock.generate(s)).collect::<Result<_, _>>()?;
+///     timelock.solve(timelock.evaluate(puzzles)?)
+/// }
+///
+/// let lhtlp = LHTLP::setup(64, 1000u64);
+/// assert!(sum(&lhtlp, &[1, 2, 3]) == Ok(BigUint::from(6u32)));
+/// ```
+pub trait TimelockPuzzle {
+    type Puzzle;
+    type Params;
+    type Error;
+
+    /// The public parameters of the instance.
+    fn params(&self) -> Self::Params;
+
+    /// Generate a puzzle embedding `secret`.
+    fn generate(&self, secret: u64) -> Result<Self::Puzzle, Self::Error>;
+
+    /// Solve `puzzle`, recovering the embedded secret.
+    fn solve(&self, puzzle: Self::Puzzle) -> Result<BigUint, Self::Error>;
+
+    /// Homomorphically add `puzzles` into one embedding the sum of their secrets.
+    fn evaluate(&self, puzzles: Vec<Self::Puzzle>) -> Result<Self::Puzzle, Self::Error>;
+}
+
+impl TimelockPuzzle for LHTLP {
+    type Puzzle = Puzzle;
+    type Params = PublicParams;
+    type Error = LhtlpError;
+
+    fn params(&self) -> PublicParams {
+        self.public_params()
+    }
+
+    fn generate(&self, secret: u64) -> Result<Puzzle, LhtlpError> {
+        Ok(LHTLP::generate(self, secret))
+    }
+
+    /// Solves with `solve_checked`, so malformed puzzles and the solve policy are reported as errors.
+    fn solve(&self, puzzle: Puzzle) -> Result<BigUint, LhtlpError> {
+        self.solve_checked(puzzle)
+    }
+
+    fn evaluate(&self, puzzles: Vec<Puzzle>) -> Result<Puzzle, LhtlpError> {
+        Ok(LHTLP::evaluate(self, puzzles))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    /// Instant implementation keeping secrets in the clear.
+    struct Plain;
+
+    impl TimelockPuzzle for Plain {
+        type Puzzle = BigUint;
+        type Params = ();
+        type Error = LhtlpError;
+
+        fn params(&self) {}
+
+        fn generate(&self, secret: u64) -> Result<BigUint, LhtlpError> {
+            Ok(BigUint::from(secret))
+        }
+
+        fn solve(&self, puzzle: BigUint) -> Result<BigUint, LhtlpError> {
+            Ok(puzzle)
+        }
+
+        fn evaluate(&self, puzzles: Vec<BigUint>) -> Result<BigUint, LhtlpError> {
+            Ok(puzzles.into_iter().sum())
+        }
+    }
+
+    fn tally<T: TimelockPuzzle>(timelock: &T, votes: &[u64]) -> Result<BigUint, T::Error> {
+        let puzzles = votes.iter().map(|&vote| timelock.generate(vote)).collect::<Result<_, _>>()?;
+        timelock.solve(timelock.evaluate(puzzles)?)
+    }
+
+    #[test]
+    fn generic_over_backends() {
+        let votes = [1, 0, 1, 1];
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        assert!(tally(&lhtlp, &votes) == Ok(BigUint::from(3u32)));
+        assert!(tally(&Plain, &votes) == Ok(BigUint::from(3u32)));
+        assert!(TimelockPuzzle::params(&lhtlp) == lhtlp.public_params());
+    }
+
+    #[test]
+    fn solve_rejects_malformed_puzzles() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = Puzzle { u: BigUint::from(0u32), v: BigUint::from(1u32) };
+        assert!(TimelockPuzzle::solve(&lhtlp, puzzle) == Err(LhtlpError::InvalidPuzzle));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::num_primes::Verification;
+use crate::vdf::expand;
+use crate::{Difficulty, HashSuite, LHTLP};
+use num_bigint::BigUint;
+
+/// Everything needed to replay a reproducible setup: the seed and the candidates it went through.
+///
+/// Replaying the transcript recomputes the primes, so publishing it reveals the trapdoor. It is
+/// meant for ceremonies where the factorization is disclosed for audit once the puzzles no longer
+/// need protecting, or is kept by auditors under the same trust as the setup party.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetupTranscript {
+    pub seed: [u8; 32],
+    pub lambda: u64,
+    pub difficulty: BigUint,
+    /// Index of the accepted candidate for `p`; all candidates before it were rejected.
+    pub p_candidate: u64,
+    /// Index of the accepted candidate for `q`.
+    pub q_candidate: u64,
+    /// Index of the accepted base of the generator `g`.
+    pub g_candidate: u64,
+    /// The hash suite deriving the candidates, kept by the instance.
+    pub hash_suite: HashSuite,
+}
+
+/// The `index`-th candidate for a safe prime of `lambda` bits, derived from `seed` and `label`.
+///
+/// The top bit is set to fix the size and the two low bits so that `(p - 1) / 2` is odd.
+fn prime_candidate(hash_suite: HashSuite, seed: &[u8; 32], label: &[u8], lambda: u64, index: u64) -> BigUint {
+    let mut candidate = expand(hash_suite, &[b"lhtlp-setup-prime", seed, label, &index.to_be_bytes()], lambda as usize)
+        % (BigUint::from(1u32) << lambda);
+    candidate.set_bit(lambda - 1, true);
+    candidate.set_bit(1, true);
+    candidate.set_bit(0, true);
+    candidate
+}
+
+/// First safe prime in the candidate sequence of `label` that differs from `other`, with its index.
+fn find_safe_prime(hash_suite: HashSuite, seed: &[u8; 32], label: &[u8], lambda: u64, other: Option<&BigUint>) -> (BigUint, u64) {
+    (0..)
+        .map(|index| (prime_candidate(hash_suite, seed, label, lambda, index), index))
+        .find(|(candidate, _)| {
+            Some(candidate) != other && Verification::is_prime(&(candidate >> 1)) && Verification::is_prime(candidate)
+        })
+        .unwrap()
+}
+
+/// First generator base derived from `seed` whose square is invertible modulo `n`, with its index.
+fn find_generator(hash_suite: HashSuite, seed: &[u8; 32], n: &BigUint) -> (BigUint, u64) {
+    (0u64..)
+        .find_map(|index| {
+            let base = expand(hash_suite, &[b"lhtlp-setup-generator", seed, &index.to_be_bytes()], 2 * n.bits() as usize) % n;
+            (&base * &base % n).modinv(n).filter(|g| g > &BigUint::from(1u32)).map(|g| (g, index))
+        })
+        .unwrap()
+}
+
+impl LHTLP {
+    /// Setup an instance deterministically from `seed`, recording a transcript to replay it.
+    ///
+    /// The safe primes and the generator are found by testing hash-derived candidates in order,
+    /// so the parameters carry no structure beyond what the seed determines. This is slower than
+    /// `setup` for large `lambda`.
+    pub fn setup_with_transcript(seed: [u8; 32], lambda: u64, difficulty: impl Into<Difficulty>) -> (LHTLP, SetupTranscript) {
+        LHTLP::setup_with_transcript_and_hash_suite(seed, lambda, difficulty, HashSuite::default())
+    }
+
+    /// Like `setup_with_transcript`, deriving the candidates under `hash_suite`, which the
+    /// instance keeps and the transcript records.
+    pub fn setup_with_transcript_and_hash_suite(
+        seed: [u8; 32],
+        lambda: u64,
+        difficulty: impl Into<Difficulty>,
+        hash_suite: HashSuite,
+    ) -> (LHTLP, SetupTranscript) {
+        let difficulty: BigUint = difficulty.into().into();
+        let (p, p_candidate) = find_safe_prime(hash_suite, &seed, b"p", lambda, None);
+        let (q, q_candidate) = find_safe_prime(hash_suite, &seed, b"q", lambda, Some(&p));
+        let n = &p * &q;
+        let (g, g_candidate) = find_generator(hash_suite, &seed, &n);
+
+        let phi_half = (&p - 1u32) * (&q - 1u32) / 2u32;
+        let h = g.modpow(&BigUint::from(2u32).modpow(&difficulty, &phi_half), &n);
+        let lhtlp = LHTLP { difficulty: difficulty.clone(), n2: &n * &n, n, g, h, policy: Default::default(), hash_suite };
+        let transcript = SetupTranscript { seed, lambda, difficulty, p_candidate, q_candidate, g_candidate, hash_suite };
+        (lhtlp, transcript)
+    }
+}
+
+/// Replay `transcript` and check that it produces exactly the public parameters of `params`.
+pub fn verify_transcript(params: &LHTLP, transcript: &SetupTranscript) -> bool {
+    let (replayed, replayed_transcript) =
+        LHTLP::setup_with_transcript_and_hash_suite(transcript.seed, transcript.lambda, transcript.difficulty.clone(), transcript.hash_suite);
+    &replayed == params && &replayed_transcript == transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn replay_transcript() {
+        let (lhtlp, transcript) = LHTLP::setup_with_transcript([7u8; 32], LAMBDA, DIFFICULTY);
+        assert!(verify_transcript(&lhtlp, &transcript));
+        assert!(lhtlp.n.bits() == 2 * LAMBDA);
+        assert!(lhtlp.solve(lhtlp.generate(42)) == BigUint::from(42u32));
+
+        let mut tampered = transcript.clone();
+        tampered.q_candidate += 1;
+        assert!(!verify_transcript(&lhtlp, &tampered));
+
+        let (other, _) = LHTLP::setup_with_transcript([8u8; 32], LAMBDA, DIFFICULTY);
+        assert!(!verify_transcript(&other, &transcript));
+
+        let (sha384, sha384_transcript) = LHTLP::setup_with_transcript_and_hash_suite([7u8; 32], LAMBDA, DIFFICULTY, HashSuite::Sha384);
+        assert!(verify_transcript(&sha384, &sha384_transcript));
+        assert!(sha384.hash_suite() == HashSuite::Sha384 && !sha384.same_modulus(&lhtlp));
+        assert!(!verify_transcript(&lhtlp, &sha384_transcript));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{math, LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+
+/// The trapdoor of an LHTLP instance: the factorization `n = p * q` of its modulus.
+///
+/// Only the trusted setup party should hold it, as it allows opening puzzles without doing the
+/// sequential squarings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateKey {
+    p: BigUint,
+    q: BigUint,
+}
+
+impl PrivateKey {
+    pub fn new(p: BigUint, q: BigUint) -> PrivateKey {
+        PrivateKey { p, q }
+    }
+
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    /// The modulus `n = p * q`.
+    pub fn modulus(&self) -> BigUint {
+        &self.p * &self.q
+    }
+
+    /// `phi(n) / 2`, a multiple of the order of every element of `Z_n^*`.
+    pub fn phi_half(&self) -> BigUint {
+        (&self.p - 1u32) * (&self.q - 1u32) / 2u32
+    }
+
+    /// The exact order `phi(n) = (p-1)*(q-1)` of `Z_n^*` for the modulus of `lhtlp`.
+    ///
+    /// Panics if `key` is not the factorization of the modulus of `lhtlp`.
+    pub fn exact_group_order(&self, lhtlp: &LHTLP) -> BigUint {
+        assert!(self.modulus() == lhtlp.n, "key does not factor the modulus of the instance");
+        (&self.p - 1u32) * (&self.q - 1u32)
+    }
+}
+
+impl LHTLP {
+    /// An approximation of the group order `phi(n)`, computed from `n` alone.
+    ///
+    /// This is `n - 2*sqrt(n)`: since `phi(n) = n - (p+q) + 1` and `p+q >= 2*sqrt(n)`, it is at
+    /// least `phi(n) - 1`, and it is close to `phi(n)` when `p` and `q` have similar sizes, as
+    /// produced by `setup`. It is only an approximation, for probability estimates; the exact value
+    /// would reveal the factorization and is available from `PrivateKey::exact_group_order`.
+    pub fn approx_group_order(&self) -> BigUint {
+        &self.n - 2u32 * self.n.sqrt()
+    }
+
+    /// Estimate in bits of the min-entropy of a puzzle of a fixed secret, over its randomness.
+    ///
+    /// A puzzle is determined by the secret and by `u = g^r mod n`. The randomness `r` is drawn
+    /// from `[1, n^2)`, about `log2(n^2)` bits, but only its class modulo the order of `g`
+    /// matters, and `g` generates the quadratic residues `QR_n` of order `phi(n) / 4`. The
+    /// estimate is `min(log2(phi(n) / 4), log2(n^2))`, with `phi(n)` taken from
+    /// `approx_group_order`. It assumes the hash functions behave as random oracles and that no
+    /// partial information about `phi(n)` has leaked; it says nothing about the time the
+    /// secret stays hidden, which the difficulty governs.
+    pub fn puzzle_entropy_bits(&self) -> f64 {
+        let group = math::log2(&(self.approx_group_order() / 4u32));
+        group.min(math::log2(&self.n2))
+    }
+
+    /// Open a puzzle with the trapdoor, reducing the `2^T` exponent modulo `phi(n) / 2` instead of
+    /// performing the sequential squarings.
+    ///
+    /// Fails with `LhtlpError::ParameterMismatch` if `key` is not the factorization of `n`.
+    pub fn solve_with_trapdoor(&self, puzzle: Puzzle, key: &PrivateKey) -> Result<BigUint, LhtlpError> {
+        let exponent = self.precompute_solve_exponent(key).ok_or(LhtlpError::ParameterMismatch)?;
+        self.solve_fast(puzzle, &exponent)
+    }
+
+    /// The secret of `evaluate(puzzles)`, opened with the trapdoor instead of solved.
+    ///
+    /// A diagnostic cross-checking the homomorphic evaluation: the result equals
+    /// `solve(evaluate(puzzles))` at the cost of a single exponentiation.
+    pub fn aggregate_plaintext_with_trapdoor(&self, puzzles: &[Puzzle], key: &PrivateKey) -> Result<BigUint, LhtlpError> {
+        self.solve_with_trapdoor(self.evaluate(puzzles.to_vec()), key)
+    }
+
+    /// The exponent `2^T mod phi(n) / 2`, which raises any `u` to `u^(2^T)` in a single modpow.
+    ///
+    /// Computing it requires the trapdoor; it is computed once and then reused with `solve_fast`
+    /// across all the puzzles of the instance. Returns `None` if `key` is not the factorization of `n`.
+    pub fn precompute_solve_exponent(&self, key: &PrivateKey) -> Option<BigUint> {
+        if key.modulus() != self.n {
+            return None;
+        }
+        Some(BigUint::from(2u32).modpow(&self.difficulty, &key.phi_half()))
+    }
+
+    /// Open a puzzle with an exponent from `precompute_solve_exponent`, in a single modpow.
+    ///
+    /// An exponent precomputed for another instance or difficulty yields a wrong solution or an error.
+    pub fn solve_fast(&self, puzzle: Puzzle, exponent: &BigUint) -> Result<BigUint, LhtlpError> {
+        let w = puzzle.u.modpow(exponent, &self.n);
+        self.try_decode(&puzzle.v, &w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::fixed_instance;
+
+    const DIFFICULTY: u64 = 1000;
+
+    #[test]
+    fn precomputed_exponent_matches_solve() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(DIFFICULTY));
+        let exponent = lhtlp.precompute_solve_exponent(&key).unwrap();
+        for secret in [0u64, 1, 42, u64::MAX] {
+            let puzzle = lhtlp.generate(secret);
+            assert!(lhtlp.solve_fast(puzzle.clone(), &exponent) == Ok(BigUint::from(secret)));
+            assert!(lhtlp.solve(puzzle) == BigUint::from(secret));
+        }
+
+        let other = PrivateKey::new(BigUint::from(1019u32), key.q().clone());
+        assert!(lhtlp.precompute_solve_exponent(&other).is_none());
+    }
+
+    #[test]
+    fn aggregate_plaintext_matches_solve() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(DIFFICULTY));
+        let puzzles: Vec<Puzzle> = [3u64, u64::MAX, 0, 17].iter().map(|&s| lhtlp.generate(s)).collect();
+        let expected = lhtlp.solve(lhtlp.evaluate(puzzles.clone()));
+        assert!(lhtlp.aggregate_plaintext_with_trapdoor(&puzzles, &key) == Ok(expected));
+        assert!(lhtlp.aggregate_plaintext_with_trapdoor(&[], &key) == Ok(BigUint::from(0u32)));
+    }
+
+    #[test]
+    fn group_order() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(DIFFICULTY));
+        let exact = key.exact_group_order(&lhtlp);
+        assert!(exact == 2u32 * key.phi_half());
+
+        let approx = lhtlp.approx_group_order();
+        assert!(&approx + 1u32 >= exact);
+        let distance = if approx > exact { &approx - &exact } else { &exact - &approx };
+        assert!(distance.bits() < lhtlp.n.bits() / 2 - 4);
+    }
+
+    #[test]
+    fn puzzle_entropy() {
+        for lambda in [32u64, 64, 128] {
+            let lhtlp = LHTLP::setup(lambda, DIFFICULTY);
+            let entropy = lhtlp.puzzle_entropy_bits();
+            assert!(entropy >= lambda as f64 / 2.0);
+            assert!(entropy < lhtlp.n.bits() as f64);
+        }
+    }
+}
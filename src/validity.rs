@@ -0,0 +1,62 @@
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+
+impl LHTLP {
+    /// Check that `puzzle` is well-formed for this instance.
+    ///
+    /// Requires `0 < u < n` and `0 < v < n^2`, with both components coprime to `n`. Out-of-range
+    /// components indicate a malformed or foreign puzzle, rejected with `LhtlpError::InvalidPuzzle`
+    /// before any squaring is spent on it.
+    pub fn verify_puzzle(&self, puzzle: &Puzzle) -> Result<(), LhtlpError> {
+        let zero = BigUint::from(0u32);
+        let one = BigUint::from(1u32);
+        let n2 = &self.n2;
+        if puzzle.u == zero || puzzle.u >= self.n || puzzle.v == zero || puzzle.v >= *n2 {
+            return Err(LhtlpError::InvalidPuzzle);
+        }
+        if puzzle.u.gcd(&self.n) != one || puzzle.v.gcd(&self.n) != one {
+            return Err(LhtlpError::InvalidPuzzle);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn component_ranges() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let puzzle = lhtlp.generate(42);
+        let n2 = &lhtlp.n2;
+        assert!(lhtlp.verify_puzzle(&puzzle).is_ok());
+
+        let malformed = [
+            Puzzle { u: BigUint::from(0u32), v: puzzle.v.clone() },
+            Puzzle { u: puzzle.u.clone(), v: BigUint::from(0u32) },
+            Puzzle { u: &puzzle.u + &lhtlp.n, v: puzzle.v.clone() },
+            Puzzle { u: puzzle.u.clone(), v: &puzzle.v + n2 },
+            Puzzle { u: lhtlp.n.clone(), v: puzzle.v.clone() },
+            Puzzle { u: puzzle.u.clone(), v: n2.clone() },
+        ];
+        for puzzle in malformed {
+            assert!(lhtlp.verify_puzzle(&puzzle) == Err(LhtlpError::InvalidPuzzle));
+            assert!(lhtlp.solve_checked(puzzle) == Err(LhtlpError::InvalidPuzzle));
+        }
+    }
+
+    #[test]
+    fn generated_puzzles_are_valid() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let secret = rand::Rng::gen::<u64>(&mut rng);
+            assert!(lhtlp.verify_puzzle(&lhtlp.generate(secret)).is_ok());
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::num_primes::Verification;
+use crate::{HashSuite, LhtlpError, PrivateKey, Puzzle, LHTLP, SQUARING_CHUNK};
+use num_bigint::BigUint;
+use num_integer::Integer;
+
+/// Size in bits of the Fiat-Shamir challenge prime of the Wesolowski proof.
+const CHALLENGE_PRIME_BITS: usize = 128;
+
+/// A verifiable delay function over the RSA group of an LHTLP instance.
+///
+/// Evaluating the VDF on a challenge `x` computes `y = x^(2^T) mod n` by `T` sequential squarings,
+/// exactly like opening a puzzle. A Wesolowski proof `pi = x^floor(2^T / l)`, with `l` a prime
+/// derived from `(x, y, T)`, lets anyone check `pi^l * x^(2^T mod l) = y` with two short
+/// exponentiations.
+///
+/// The modulus must come from a trusted setup: whoever knows its factorization can evaluate the
+/// VDF without doing the squarings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vdf {
+    n: BigUint,
+    hash_suite: HashSuite,
+}
+
+/// Expand `parts` with `hash_suite` in counter mode into at least `bits` bits.
+pub(crate) fn expand(hash_suite: HashSuite, parts: &[&[u8]], bits: usize) -> BigUint {
+    let mut data = Vec::new();
+    for part in parts {
+        data.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        data.extend_from_slice(part);
+    }
+    let prefix = data.len();
+    let mut bytes = Vec::new();
+    let mut counter = 0u64;
+    while bytes.len() * 8 < bits {
+        data.truncate(prefix);
+        data.extend_from_slice(&counter.to_be_bytes());
+        bytes.extend_from_slice(&hash_suite.digest(&data));
+        counter += 1;
+    }
+    BigUint::from_bytes_be(&bytes)
+}
+
+/// Fiat-Shamir prime for a Wesolowski proof of `y = x^(2^difficulty) mod n`.
+pub(crate) fn challenge_prime(hash_suite: HashSuite, n: &BigUint, x: &BigUint, y: &BigUint, difficulty: u64) -> BigUint {
+    let mut counter = 0u64;
+    loop {
+        let candidate = expand(
+            hash_suite,
+            &[b"lhtlp-wesolowski-prime", &n.to_bytes_be(), &x.to_bytes_be(), &y.to_bytes_be(), &difficulty.to_be_bytes(), &counter.to_be_bytes()],
+            CHALLENGE_PRIME_BITS,
+        );
+        let mut candidate = candidate % (BigUint::from(1u32) << CHALLENGE_PRIME_BITS);
+        candidate.set_bit(CHALLENGE_PRIME_BITS as u64 - 1, true);
+        candidate.set_bit(0, true);
+        if Verification::is_prime(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Compute `y = x^(2^difficulty) mod n` and the Wesolowski proof of it.
+///
+/// `y` is computed by the squaring routine of puzzles. The proof `x^floor(2^difficulty / l)` is
+/// then computed by long division of `2^difficulty` by `l` one bit at a time, which costs another
+/// `difficulty` squarings.
+pub(crate) fn prove(hash_suite: HashSuite, n: &BigUint, x: &BigUint, difficulty: u64) -> (BigUint, BigUint) {
+    let y = LHTLP::square_in_chunks(x, &BigUint::from(difficulty), n, SQUARING_CHUNK, &mut 0);
+    let l = challenge_prime(hash_suite, n, x, &y, difficulty);
+
+    let mut pi = BigUint::from(1u32);
+    let mut r = BigUint::from(1u32);
+    for _ in 0..difficulty {
+        let doubled = &r << 1usize;
+        let (bit, rest) = doubled.div_rem(&l);
+        r = rest;
+        pi = &pi * &pi % n;
+        if bit == BigUint::from(1u32) {
+            pi = pi * x % n;
+        }
+    }
+    (y, pi)
+}
+
+/// Check a Wesolowski proof that `y = x^(2^difficulty) mod n`.
+pub(crate) fn verify(hash_suite: HashSuite, n: &BigUint, x: &BigUint, y: &BigUint, pi: &BigUint, difficulty: u64) -> bool {
+    if y >= n || pi >= n {
+        return false;
+    }
+    let l = challenge_prime(hash_suite, n, x, y, difficulty);
+    let r = BigUint::from(2u32).modpow(&BigUint::from(difficulty), &l);
+    (pi.modpow(&l, n) * x.modpow(&r, n)) % n == *y
+}
+
+impl Vdf {
+    /// A VDF sharing the modulus and the hash suite of `lhtlp`.
+    pub fn new(lhtlp: &LHTLP) -> Vdf {
+        Vdf { n: lhtlp.n.clone(), hash_suite: lhtlp.hash_suite }
+    }
+
+    /// A VDF over an arbitrary RSA modulus `n`, hashing with the default suite.
+    pub fn from_modulus(n: BigUint) -> Vdf {
+        Vdf { n, hash_suite: HashSuite::default() }
+    }
+
+    /// Select the hash suite deriving the inputs and the challenge primes.
+    pub fn with_hash_suite(mut self, hash_suite: HashSuite) -> Vdf {
+        self.hash_suite = hash_suite;
+        self
+    }
+
+    /// Map a `challenge` to a quadratic residue modulo `n`.
+    fn input(&self, challenge: &[u8]) -> BigUint {
+        let x = expand(self.hash_suite, &[b"lhtlp-vdf-input", challenge], 2 * self.n.bits() as usize) % &self.n;
+        &x * &x % &self.n
+    }
+
+    /// Evaluate the VDF on `challenge`, returning the output and its proof.
+    #[must_use]
+    pub fn eval(&self, challenge: &[u8], difficulty: u64) -> (BigUint, BigUint) {
+        prove(self.hash_suite, &self.n, &self.input(challenge), difficulty)
+    }
+
+    /// Check that `output` is the VDF evaluation of `challenge` for `difficulty`, given its `proof`.
+    pub fn verify(&self, challenge: &[u8], output: &BigUint, proof: &BigUint, difficulty: u64) -> bool {
+        verify(self.hash_suite, &self.n, &self.input(challenge), output, proof, difficulty)
+    }
+}
+
+/// A Wesolowski proof that `w = u^(2^T) mod n` for the `u` of a puzzle, from which the solution
+/// decodes.
+///
+/// `pi = u^floor(2^T / l) mod n`, with `l` the Fiat-Shamir prime derived from `(n, u, w, T)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WesolowskiProof {
+    pub w: BigUint,
+    pub pi: BigUint,
+    pub l: BigUint,
+}
+
+impl LHTLP {
+    /// Open a puzzle with the trapdoor and prove the solution with a Wesolowski proof.
+    ///
+    /// With `phi(n) / 2` both `w` and `pi` take a single exponentiation each, instead of the `2 T`
+    /// squarings of `Vdf::eval`. Fails with `LhtlpError::ParameterMismatch` if `key` is not the
+    /// factorization of `n`, and with `LhtlpError::DifficultyTooLarge` if the difficulty does not
+    /// fit in `u64`.
+    pub fn solve_with_wesolowski_proof(&self, key: &PrivateKey, puzzle: &Puzzle) -> Result<(BigUint, WesolowskiProof), LhtlpError> {
+        let difficulty = self.try_squarings()?;
+        let exponent = self.precompute_solve_exponent(key).ok_or(LhtlpError::ParameterMismatch)?;
+        let order = key.phi_half();
+        let w = puzzle.u.modpow(&exponent, &self.n);
+        let l = challenge_prime(self.hash_suite, &self.n, &puzzle.u, &w, difficulty);
+
+        // floor(2^T / l) = (2^T - (2^T mod l)) / l, reduced modulo phi(n) / 2, a multiple of the
+        // order of u. l is a large prime, so it is invertible modulo phi(n) / 2.
+        let remainder = BigUint::from(2u32).modpow(&self.difficulty, &l);
+        let l_inverse = l.modinv(&order).ok_or(LhtlpError::ParameterMismatch)?;
+        let quotient = (&exponent + &order - &remainder % &order) % &order * l_inverse % &order;
+        let pi = puzzle.u.modpow(&quotient, &self.n);
+
+        let solution = self.try_decode(&puzzle.v, &w)?;
+        Ok((solution, WesolowskiProof { w, pi, l }))
+    }
+
+    /// Check that `solution` opens `puzzle`, given a Wesolowski `proof`, in `O(log T)` time.
+    ///
+    /// Proofs are only defined for difficulties fitting in `u64`; above that the check fails.
+    pub fn verify_wesolowski_proof(&self, puzzle: &Puzzle, solution: BigUint, proof: &WesolowskiProof) -> bool {
+        let Ok(difficulty) = self.try_squarings() else { return false };
+        if proof.l != challenge_prime(self.hash_suite, &self.n, &puzzle.u, &proof.w, difficulty) {
+            return false;
+        }
+        verify(self.hash_suite, &self.n, &puzzle.u, &proof.w, &proof.pi, difficulty) && self.try_decode(&puzzle.v, &proof.w) == Ok(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::fixed_instance;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    #[test]
+    fn eval_and_verify() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let vdf = Vdf::new(&lhtlp);
+        let (output, proof) = vdf.eval(b"challenge", DIFFICULTY);
+
+        assert!(vdf.verify(b"challenge", &output, &proof, DIFFICULTY));
+        assert!(!vdf.verify(b"challenge", &output, &proof, DIFFICULTY + 1));
+        assert!(!vdf.verify(b"another", &output, &proof, DIFFICULTY));
+        assert!(!vdf.verify(b"challenge", &(&output + 1u32), &proof, DIFFICULTY));
+        assert!(!vdf.verify(b"challenge", &output, &(&proof + 1u32), DIFFICULTY));
+
+        let sha384 = Vdf::new(&lhtlp.clone().with_hash_suite(HashSuite::Sha384));
+        assert!(sha384 == vdf.clone().with_hash_suite(HashSuite::Sha384));
+        assert!(!sha384.verify(b"challenge", &output, &proof, DIFFICULTY));
+        let (output, proof) = sha384.eval(b"challenge", DIFFICULTY);
+        assert!(sha384.verify(b"challenge", &output, &proof, DIFFICULTY));
+    }
+
+    #[test]
+    fn output_matches_squaring() {
+        let lhtlp = LHTLP::setup(LAMBDA, BigUint::from(DIFFICULTY));
+        let vdf = Vdf::new(&lhtlp);
+        let (output, _) = vdf.eval(b"challenge", DIFFICULTY);
+        let x = vdf.input(b"challenge");
+
+        assert!(output == x.modpow(&(BigUint::from(1u32) << DIFFICULTY as usize), &lhtlp.n));
+    }
+
+    #[test]
+    fn wesolowski_solve_proofs() {
+        let (lhtlp, key) = fixed_instance(BigUint::from(DIFFICULTY));
+        let puzzle = lhtlp.generate(42);
+
+        let (solution, proof) = lhtlp.solve_with_wesolowski_proof(&key, &puzzle).unwrap();
+        assert!(solution == BigUint::from(42u32));
+        assert!(proof.w == lhtlp.square(&puzzle.u, &lhtlp.difficulty));
+        assert!(lhtlp.verify_wesolowski_proof(&puzzle, solution, &proof));
+        // The proof matches the one computed by squaring, without the trapdoor.
+        assert!(prove(lhtlp.hash_suite, &lhtlp.n, &puzzle.u, DIFFICULTY) == (proof.w.clone(), proof.pi.clone()));
+
+        assert!(!lhtlp.verify_wesolowski_proof(&puzzle, BigUint::from(43u32), &proof));
+        assert!(!lhtlp.verify_wesolowski_proof(&lhtlp.generate(42), BigUint::from(42u32), &proof));
+        let forged = WesolowskiProof { pi: &proof.pi * &proof.pi % &lhtlp.n, ..proof.clone() };
+        assert!(!lhtlp.verify_wesolowski_proof(&puzzle, BigUint::from(42u32), &forged));
+        let forged = WesolowskiProof { l: &proof.l + 2u32, ..proof.clone() };
+        assert!(!lhtlp.verify_wesolowski_proof(&puzzle, BigUint::from(42u32), &forged));
+
+        let other = PrivateKey::new(BigUint::from(1019u32), key.q().clone());
+        assert!(lhtlp.solve_with_wesolowski_proof(&other, &puzzle) == Err(LhtlpError::ParameterMismatch));
+
+        let lhtlp = LHTLP { difficulty: BigUint::from(u64::MAX) + 1u32, ..lhtlp };
+        assert!(!lhtlp.verify_wesolowski_proof(&puzzle, BigUint::from(42u32), &proof));
+        assert!(matches!(lhtlp.solve_with_wesolowski_proof(&key, &puzzle), Err(LhtlpError::DifficultyTooLarge { .. })));
+    }
+}
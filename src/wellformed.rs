@@ -0,0 +1,331 @@
+use crate::num_primes::RandBigInt;
+use crate::{LhtlpError, Puzzle, LHTLP};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use rand::Rng;
+
+/// Size in bits of the Fiat-Shamir challenge of a well-formedness proof.
+const CHALLENGE_BITS: u64 = 128;
+/// Extra bits of the prover masks, hiding the challenge times the witness statistically.
+const MASK_SLACK_BITS: u64 = 2 * CHALLENGE_BITS;
+
+/// Non-interactive proof that a puzzle was generated honestly, i.e. that the prover knows `r` and
+/// `s` with `u = g^r mod n` and `v = h^(r*n) * (1+n)^s mod n^2`.
+///
+/// It is a Fiat-Shamir Schnorr-style proof over integers: with commitments `a = g^k_r`,
+/// `b = h^(k_r*n) * (1+n)^k_s` and challenge `c`, the responses are `z_r = k_r + c*r` and
+/// `z_s = k_s + c*s mod n`. The verifier checks the squares of `g^z_r = a * u^c mod n` and
+/// `h^(z_r*n) * (1+n)^z_s = b * v^c mod n^2`.
+///
+/// A prover can always slip a factor of order 2, such as `-1`, into `u` or `v` and pass an
+/// unsquared check for half of the challenges. Squaring makes such factors irrelevant to the
+/// verifier, and solving decodes `v` through its square as well, so a puzzle with a valid proof
+/// decodes to the proven secret either way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WellFormednessProof {
+    pub a: BigUint,
+    pub b: BigUint,
+    pub z_r: BigUint,
+    pub z_s: BigUint,
+}
+
+/// Left-to-right square-and-multiply, counting every step in `ops`.
+fn pow_counted(base: &BigUint, exponent: &BigUint, modulus: &BigUint, ops: &mut u64) -> BigUint {
+    multi_pow_counted(&[(base, exponent.clone())], modulus, ops)
+}
+
+/// Product of `base^exponent` over `terms`, sharing one squaring chain, counting every step in `ops`.
+fn multi_pow_counted(terms: &[(&BigUint, BigUint)], modulus: &BigUint, ops: &mut u64) -> BigUint {
+    let bits = terms.iter().map(|(_, exponent)| exponent.bits()).max().unwrap_or(0);
+    let mut result = BigUint::from(1u32) % modulus;
+    for i in (0..bits).rev() {
+        result = &result * &result % modulus;
+        *ops += 1;
+        for (base, exponent) in terms {
+            if exponent.bit(i) {
+                result = result * *base % modulus;
+                *ops += 1;
+            }
+        }
+    }
+    result
+}
+
+fn square(value: &BigUint, modulus: &BigUint) -> BigUint {
+    value * value % modulus
+}
+
+impl LHTLP {
+    fn well_formedness_challenge(&self, puzzle: &Puzzle, a: &BigUint, b: &BigUint) -> BigUint {
+        let mut data = self.fingerprint();
+        for value in [&puzzle.u, &puzzle.v, a, b] {
+            let bytes = value.to_bytes_be();
+            data.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+            data.extend_from_slice(&bytes);
+        }
+        BigUint::from_bytes_be(&self.hash_suite.digest(&data)[..CHALLENGE_BITS as usize / 8])
+    }
+
+    /// Generate a puzzle of `secret` together with a proof that it is well-formed.
+    #[must_use]
+    pub fn generate_with_proof(&self, secret: u64) -> (Puzzle, WellFormednessProof) {
+        let mut rng = rand::thread_rng();
+        let secret = BigUint::from(secret);
+        let r = self.sample_randomness(&mut rng);
+        let puzzle = self.generate_with_randomness(&secret, &r);
+        let proof = self.prove_well_formed(&puzzle, &r, &secret, &mut rng);
+        (puzzle, proof)
+    }
+
+    /// Proof that `puzzle` was generated from randomness `r` and `secret`.
+    fn prove_well_formed<R: Rng + ?Sized>(&self, puzzle: &Puzzle, r: &BigUint, secret: &BigUint, rng: &mut R) -> WellFormednessProof {
+        let n2 = &self.n2;
+        let k_r = rng.gen_biguint(n2.bits() + CHALLENGE_BITS + MASK_SLACK_BITS);
+        let k_s = rng.gen_biguint_below(&self.n);
+        let a = self.g.modpow(&k_r, &self.n);
+        let b = self.h.modpow(&(&k_r * &self.n), n2) * self.plaintext_power(&k_s, n2) % n2;
+        let c = self.well_formedness_challenge(puzzle, &a, &b);
+
+        let z_r = k_r + &c * r;
+        let z_s = (k_s + c * secret) % &self.n;
+        WellFormednessProof { a, b, z_r, z_s }
+    }
+
+    /// Range checks on a proof and its puzzle, done before any exponentiation.
+    fn proof_in_range(&self, puzzle: &Puzzle, proof: &WellFormednessProof) -> bool {
+        let n2 = &self.n2;
+        self.verify_puzzle(puzzle).is_ok()
+            && proof.a < self.n
+            && proof.a.gcd(&self.n) == BigUint::from(1u32)
+            && proof.b < *n2
+            && proof.b.gcd(&self.n) == BigUint::from(1u32)
+            && proof.z_s < self.n
+    }
+
+    fn verify_well_formed_counted(&self, puzzle: &Puzzle, proof: &WellFormednessProof, ops: &mut u64) -> bool {
+        if !self.proof_in_range(puzzle, proof) {
+            return false;
+        }
+        let n2 = &self.n2;
+        let c = self.well_formedness_challenge(puzzle, &proof.a, &proof.b);
+        let lhs_u = pow_counted(&self.g, &proof.z_r, &self.n, ops);
+        let rhs_u = pow_counted(&puzzle.u, &c, &self.n, ops) * &proof.a % &self.n;
+        let lhs_v = pow_counted(&self.h, &(&proof.z_r * &self.n), n2, ops) * self.plaintext_power(&proof.z_s, n2) % n2;
+        let rhs_v = pow_counted(&puzzle.v, &c, n2, ops) * &proof.b % n2;
+        *ops += 7;
+        square(&lhs_u, &self.n) == square(&rhs_u, &self.n) && square(&lhs_v, n2) == square(&rhs_v, n2)
+    }
+
+    /// Check a proof that `puzzle` is well-formed.
+    pub fn verify_well_formed(&self, puzzle: &Puzzle, proof: &WellFormednessProof) -> bool {
+        self.verify_well_formed_counted(puzzle, proof, &mut 0)
+    }
+
+    /// Combined check of all the proofs of `items`, with each equation raised to a random 64-bit exponent.
+    ///
+    /// Both sides reduce to one long exponentiation of `g` and `h` and a multi-exponentiation with
+    /// short exponents. As in the single check, both sides are squared: with safe primes the only
+    /// elements of small order have order 2, and without squaring two errors of `-1` would cancel
+    /// under the odd exponents. If any proof is invalid, so would the combined check be except with
+    /// negligible probability.
+    fn verify_combined(&self, items: &[(Puzzle, WellFormednessProof)], ops: &mut u64) -> bool {
+        if !items.iter().all(|(puzzle, proof)| self.proof_in_range(puzzle, proof)) {
+            return false;
+        }
+        let mut rng = rand::thread_rng();
+        let n2 = &self.n2;
+        let mut z_r = BigUint::from(0u32);
+        let mut z_s = BigUint::from(0u32);
+        let mut terms_u = Vec::with_capacity(2 * items.len());
+        let mut terms_v = Vec::with_capacity(2 * items.len());
+        for (puzzle, proof) in items {
+            let rho = BigUint::from(rng.gen::<u64>() | 1);
+            let c = self.well_formedness_challenge(puzzle, &proof.a, &proof.b);
+            z_r += &rho * &proof.z_r;
+            z_s += &rho * &proof.z_s;
+            terms_u.push((&proof.a, rho.clone()));
+            terms_u.push((&puzzle.u, &rho * &c));
+            terms_v.push((&proof.b, rho.clone()));
+            terms_v.push((&puzzle.v, rho * c));
+        }
+
+        let lhs_u = pow_counted(&self.g, &z_r, &self.n, ops);
+        let rhs_u = multi_pow_counted(&terms_u, &self.n, ops);
+        let lhs_v = pow_counted(&self.h, &(&z_r * &self.n), n2, ops) * self.plaintext_power(&z_s, n2) % n2;
+        let rhs_v = multi_pow_counted(&terms_v, n2, ops);
+        *ops += 5;
+        square(&lhs_u, &self.n) == square(&rhs_u, &self.n) && square(&lhs_v, n2) == square(&rhs_v, n2)
+    }
+
+    /// Indices, offset by `start`, of the invalid proofs in `items`, found by bisection.
+    ///
+    /// Stops at the first invalid index unless `exhaustive`.
+    fn bisect_invalid(&self, items: &[(Puzzle, WellFormednessProof)], start: usize, exhaustive: bool, invalid: &mut Vec<usize>, ops: &mut u64) {
+        if items.len() == 1 {
+            if !self.verify_well_formed_counted(&items[0].0, &items[0].1, ops) {
+                invalid.push(start);
+            }
+            return;
+        }
+        let (left, right) = items.split_at(items.len() / 2);
+        for (half, offset) in [(left, start), (right, start + left.len())] {
+            if !exhaustive && !invalid.is_empty() {
+                return;
+            }
+            if !self.verify_combined(half, ops) {
+                self.bisect_invalid(half, offset, exhaustive, invalid, ops);
+            }
+        }
+    }
+
+    fn verify_well_formed_batch_counted(
+        &self,
+        items: &[(Puzzle, WellFormednessProof)],
+        exhaustive: bool,
+        ops: &mut u64,
+    ) -> Result<(), LhtlpError> {
+        if items.is_empty() || self.verify_combined(items, ops) {
+            return Ok(());
+        }
+        let mut invalid = Vec::new();
+        self.bisect_invalid(items, 0, exhaustive, &mut invalid, ops);
+        Err(LhtlpError::InvalidProofs { indices: invalid })
+    }
+
+    /// Check many well-formedness proofs at once.
+    ///
+    /// All equations are combined into a single check. When it fails, the batch is bisected to
+    /// locate the invalid proofs, reported in `LhtlpError::InvalidProofs`: only the first one, or
+    /// all of them if `exhaustive`.
+    pub fn verify_well_formed_batch(&self, items: &[(Puzzle, WellFormednessProof)], exhaustive: bool) -> Result<(), LhtlpError> {
+        self.verify_well_formed_batch_counted(items, exhaustive, &mut 0)
+    }
+
+    /// Check a single proof like `verify_well_formed`, counting the operations performed.
+    #[cfg(feature = "metrics")]
+    pub fn verify_well_formed_with_metrics(&self, puzzle: &Puzzle, proof: &WellFormednessProof) -> (bool, crate::Metrics) {
+        let mut multiplications = 0;
+        let valid = self.verify_well_formed_counted(puzzle, proof, &mut multiplications);
+        (valid, crate::Metrics { multiplications, ..crate::Metrics::default() })
+    }
+
+    /// Check proofs like `verify_well_formed_batch`, counting the operations performed.
+    #[cfg(feature = "metrics")]
+    pub fn verify_well_formed_batch_with_metrics(
+        &self,
+        items: &[(Puzzle, WellFormednessProof)],
+        exhaustive: bool,
+    ) -> (Result<(), LhtlpError>, crate::Metrics) {
+        let mut multiplications = 0;
+        let result = self.verify_well_formed_batch_counted(items, exhaustive, &mut multiplications);
+        (result, crate::Metrics { multiplications, ..crate::Metrics::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTY: u64 = 1000;
+    const LAMBDA: u64 = 64;
+
+    fn batch(lhtlp: &LHTLP, size: u64) -> Vec<(Puzzle, WellFormednessProof)> {
+        (0..size).map(|s| lhtlp.generate_with_proof(s)).collect()
+    }
+
+    #[test]
+    fn single_proof() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let (puzzle, proof) = lhtlp.generate_with_proof(42);
+        assert!(lhtlp.verify_well_formed(&puzzle, &proof));
+        assert!(lhtlp.solve(puzzle.clone()) == BigUint::from(42u32));
+
+        let other = lhtlp.generate(42);
+        assert!(!lhtlp.verify_well_formed(&other, &proof));
+        let mut forged = proof.clone();
+        forged.z_s = (&forged.z_s + 1u32) % &lhtlp.n;
+        assert!(!lhtlp.verify_well_formed(&puzzle, &forged));
+    }
+
+    #[test]
+    fn valid_batch() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        assert!(lhtlp.verify_well_formed_batch(&batch(&lhtlp, 32), false) == Ok(()));
+        assert!(lhtlp.verify_well_formed_batch(&[], false) == Ok(()));
+    }
+
+    #[test]
+    fn corrupted_proofs_are_located() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut items = batch(&lhtlp, 16);
+        items[11].1.z_r += 1u32;
+        assert!(lhtlp.verify_well_formed_batch(&items, false) == Err(LhtlpError::InvalidProofs { indices: vec![11] }));
+
+        items[4].0 = lhtlp.generate(4);
+        assert!(lhtlp.verify_well_formed_batch(&items, false) == Err(LhtlpError::InvalidProofs { indices: vec![4] }));
+        assert!(lhtlp.verify_well_formed_batch(&items, true) == Err(LhtlpError::InvalidProofs { indices: vec![4, 11] }));
+    }
+
+    #[test]
+    fn negated_v_decodes_to_the_proven_secret() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut rng = rand::thread_rng();
+        let secret = BigUint::from(42u32);
+        let r = lhtlp.sample_randomness(&mut rng);
+        let mut puzzle = lhtlp.generate_with_randomness(&secret, &r);
+        puzzle.v = &lhtlp.n2 - &puzzle.v;
+        // An unsquared check accepted this puzzle exactly for the even challenges.
+        let proof = loop {
+            let proof = lhtlp.prove_well_formed(&puzzle, &r, &secret, &mut rng);
+            if lhtlp.well_formedness_challenge(&puzzle, &proof.a, &proof.b).is_even() {
+                break proof;
+            }
+        };
+        assert!(lhtlp.verify_well_formed(&puzzle, &proof));
+        assert!(lhtlp.solve(puzzle.clone()) == secret);
+        let sum = lhtlp.evaluate(vec![puzzle, lhtlp.generate(8)]);
+        assert!(lhtlp.solve(sum) == BigUint::from(50u32));
+    }
+
+    #[test]
+    fn negated_commitments_agree_between_single_and_batch() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let mut rng = rand::thread_rng();
+        let n2 = &lhtlp.n2;
+        let mut items = batch(&lhtlp, 4);
+        for index in [1, 3] {
+            let secret = BigUint::from(index as u32);
+            let r = lhtlp.sample_randomness(&mut rng);
+            let puzzle = lhtlp.generate_with_randomness(&secret, &r);
+            let k_r = rng.gen_biguint(n2.bits() + CHALLENGE_BITS + MASK_SLACK_BITS);
+            let k_s = rng.gen_biguint_below(&lhtlp.n);
+            let a = &lhtlp.n - lhtlp.g.modpow(&k_r, &lhtlp.n);
+            let b = lhtlp.h.modpow(&(&k_r * &lhtlp.n), n2) * lhtlp.plaintext_power(&k_s, n2) % n2;
+            let c = lhtlp.well_formedness_challenge(&puzzle, &a, &b);
+            let z_r = k_r + &c * r;
+            let z_s = (k_s + c * secret) % &lhtlp.n;
+            items[index] = (puzzle, WellFormednessProof { a, b, z_r, z_s });
+        }
+        // Two negated commitments cancel under the odd batch exponents, so the combined check
+        // must accept them only if the single check does, which it does once squared.
+        for (puzzle, proof) in &items {
+            assert!(lhtlp.verify_well_formed(puzzle, proof));
+        }
+        assert!(lhtlp.verify_well_formed_batch(&items, true) == Ok(()));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn batch_cost() {
+        let lhtlp = LHTLP::setup(LAMBDA, DIFFICULTY);
+        let items = batch(&lhtlp, 32);
+        let (valid, single) = lhtlp.verify_well_formed_with_metrics(&items[0].0, &items[0].1);
+        assert!(valid);
+        let (result, combined) = lhtlp.verify_well_formed_batch_with_metrics(&items, false);
+        assert!(result == Ok(()));
+        // One by one, 32 proofs would cost 32 times a single verification. The combined check
+        // shares the long exponentiations of g and h, leaving only the short per-proof exponents.
+        assert!(combined.multiplications < 8 * single.multiplications);
+    }
+}
@@ -0,0 +1,77 @@
+//! Allocation counts, measured per thread by a counting allocator.
+use lhtlp::{Puzzle, LHTLP};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static LIVE_BYTES: Cell<i64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        LIVE_BYTES.with(|live| live.set(live.get() + layout.size() as i64));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.with(|live| live.set(live.get() - layout.size() as i64));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, u64) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[test]
+fn borrowed_validation_does_not_allocate() {
+    let lhtlp = LHTLP::setup(64, 1000u64);
+    let encodings: Vec<Vec<u8>> = (0..100).map(|s| lhtlp.generate(s).to_bytes()).collect();
+
+    let (valid, borrowed) = allocations(|| {
+        encodings.iter().all(|bytes| {
+            lhtlp.verify_puzzle_bytes(bytes).map(|puzzle| puzzle.fingerprint()).is_ok()
+        })
+    });
+    assert!(valid);
+    assert!(borrowed == 0);
+
+    let (valid, owned) = allocations(|| {
+        encodings.iter().all(|bytes| {
+            Puzzle::from_bytes(bytes).is_ok_and(|puzzle| lhtlp.verify_puzzle(&puzzle).is_ok() && puzzle.fingerprint() != [0; 32])
+        })
+    });
+    assert!(valid);
+    assert!(owned >= 2 * encodings.len() as u64);
+}
+
+/// Soak test for long-running services: memory held after a generate/solve cycle must not grow.
+///
+/// Run with `cargo test --release --test allocations -- --ignored`.
+#[test]
+#[ignore]
+fn no_memory_growth_across_solves() {
+    let lhtlp = LHTLP::setup(64, 10u64);
+    let cycle = |secret: u64| {
+        let puzzles = vec![lhtlp.generate(secret), lhtlp.generate(1)];
+        assert!(lhtlp.solve(lhtlp.evaluate(puzzles)) == (secret + 1).into());
+    };
+    for secret in 0..100 {
+        cycle(secret);
+    }
+
+    let baseline = LIVE_BYTES.with(Cell::get);
+    for secret in 0..5000 {
+        cycle(secret);
+    }
+    assert!(LIVE_BYTES.with(Cell::get) == baseline);
+}
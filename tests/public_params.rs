@@ -0,0 +1,43 @@
+use lhtlp::{LhtlpError, PublicParams, LHTLP};
+use num_bigint::BigUint;
+
+const DIFFICULTY: u64 = 1000;
+const LAMBDA: u64 = 64;
+
+#[test]
+fn reconstructed_instance_solves_original_puzzles() {
+    let original = LHTLP::setup(LAMBDA, DIFFICULTY);
+    let reconstructed = LHTLP::from_public_params(original.public_params(), DIFFICULTY).unwrap();
+    assert!(reconstructed == original);
+
+    let puzzle = original.evaluate(vec![original.generate(40), original.generate(2)]);
+    assert!(reconstructed.solve(puzzle) == BigUint::from(42u32));
+
+    let puzzle = reconstructed.generate(7);
+    assert!(original.solve(puzzle) == BigUint::from(7u32));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized_params_roundtrip() {
+    let original = LHTLP::setup(LAMBDA, DIFFICULTY);
+    let json = serde_json::to_string(&original.public_params()).unwrap();
+    let params: PublicParams = serde_json::from_str(&json).unwrap();
+    let reconstructed = LHTLP::from_public_params(params, DIFFICULTY).unwrap();
+
+    assert!(reconstructed.fingerprint() == original.fingerprint());
+    assert!(reconstructed.solve(original.generate(42)) == BigUint::from(42u32));
+}
+
+#[test]
+fn malformed_params_are_rejected() {
+    let original = LHTLP::setup(LAMBDA, DIFFICULTY);
+    let params = original.public_params();
+    let even = PublicParams { n: &params.n + 1u32, ..params.clone() };
+    let trivial = PublicParams { g: BigUint::from(1u32), ..params.clone() };
+    let out_of_range = PublicParams { h: params.n.clone(), ..params };
+
+    for params in [even, trivial, out_of_range] {
+        assert!(LHTLP::from_public_params(params, DIFFICULTY) == Err(LhtlpError::InconsistentParameters));
+    }
+}
@@ -0,0 +1,48 @@
+//! The workflows of `examples/`, run at a small difficulty.
+use num_bigint::BigUint;
+
+#[allow(dead_code)]
+#[path = "../examples/seal_and_open.rs"]
+mod seal_and_open;
+
+#[allow(dead_code)]
+#[path = "../examples/multi_party.rs"]
+mod multi_party;
+
+#[allow(dead_code)]
+#[path = "../examples/export_params.rs"]
+mod export_params;
+
+#[allow(dead_code)]
+#[path = "../examples/aggregation.rs"]
+mod aggregation;
+
+const DIFFICULTY: u64 = 1000;
+
+#[test]
+fn seal_and_force_open() {
+    assert!(seal_and_open::seal_and_open(DIFFICULTY, 42) == BigUint::from(42u32));
+    assert!(seal_and_open::seal_and_open(DIFFICULTY, u64::MAX) == BigUint::from(u64::MAX));
+}
+
+#[test]
+fn aggregate_then_open() {
+    let secrets = [BigUint::from(1u32), BigUint::from(u64::MAX), BigUint::from(1u32) << 100u32];
+    let expected: BigUint = secrets.iter().sum();
+    assert!(multi_party::aggregate_and_open(DIFFICULTY, &secrets) == expected);
+    assert!(multi_party::aggregate_and_open(DIFFICULTY, &[]) == BigUint::from(0u32));
+}
+
+#[test]
+fn export_then_import() {
+    assert!(export_params::export_and_import(DIFFICULTY, 40, 2) == BigUint::from(42u32));
+}
+
+#[test]
+fn weighted_voting() {
+    use aggregation::Ballot;
+
+    let ballots = [Ballot { weight: 2, choice: 1 }, Ballot { weight: 7, choice: 0 }, Ballot { weight: 1, choice: 1 }];
+    assert!(aggregation::weighted_tally(DIFFICULTY, 3, &ballots) == Ok([7u32, 3, 0].map(BigUint::from).to_vec()));
+    assert!(aggregation::weighted_tally(DIFFICULTY, 2, &[]) == Ok([0u32, 0].map(BigUint::from).to_vec()));
+}